@@ -0,0 +1,115 @@
+//! Ray–surface query throughput with and without the BVH as the surface count
+//! grows, for scattered spheres and for a mixed chamber scene
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use glam::{Quat, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use lithos::bvh::Bvh;
+use lithos::optics::{SagProfile, SurfaceGeometry};
+use lithos::units::{Distance, Position3D};
+
+const RAYS: usize = 1_000;
+
+/// Small spherical surfaces scattered through a 10 m chamber
+fn surfaces(count: usize, rng: &mut StdRng) -> Vec<SurfaceGeometry> {
+    (0..count)
+        .map(|_| SurfaceGeometry::Spherical {
+            radius: Distance::from_millimeters(rng.gen_range(50..300)),
+            center: random_point(rng),
+        })
+        .collect()
+}
+
+fn random_point(rng: &mut StdRng) -> Position3D {
+    Position3D::from_vec3(Vec3::new(rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0)))
+}
+
+/// A chamber scene: a 6 m wall enclosing spherical, aspheric and ellipsoidal
+/// mirrors with a few small sensor heads among them
+fn mixed_scene(count: usize, rng: &mut StdRng) -> Vec<SurfaceGeometry> {
+    let mut scene = vec![SurfaceGeometry::Spherical { radius: Distance::from_meters(6), center: Position3D::zero() }];
+    for i in 0..count {
+        scene.push(match i % 8 {
+            0..=2 => SurfaceGeometry::Spherical {
+                radius: Distance::from_millimeters(rng.gen_range(50..300)),
+                center: random_point(rng),
+            },
+            3 | 4 => SurfaceGeometry::Sag {
+                vertex: random_point(rng),
+                orientation: Quat::from_rotation_arc(Vec3::Z, random_direction(rng)),
+                profile: SagProfile::conic(rng.gen_range(1.0..4.0), rng.gen_range(-1.5..0.0), rng.gen_range(0.05..0.3)),
+            },
+            5 | 6 => {
+                let focus1 = random_point(rng);
+                let focus2 = Position3D::from_vec3(focus1.to_vec3() + random_direction(rng) * rng.gen_range(0.1..0.5));
+                SurfaceGeometry::ellipsoid_from_foci(focus1, focus2, 0.3).unwrap()
+            }
+            _ => SurfaceGeometry::Spherical { radius: Distance::from_millimeters(5), center: random_point(rng) },
+        });
+    }
+    scene
+}
+
+fn random_direction(rng: &mut StdRng) -> Vec3 {
+    Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)).normalize()
+}
+
+fn rays(count: usize, rng: &mut StdRng) -> Vec<Vec3> {
+    (0..count).map(|_| random_direction(rng)).collect()
+}
+
+fn nearest(surface: &SurfaceGeometry, direction: Vec3) -> f32 {
+    match surface.ray_intersection(Position3D::zero(), direction) {
+        (true, Some(_), t) => t,
+        _ => f32::INFINITY,
+    }
+}
+
+/// Brute-force and BVH queries for rays from the origin into scenes of growing size
+fn bench_scenes(c: &mut Criterion, name: &str, seed: u64, scene: fn(usize, &mut StdRng) -> Vec<SurfaceGeometry>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let directions = rays(RAYS, &mut rng);
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements(RAYS as u64));
+
+    for count in [4, 16, 64, 256, 1024] {
+        let surfaces = scene(count, &mut rng);
+        let bounds: Vec<_> = surfaces.iter().map(|s| s.bounds()).collect();
+        let bvh = Bvh::build(&bounds);
+
+        group.bench_with_input(BenchmarkId::new("brute_force", count), &surfaces, |b, surfaces| {
+            b.iter(|| {
+                for &direction in &directions {
+                    let t = surfaces.iter().map(|s| nearest(s, direction)).fold(f32::INFINITY, f32::min);
+                    black_box(t);
+                }
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("bvh", count), &surfaces, |b, surfaces| {
+            b.iter(|| {
+                for &direction in &directions {
+                    let mut t = f32::INFINITY;
+                    bvh.traverse(Vec3::ZERO, direction, f32::INFINITY, |i| {
+                        t = t.min(nearest(&surfaces[i], direction));
+                        t
+                    });
+                    black_box(t);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_ray_queries(c: &mut Criterion) {
+    bench_scenes(c, "ray_surface_queries", 42, surfaces);
+}
+
+fn bench_mixed_scene(c: &mut Criterion) {
+    bench_scenes(c, "mixed_scene_queries", 7, mixed_scene);
+}
+
+criterion_group!(benches, bench_ray_queries, bench_mixed_scene);
+criterion_main!(benches);
@@ -0,0 +1,273 @@
+//! `lithos analyze` command line: image-quality analysis of a prescription
+//!
+//! ```text
+//! lithos analyze wavefront [--prescription FILE] [--field X,Y]... [--grid N] [--terms N]
+//! lithos analyze spot [--prescription FILE] [--field X,Y]... [--half-field X,Y [--field-grid N]]
+//!                     [--grid N] [--max-frequency CYCLES_PER_MM] [--output DIR]
+//! ```
+//!
+//! The prescription defaults to `LITHOS_PRESCRIPTION` and must place the
+//! object, image and pupil. Field points are reticle coordinates in meters;
+//! `--half-field` adds an N × N grid (default 3) spanning ±X, ±Y, and without
+//! either the on-axis point is analysed.
+
+use glam::Vec2;
+use crate::prescription::Prescription;
+use crate::projection::ProjectionOptics;
+use crate::spot::{self, SpotDiagram};
+use crate::wavefront::{analyze_wavefront, WavefrontConfig};
+
+/// Options shared by the analysis commands
+struct Options {
+    prescription: Option<String>,
+    fields: Vec<Vec2>,
+    grid: Option<usize>,
+    terms: Option<u32>,
+    max_frequency: Option<f64>,
+    output: Option<String>,
+}
+
+fn parse_pair(text: &str) -> Result<Vec2, String> {
+    let parsed: Vec<f32> = text
+        .split(',')
+        .map(|v| v.trim().parse().map_err(|e| format!("bad coordinates '{text}': {e}")))
+        .collect::<Result<_, _>>()?;
+    match parsed[..] {
+        [x, y] => Ok(Vec2::new(x, y)),
+        _ => Err(format!("'{text}' needs two coordinates")),
+    }
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Options {
+            prescription: None,
+            fields: Vec::new(),
+            grid: None,
+            terms: None,
+            max_frequency: None,
+            output: None,
+        };
+        let mut half_field = None;
+        let mut field_grid = 3;
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{flag} needs a value"));
+            match flag.as_str() {
+                "--prescription" => options.prescription = Some(value()?.clone()),
+                "--field" => options.fields.push(parse_pair(value()?)?),
+                "--half-field" => half_field = Some(parse_pair(value()?)?),
+                "--field-grid" => field_grid = value()?.parse().map_err(|e| format!("bad field grid: {e}"))?,
+                "--grid" => options.grid = Some(value()?.parse().map_err(|e| format!("bad grid: {e}"))?),
+                "--terms" => options.terms = Some(value()?.parse().map_err(|e| format!("bad term count: {e}"))?),
+                "--max-frequency" => {
+                    let cycles_per_mm: f64 = value()?.parse().map_err(|e| format!("bad frequency: {e}"))?;
+                    options.max_frequency = Some(cycles_per_mm * 1e3);
+                }
+                "--output" => options.output = Some(value()?.clone()),
+                other => return Err(format!("unknown option '{other}'")),
+            }
+        }
+        if let Some(half_field) = half_field {
+            let steps: usize = field_grid.max(1);
+            let coordinate = |i: usize| if steps == 1 { 0.0 } else { 2.0 * i as f32 / (steps - 1) as f32 - 1.0 };
+            for i in 0..steps {
+                for j in 0..steps {
+                    options.fields.push(Vec2::new(coordinate(i), coordinate(j)) * half_field);
+                }
+            }
+        }
+        if options.fields.is_empty() {
+            options.fields.push(Vec2::ZERO);
+        }
+        Ok(options)
+    }
+
+    fn optics(&self) -> Result<ProjectionOptics, String> {
+        let path = self
+            .prescription
+            .clone()
+            .or_else(|| std::env::var("LITHOS_PRESCRIPTION").ok())
+            .ok_or("no prescription: pass --prescription or set LITHOS_PRESCRIPTION")?;
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("cannot read {path}: {e}"))?;
+        let prescription: Prescription = text.parse().map_err(|e| format!("{path}: {e}"))?;
+        prescription.projection_optics()
+    }
+}
+
+/// Runs `lithos analyze <command> [options]`
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (command, rest) = args.split_first().ok_or("usage: lithos analyze <wavefront|spot> [options]")?;
+    let options = Options::parse(rest)?;
+    match command.as_str() {
+        "wavefront" => wavefront(&options),
+        "spot" => spot(&options),
+        other => Err(format!("unknown analysis '{other}'")),
+    }
+}
+
+fn wavefront(options: &Options) -> Result<(), String> {
+    let optics = options.optics()?;
+    let defaults = WavefrontConfig::default();
+    let config = WavefrontConfig {
+        grid: options.grid.unwrap_or(defaults.grid),
+        zernike_terms: options.terms.unwrap_or(defaults.zernike_terms),
+        ..defaults
+    };
+    for field in &options.fields {
+        let wavefront = analyze_wavefront(&optics, *field, &config)
+            .ok_or_else(|| format!("field ({}, {}) m: too few rays reach the image", field.x, field.y))?;
+        println!("Wavefront at reticle field ({:.4}, {:.4}) m, λ = {:.2} nm", field.x, field.y, config.wavelength * 1e9);
+        println!("  ├─ Rays traced: {}", wavefront.samples.len());
+        println!("  ├─ RMS: {:.2} mλ", wavefront.rms_milliwaves());
+        println!("  ├─ PV: {:.2} mλ", wavefront.peak_to_valley() / config.wavelength * 1e3);
+        println!("  └─ Zernike coefficients (Noll, RMS-normalised, mλ):");
+        for j in 1..=wavefront.zernike.len() as u32 {
+            let (n, m) = crate::zernike::noll_to_nm(j);
+            println!("       Z{j:<3} n={n} m={m:<3} {:>10.3}", wavefront.zernike_milliwaves(j));
+        }
+    }
+    Ok(())
+}
+
+fn spot(options: &Options) -> Result<(), String> {
+    let optics = options.optics()?;
+    let grid = options.grid.unwrap_or(32);
+    let mut spots = Vec::new();
+    for field in &options.fields {
+        match SpotDiagram::trace(&optics, *field, grid) {
+            Some(spot) => spots.push(spot),
+            None => eprintln!("field ({}, {}) m: no rays reach the image, skipped", field.x, field.y),
+        }
+    }
+    if spots.is_empty() {
+        return Err("no field point reaches the image".to_string());
+    }
+
+    // Default MTF range: out to the cutoff of the blurriest spot, 1 / RMS radius
+    let max_frequency = options.max_frequency.unwrap_or_else(|| {
+        let rms = spots.iter().map(SpotDiagram::rms_radius).fold(0.0, f64::max);
+        if rms > 0.0 { 1.0 / rms } else { 1e6 }
+    });
+    let output = std::path::PathBuf::from(options.output.as_deref().unwrap_or("spot_analysis"));
+    std::fs::create_dir_all(&output).map_err(|e| format!("cannot create {}: {e}", output.display()))?;
+    let files = [
+        ("spot_summary.csv", spot::summary_csv(&spots)),
+        ("spots.csv", spot::points_csv(&spots)),
+        ("encircled_energy.csv", spot::encircled_energy_csv(&spots, 50)),
+        ("mtf.csv", spot::mtf_csv(&spots, max_frequency, 50)),
+        ("spots.svg", spot::render_svg(&spots)),
+    ];
+    for (name, contents) in &files {
+        let path = output.join(name);
+        std::fs::write(&path, contents).map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+    }
+
+    println!("Spot diagrams for {} field points, {grid} × {grid} pupil grid", spots.len());
+    for (index, spot) in spots.iter().enumerate() {
+        let branch = if index + 1 == spots.len() { "└─" } else { "├─" };
+        let half_mtf = spot.mtf(max_frequency / 2.0, glam::DVec2::X);
+        println!(
+            "  {branch} ({:.4}, {:.4}) m: RMS {:.3} nm, GEO {:.3} nm, MTF {:.3} at {:.0} cycles/mm",
+            spot.field.x, spot.field.y, spot.rms_radius() * 1e9, spot.geo_radius() * 1e9, half_mtf, max_frequency / 2.0 * 1e-3
+        );
+    }
+    println!("Wrote {} files to {}", files.len(), output.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    /// One spherical mirror imaging a reticle 1.5 m out onto a wafer at 0.75 m
+    const SPHERE: &str = "object 0 0 1.5 0 180 0
+image 0 0 0.75
+pupil 0 0 1 0.05
+surface m1
+    type sag
+    position 0 0 0
+    radius 1
+    semi_diameter 0.2
+    coating none
+end
+";
+
+    /// Writes the test prescription to a file of its own under the temp directory
+    fn sphere_file(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("lithos_analysis_{}_{name}.txt", std::process::id()));
+        std::fs::write(&path, SPHERE).unwrap();
+        path.display().to_string()
+    }
+
+    #[test]
+    fn test_parse_flags() {
+        let options = Options::parse(&args(
+            "--prescription a.txt --field 0.01,-0.02 --field 0,0.03 --grid 16 --terms 9 --max-frequency 250 --output out",
+        ))
+        .unwrap();
+        assert_eq!(options.prescription.as_deref(), Some("a.txt"));
+        assert_eq!(options.fields, vec![Vec2::new(0.01, -0.02), Vec2::new(0.0, 0.03)]);
+        assert_eq!((options.grid, options.terms), (Some(16), Some(9)));
+        // Cycles per mm become cycles per m
+        assert_eq!(options.max_frequency, Some(250e3));
+        assert_eq!(options.output.as_deref(), Some("out"));
+
+        // Without fields the on-axis point is analysed
+        assert_eq!(Options::parse(&[]).unwrap().fields, vec![Vec2::ZERO]);
+    }
+
+    #[test]
+    fn test_parse_half_field_grid() {
+        let fields = Options::parse(&args("--half-field 0.02,0.01")).unwrap().fields;
+        assert_eq!(fields.len(), 9);
+        assert_eq!((fields[0], fields[4], fields[8]), (Vec2::new(-0.02, -0.01), Vec2::ZERO, Vec2::new(0.02, 0.01)));
+
+        // Explicit fields come first, then the grid
+        let fields = Options::parse(&args("--field 0.5,0.5 --half-field 0.02,0.01 --field-grid 2")).unwrap().fields;
+        assert_eq!(fields, vec![
+            Vec2::new(0.5, 0.5),
+            Vec2::new(-0.02, -0.01),
+            Vec2::new(-0.02, 0.01),
+            Vec2::new(0.02, -0.01),
+            Vec2::new(0.02, 0.01),
+        ]);
+        assert_eq!(Options::parse(&args("--half-field 0.02,0.01 --field-grid 1")).unwrap().fields, vec![Vec2::ZERO]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text: &str| Options::parse(&args(text)).err().unwrap();
+        assert_eq!(error("--grid"), "--grid needs a value");
+        assert!(error("--grid many").starts_with("bad grid"));
+        assert!(error("--max-frequency fast").starts_with("bad frequency"));
+        assert_eq!(error("--field 1,2,3"), "'1,2,3' needs two coordinates");
+        assert!(error("--half-field 1,y").starts_with("bad coordinates '1,y'"));
+        assert_eq!(error("--verbose"), "unknown option '--verbose'");
+    }
+
+    #[test]
+    fn test_run() {
+        assert!(run(&[]).unwrap_err().starts_with("usage"));
+        assert_eq!(run(&args("focus")).unwrap_err(), "unknown analysis 'focus'");
+        assert!(run(&args("spot --bogus")).unwrap_err().contains("--bogus"));
+        assert!(run(&args("wavefront --prescription /nonexistent/lens.txt")).unwrap_err().starts_with("cannot read"));
+
+        let prescription = sphere_file("wavefront");
+        run(&args(&format!("wavefront --prescription {prescription} --grid 8 --terms 4"))).unwrap();
+        std::fs::remove_file(prescription).unwrap();
+
+        let prescription = sphere_file("spot");
+        let output = std::env::temp_dir().join(format!("lithos_analysis_{}_spot", std::process::id()));
+        run(&args(&format!("spot --prescription {prescription} --grid 8 --output {}", output.display()))).unwrap();
+        for name in ["spot_summary.csv", "spots.csv", "encircled_energy.csv", "mtf.csv", "spots.svg"] {
+            assert!(output.join(name).is_file(), "{name}");
+        }
+        std::fs::remove_dir_all(output).unwrap();
+        std::fs::remove_file(prescription).unwrap();
+    }
+}
@@ -0,0 +1,308 @@
+//! Burst-mode source operation: the laser fires bursts of pulses (one per
+//! exposure field) separated by idle gaps while the droplet stream keeps running
+//!
+//! Models start-of-burst transients in laser energy and plasma yield and keeps
+//! per-burst statistics
+
+use bevy_ecs::prelude::*;
+use crate::dose::{DoseController, RunningStatistics};
+use crate::plasma::PlasmaPulseEvent;
+use crate::source::SimulationTime;
+
+/// Start-of-burst transients, decaying exponentially with pulse index in the burst
+#[derive(Debug, Clone, Copy)]
+pub struct BurstTransient {
+    /// Relative excess laser energy on the first pulse (stored gain in the amplifiers)
+    pub energy_overshoot: f32,
+    /// Pulses over which the energy overshoot decays (1/e)
+    pub energy_decay_pulses: f32,
+    /// Relative plasma-yield deficit on the first pulse (target conditions settling)
+    pub yield_deficit: f32,
+    /// Pulses over which the yield deficit recovers (1/e)
+    pub yield_decay_pulses: f32,
+}
+
+impl Default for BurstTransient {
+    fn default() -> Self {
+        Self {
+            energy_overshoot: 0.10,
+            energy_decay_pulses: 10.0,
+            yield_deficit: 0.15,
+            yield_decay_pulses: 25.0,
+        }
+    }
+}
+
+impl BurstTransient {
+    /// Multiplier on the commanded laser energy for pulse `k` of a burst
+    pub fn energy_factor(&self, k: u32) -> f32 {
+        1.0 + self.energy_overshoot * decay(k, self.energy_decay_pulses)
+    }
+
+    /// Multiplier on the plasma in-band yield for pulse `k` of a burst
+    pub fn yield_factor(&self, k: u32) -> f32 {
+        1.0 - self.yield_deficit * decay(k, self.yield_decay_pulses)
+    }
+}
+
+fn decay(k: u32, pulses: f32) -> f32 {
+    if pulses > 0.0 {
+        (-(k as f32) / pulses).exp()
+    } else if k == 0 {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Whether the laser is inside a burst or waiting out the inter-burst gap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BurstPhase {
+    Firing,
+    Idle { remaining: f32 },
+}
+
+/// Gates a laser channel's main pulses into bursts of `pulses_per_burst` slots
+#[derive(Component, Debug, Clone)]
+pub struct BurstScheduler {
+    /// Main-pulse slots (fired or dose-skipped) per burst
+    pub pulses_per_burst: u32,
+    /// Idle time between bursts (seconds)
+    pub inter_burst_gap: f32,
+    pub transient: BurstTransient,
+    phase: BurstPhase,
+    /// Bursts started so far
+    bursts_started: u64,
+    pulse_in_burst: u32,
+    skipped_in_burst: u32,
+}
+
+impl BurstScheduler {
+    pub fn new(pulses_per_burst: u32, inter_burst_gap: f32) -> Self {
+        Self {
+            pulses_per_burst: pulses_per_burst.max(1),
+            inter_burst_gap,
+            transient: BurstTransient::default(),
+            // The first burst starts on the first tick
+            phase: BurstPhase::Idle { remaining: 0.0 },
+            bursts_started: 0,
+            pulse_in_burst: 0,
+            skipped_in_burst: 0,
+        }
+    }
+
+    pub fn phase(&self) -> BurstPhase {
+        self.phase
+    }
+
+    pub fn is_firing(&self) -> bool {
+        self.phase == BurstPhase::Firing
+    }
+
+    /// Index of the next main-pulse slot within the current burst
+    pub fn pulse_in_burst(&self) -> u32 {
+        self.pulse_in_burst
+    }
+
+    /// Multiplier on the commanded energy for the next main pulse
+    pub fn energy_factor(&self) -> f32 {
+        self.transient.energy_factor(self.pulse_in_burst)
+    }
+
+    /// Fraction of time spent firing at the given repetition period
+    pub fn duty_cycle(&self, repetition_period: f32) -> f32 {
+        let burst = self.pulses_per_burst as f32 * repetition_period;
+        burst / (burst + self.inter_burst_gap)
+    }
+
+    /// Consumes a main-pulse slot; returns true if it closed the burst
+    pub fn record_slot(&mut self, fired: bool) -> bool {
+        if !fired {
+            self.skipped_in_burst += 1;
+        }
+        self.pulse_in_burst += 1;
+        if self.pulse_in_burst >= self.pulses_per_burst {
+            self.phase = BurstPhase::Idle { remaining: self.inter_burst_gap };
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Advances the inter-burst gap; returns the index of a burst that starts now
+    pub fn advance(&mut self, seconds: f32) -> Option<u64> {
+        let BurstPhase::Idle { remaining } = self.phase else {
+            return None;
+        };
+        let remaining = remaining - seconds;
+        if remaining > 0.0 {
+            self.phase = BurstPhase::Idle { remaining };
+            return None;
+        }
+        self.phase = BurstPhase::Firing;
+        self.pulse_in_burst = 0;
+        self.skipped_in_burst = 0;
+        self.bursts_started += 1;
+        Some(self.bursts_started - 1)
+    }
+}
+
+/// Totals for a single burst
+#[derive(Debug, Clone, Default)]
+pub struct BurstRecord {
+    pub index: u64,
+    /// Simulation time the burst started / ended (seconds)
+    pub start_time: f64,
+    pub end_time: Option<f64>,
+    pub pulses_fired: u32,
+    pub pulses_skipped: u32,
+    /// Main-pulse energy delivered (J)
+    pub laser_energy: f64,
+    /// In-band energy into 2π sr (J)
+    pub in_band_energy: f64,
+}
+
+impl BurstRecord {
+    /// Burst length (seconds); open bursts are measured up to `now`
+    pub fn duration(&self, now: f64) -> f64 {
+        self.end_time.unwrap_or(now) - self.start_time
+    }
+
+    /// Mean in-band power (W, 2π sr) while the burst was firing
+    pub fn in_band_power(&self, now: f64) -> f64 {
+        let duration = self.duration(now);
+        if duration > 0.0 {
+            self.in_band_energy / duration
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Per-burst records and the in-band yield profile across the burst, kept
+/// next to the channel's scheduler
+#[derive(Component, Debug, Default)]
+pub struct BurstStatistics {
+    pub bursts: Vec<BurstRecord>,
+    /// In-band energy (J, 2π sr) by pulse index within the burst, over all bursts
+    pub in_band_by_pulse: Vec<RunningStatistics>,
+}
+
+impl BurstStatistics {
+    /// Fraction of `elapsed` spent inside bursts
+    pub fn duty_cycle(&self, elapsed: f64) -> f64 {
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        self.bursts.iter().map(|b| b.duration(elapsed)).sum::<f64>() / elapsed
+    }
+
+    /// In-band power averaged over bursts and gaps (W, 2π sr), the long-term thermal load
+    pub fn average_in_band_power(&self, elapsed: f64) -> f64 {
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        self.bursts.iter().map(|b| b.in_band_energy).sum::<f64>() / elapsed
+    }
+}
+
+/// System that runs each channel's inter-burst gap and opens new bursts with a fresh dose window
+pub fn burst_scheduler_system(
+    time: Res<SimulationTime>,
+    mut channels: Query<(&mut BurstScheduler, &mut DoseController, &mut BurstStatistics)>,
+) {
+    for (mut scheduler, mut dose, mut stats) in channels.iter_mut() {
+        if let Some(index) = scheduler.advance(time.delta_seconds) {
+            dose.reset_window();
+            stats.bursts.push(BurstRecord {
+                index,
+                start_time: time.total_seconds,
+                ..Default::default()
+            });
+        }
+    }
+}
+
+/// System that attributes plasma pulses to the current burst of the channel that fired them
+pub fn burst_statistics_system(
+    time: Res<SimulationTime>,
+    mut pulses: EventReader<PlasmaPulseEvent>,
+    mut channels: Query<(&BurstScheduler, &mut BurstStatistics)>,
+) {
+    for pulse in pulses.read() {
+        let Ok((_, mut stats)) = channels.get_mut(pulse.channel) else {
+            continue;
+        };
+        let k = pulse.burst_pulse as usize;
+        if stats.in_band_by_pulse.len() <= k {
+            stats.in_band_by_pulse.resize(k + 1, RunningStatistics::default());
+        }
+        stats.in_band_by_pulse[k].push(pulse.emission.energy_2pi_sr as f64);
+
+        if let Some(record) = stats.bursts.last_mut() {
+            record.pulses_fired += 1;
+            record.laser_energy += pulse.emission.pulse_energy as f64;
+            record.in_band_energy += pulse.emission.energy_2pi_sr as f64;
+        }
+    }
+
+    for (scheduler, mut stats) in channels.iter_mut() {
+        if let Some(record) = stats.bursts.last_mut() {
+            record.pulses_skipped = scheduler.skipped_in_burst;
+            if !scheduler.is_firing() && record.end_time.is_none() {
+                record.end_time = Some(time.total_seconds);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transients_decay() {
+        let transient = BurstTransient::default();
+        assert!((transient.energy_factor(0) - 1.10).abs() < 1e-6);
+        assert!((transient.yield_factor(0) - 0.85).abs() < 1e-6);
+        assert!((transient.energy_factor(100) - 1.0).abs() < 1e-4);
+        assert!((transient.yield_factor(200) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_scheduler_cycles_bursts() {
+        let period = 20e-6;
+        let mut scheduler = BurstScheduler::new(3, 50e-6);
+        assert_eq!(scheduler.advance(period), Some(0));
+        assert!(scheduler.is_firing());
+
+        assert!(!scheduler.record_slot(true));
+        assert!(!scheduler.record_slot(false));
+        assert!(scheduler.record_slot(true));
+        assert!(!scheduler.is_firing());
+        assert_eq!(scheduler.skipped_in_burst, 1);
+
+        // 50 μs gap: still idle after two periods, firing after the third
+        assert_eq!(scheduler.advance(period), None);
+        assert_eq!(scheduler.advance(period), None);
+        assert_eq!(scheduler.advance(period), Some(1));
+        assert_eq!(scheduler.pulse_in_burst(), 0);
+        assert_eq!(scheduler.skipped_in_burst, 0);
+    }
+
+    #[test]
+    fn test_duty_cycle() {
+        let scheduler = BurstScheduler::new(100, 500e-6);
+        assert!((scheduler.duty_cycle(20e-6) - 0.8).abs() < 1e-6);
+
+        let stats = BurstStatistics {
+            bursts: vec![
+                BurstRecord { start_time: 0.0, end_time: Some(2e-3), in_band_energy: 0.8, ..Default::default() },
+                BurstRecord { start_time: 2.5e-3, end_time: None, in_band_energy: 0.4, ..Default::default() },
+            ],
+            ..Default::default()
+        };
+        assert!((stats.duty_cycle(3.5e-3) - 3.0 / 3.5).abs() < 1e-9);
+        assert!((stats.average_in_band_power(3.5e-3) - 1.2 / 3.5e-3).abs() < 1e-6);
+    }
+}
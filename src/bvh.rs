@@ -0,0 +1,260 @@
+//! Bounding volume hierarchy over ray-traced surfaces
+//!
+//! Primitives are referred to by index into the caller's surface list.
+//! Unbounded primitives (e.g. infinite planes) are kept outside the tree and
+//! tested on every query.
+
+use glam::Vec3;
+
+/// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn from_center_half_extents(center: Vec3, half_extents: Vec3) -> Self {
+        Self {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+
+    pub fn union(self, other: Aabb) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Distance at which the ray enters the box, if it does so within `max_distance`
+    ///
+    /// Returns 0 for origins inside the box. `inverse_direction` is the
+    /// component-wise reciprocal of the unit ray direction.
+    pub fn ray_entry(&self, origin: Vec3, inverse_direction: Vec3, max_distance: f32) -> Option<f32> {
+        let t1 = (self.min - origin) * inverse_direction;
+        let t2 = (self.max - origin) * inverse_direction;
+        let t_enter = t1.min(t2).max_element().max(0.0);
+        let t_exit = t1.max(t2).min_element().min(max_distance);
+        (t_enter <= t_exit).then_some(t_enter)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    /// Range into `Bvh::primitives`
+    Leaf { first: usize, count: usize },
+    Interior { left: usize, right: usize },
+}
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    bounds: Aabb,
+    kind: NodeKind,
+}
+
+/// Binary BVH built by median splits along the widest centroid axis
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    primitives: Vec<usize>,
+    unbounded: Vec<usize>,
+}
+
+impl Bvh {
+    /// Primitives per leaf
+    const LEAF_SIZE: usize = 2;
+
+    /// Builds the hierarchy over primitive bounds; `None` marks an unbounded primitive
+    pub fn build(bounds: &[Option<Aabb>]) -> Self {
+        let mut bvh = Self::default();
+        let mut bounded = Vec::with_capacity(bounds.len());
+        for (index, b) in bounds.iter().enumerate() {
+            match b {
+                Some(b) => bounded.push((index, *b)),
+                None => bvh.unbounded.push(index),
+            }
+        }
+        if !bounded.is_empty() {
+            bvh.build_node(&mut bounded);
+        }
+        bvh
+    }
+
+    fn build_node(&mut self, items: &mut [(usize, Aabb)]) -> usize {
+        let bounds = items[1..].iter().fold(items[0].1, |acc, (_, b)| acc.union(*b));
+        let node = self.nodes.len();
+
+        if items.len() <= Self::LEAF_SIZE {
+            let first = self.primitives.len();
+            self.primitives.extend(items.iter().map(|(index, _)| *index));
+            self.nodes.push(BvhNode {
+                bounds,
+                kind: NodeKind::Leaf { first, count: items.len() },
+            });
+            return node;
+        }
+
+        let centroids = items[1..].iter().fold(
+            Aabb { min: items[0].1.centroid(), max: items[0].1.centroid() },
+            |acc, (_, b)| acc.union(Aabb { min: b.centroid(), max: b.centroid() }),
+        );
+        let extent = centroids.max - centroids.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |a, b| a.1.centroid()[axis].total_cmp(&b.1.centroid()[axis]));
+
+        // Reserve this node; children are filled in once built
+        self.nodes.push(BvhNode {
+            bounds,
+            kind: NodeKind::Leaf { first: 0, count: 0 },
+        });
+        let (left_items, right_items) = items.split_at_mut(mid);
+        let left = self.build_node(left_items);
+        let right = self.build_node(right_items);
+        self.nodes[node].kind = NodeKind::Interior { left, right };
+        node
+    }
+
+    /// Number of primitives, bounded or not
+    pub fn len(&self) -> usize {
+        self.primitives.len() + self.unbounded.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Calls `visit` for every primitive whose bounds the ray enters within
+    /// `max_distance`, nearer subtrees first
+    ///
+    /// `visit` tests the primitive and returns the distance beyond which the
+    /// caller no longer needs candidates (e.g. its nearest hit so far);
+    /// subtrees entered beyond it are skipped.
+    pub fn traverse(&self, origin: Vec3, direction: Vec3, max_distance: f32, mut visit: impl FnMut(usize) -> f32) {
+        let mut limit = max_distance;
+        for &index in &self.unbounded {
+            limit = limit.min(visit(index));
+        }
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let inverse_direction = direction.normalize().recip();
+        let mut stack = vec![0usize];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if node.bounds.ray_entry(origin, inverse_direction, limit).is_none() {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf { first, count } => {
+                    for &index in &self.primitives[first..first + count] {
+                        limit = limit.min(visit(index));
+                    }
+                }
+                NodeKind::Interior { left, right } => {
+                    let entry = |child: usize| {
+                        self.nodes[child].bounds.ray_entry(origin, inverse_direction, limit)
+                    };
+                    match (entry(left), entry(right)) {
+                        (Some(l), Some(r)) => {
+                            // Push the farther child first so the nearer one is visited next
+                            let (near, far) = if l <= r { (left, right) } else { (right, left) };
+                            stack.push(far);
+                            stack.push(near);
+                        }
+                        (Some(_), None) => stack.push(left),
+                        (None, Some(_)) => stack.push(right),
+                        (None, None) => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    fn ray_sphere(origin: Vec3, direction: Vec3, center: Vec3, radius: f32) -> Option<f32> {
+        let oc = origin - center;
+        let b = oc.dot(direction);
+        let c = oc.dot(oc) - radius * radius;
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let t = -b - discriminant.sqrt();
+        (t >= 0.0).then_some(t)
+    }
+
+    #[test]
+    fn test_ray_entry() {
+        let aabb = Aabb::from_center_half_extents(Vec3::new(5.0, 0.0, 0.0), Vec3::ONE);
+        let entry = aabb.ray_entry(Vec3::ZERO, Vec3::X.recip(), 100.0).unwrap();
+        assert!((entry - 4.0).abs() < 1e-6);
+        assert!(aabb.ray_entry(Vec3::ZERO, Vec3::X.recip(), 3.0).is_none());
+        assert!(aabb.ray_entry(Vec3::ZERO, Vec3::Y.recip(), 100.0).is_none());
+        assert_eq!(aabb.ray_entry(Vec3::new(5.0, 0.5, 0.0), Vec3::Y.recip(), 100.0), Some(0.0));
+    }
+
+    #[test]
+    fn test_traversal_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let spheres: Vec<(Vec3, f32)> = (0..200)
+            .map(|_| {
+                let center = Vec3::new(rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0));
+                (center, rng.gen_range(0.1..0.8))
+            })
+            .collect();
+        let bounds: Vec<Option<Aabb>> = spheres
+            .iter()
+            .map(|&(c, r)| Some(Aabb::from_center_half_extents(c, Vec3::splat(r))))
+            .collect();
+        let bvh = Bvh::build(&bounds);
+        assert_eq!(bvh.len(), spheres.len());
+
+        for _ in 0..500 {
+            let direction = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)).normalize();
+            let brute = spheres
+                .iter()
+                .filter_map(|&(c, r)| ray_sphere(Vec3::ZERO, direction, c, r))
+                .fold(f32::INFINITY, f32::min);
+
+            let mut nearest = f32::INFINITY;
+            bvh.traverse(Vec3::ZERO, direction, f32::INFINITY, |i| {
+                if let Some(t) = ray_sphere(Vec3::ZERO, direction, spheres[i].0, spheres[i].1) {
+                    nearest = nearest.min(t);
+                }
+                nearest
+            });
+            assert_eq!(nearest, brute);
+        }
+    }
+
+    #[test]
+    fn test_unbounded_primitives_always_visited() {
+        let bvh = Bvh::build(&[None, Some(Aabb::from_center_half_extents(Vec3::splat(50.0), Vec3::ONE))]);
+        let mut visited = Vec::new();
+        bvh.traverse(Vec3::ZERO, Vec3::X, f32::INFINITY, |i| {
+            visited.push(i);
+            f32::INFINITY
+        });
+        assert_eq!(visited, vec![0]);
+    }
+}
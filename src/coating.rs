@@ -0,0 +1,369 @@
+//! Multilayer mirror coatings: Mo/Si-style layer stacks whose reflectance is
+//! computed with the transfer-matrix (characteristic matrix) method
+//!
+//! Reflectance depends on wavelength and angle of incidence; each coating
+//! caches it as a 2D lookup table that the ray tracer samples per hit.
+
+use std::sync::Arc;
+use bevy_ecs::prelude::*;
+use nalgebra::Complex;
+
+/// Wavelength range covered by the tabulated optical constants (nm)
+const TABLE_MIN_NM: f64 = 12.5;
+const TABLE_MAX_NM: f64 = 14.5;
+const TABLE_WAVELENGTH_STEP_NM: f64 = 0.02;
+/// Angle-of-incidence range of the lookup tables (degrees from the normal)
+const TABLE_MAX_ANGLE_DEG: f64 = 88.0;
+const TABLE_ANGLE_STEP_DEG: f64 = 1.0;
+
+/// Coating materials with tabulated EUV optical constants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoatingMaterial {
+    Molybdenum,
+    Silicon,
+    /// Interdiffused Mo/Si interface layer
+    MolybdenumSilicide,
+    /// Oxidation-resistant capping layer
+    Ruthenium,
+}
+
+/// Parses the chemical symbols `Mo`, `Si`, `MoSi2` and `Ru`
+impl std::str::FromStr for CoatingMaterial {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "Mo" => Ok(CoatingMaterial::Molybdenum),
+            "Si" => Ok(CoatingMaterial::Silicon),
+            "MoSi2" => Ok(CoatingMaterial::MolybdenumSilicide),
+            "Ru" => Ok(CoatingMaterial::Ruthenium),
+            other => Err(format!("unknown coating material '{other}'")),
+        }
+    }
+}
+
+impl std::fmt::Display for CoatingMaterial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CoatingMaterial::Molybdenum => "Mo",
+            CoatingMaterial::Silicon => "Si",
+            CoatingMaterial::MolybdenumSilicide => "MoSi2",
+            CoatingMaterial::Ruthenium => "Ru",
+        })
+    }
+}
+
+/// δ and β of n = 1 - δ - iβ (the n - ik convention) sampled at 12.5, 13.0, 13.5, 14.0 and 14.5 nm (approximate CXRO values)
+const CONSTANTS_WAVELENGTHS_NM: [f64; 5] = [12.5, 13.0, 13.5, 14.0, 14.5];
+
+impl CoatingMaterial {
+    fn table(self) -> ([f64; 5], [f64; 5]) {
+        match self {
+            CoatingMaterial::Molybdenum => (
+                [0.0666, 0.0716, 0.0769, 0.0826, 0.0886],
+                [0.00536, 0.00587, 0.00643, 0.00703, 0.00769],
+            ),
+            // Just above the Si L-edge at 12.4 nm the real part approaches 1
+            CoatingMaterial::Silicon => (
+                [0.0002, 0.0006, 0.0010, 0.0015, 0.0021],
+                [0.00118, 0.00150, 0.00183, 0.00218, 0.00256],
+            ),
+            CoatingMaterial::MolybdenumSilicide => (
+                [0.0263, 0.0285, 0.0307, 0.0330, 0.0354],
+                [0.0037, 0.0040, 0.0043, 0.0046, 0.0050],
+            ),
+            CoatingMaterial::Ruthenium => (
+                [0.0974, 0.1053, 0.1136, 0.1222, 0.1311],
+                [0.0147, 0.0159, 0.0171, 0.0184, 0.0197],
+            ),
+        }
+    }
+
+    /// Complex refractive index n - ik at the given wavelength (nm), clamped to the table
+    pub fn refractive_index(self, wavelength_nm: f64) -> Complex<f64> {
+        let (delta, beta) = self.table();
+        let x = wavelength_nm.clamp(TABLE_MIN_NM, TABLE_MAX_NM);
+        let i = CONSTANTS_WAVELENGTHS_NM
+            .windows(2)
+            .position(|w| x <= w[1])
+            .unwrap_or(CONSTANTS_WAVELENGTHS_NM.len() - 2);
+        let f = (x - CONSTANTS_WAVELENGTHS_NM[i]) / (CONSTANTS_WAVELENGTHS_NM[i + 1] - CONSTANTS_WAVELENGTHS_NM[i]);
+        let lerp = |v: [f64; 5]| v[i] + f * (v[i + 1] - v[i]);
+        Complex::new(1.0 - lerp(delta), -lerp(beta))
+    }
+}
+
+/// A single homogeneous film
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layer {
+    pub material: CoatingMaterial,
+    /// Physical thickness (nm)
+    pub thickness: f64,
+}
+
+/// Periodic multilayer on a substrate, with an optional capping layer
+///
+/// Each period lists its layers from the top (vacuum side) down. Interdiffusion
+/// inserts a silicide layer at every interface inside the periodic stack; half
+/// of its thickness is taken from each neighbour so the period is unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultilayerStack {
+    pub period: Vec<Layer>,
+    pub periods: u32,
+    pub capping: Option<Layer>,
+    /// Silicide thickness formed at each interface (nm)
+    pub interdiffusion: f64,
+    pub substrate: CoatingMaterial,
+}
+
+impl MultilayerStack {
+    /// 40 × 6.9 nm Mo/Si (Γ = 0.4) with 0.5 nm interlayers and a 2 nm Ru cap
+    pub fn mo_si() -> Self {
+        Self {
+            period: vec![
+                Layer { material: CoatingMaterial::Silicon, thickness: 4.14 },
+                Layer { material: CoatingMaterial::Molybdenum, thickness: 2.76 },
+            ],
+            periods: 40,
+            capping: Some(Layer { material: CoatingMaterial::Ruthenium, thickness: 2.0 }),
+            interdiffusion: 0.5,
+            substrate: CoatingMaterial::Silicon,
+        }
+    }
+
+    /// Period thickness (nm)
+    pub fn period_thickness(&self) -> f64 {
+        self.period.iter().map(|l| l.thickness).sum()
+    }
+
+    /// All films from the top down, interlayers included
+    pub fn layers(&self) -> Vec<Layer> {
+        let mut period = Vec::with_capacity(self.period.len() * 2);
+        let shared = self.interdiffusion * 0.5;
+        let interfaces = self.interdiffusion > 0.0 && self.period.len() > 1;
+        for layer in &self.period {
+            let thickness = if interfaces { (layer.thickness - 2.0 * shared).max(0.0) } else { layer.thickness };
+            period.push(Layer { material: layer.material, thickness });
+            if interfaces {
+                period.push(Layer { material: CoatingMaterial::MolybdenumSilicide, thickness: self.interdiffusion });
+            }
+        }
+
+        let mut layers: Vec<Layer> = self.capping.into_iter().collect();
+        for _ in 0..self.periods {
+            layers.extend_from_slice(&period);
+        }
+        layers
+    }
+
+    /// Complex amplitude reflection coefficients (r_s, r_p) from vacuum
+    pub fn reflection_coefficients(&self, wavelength_nm: f64, incidence: f64) -> (Complex<f64>, Complex<f64>) {
+        self.reflection_coefficients_beneath(&[], wavelength_nm, incidence)
+    }
+
+    /// Reflection coefficients with extra films on top of the stack, each
+    /// given as (complex index n - ik, thickness in nm), e.g. a reticle absorber
+    pub fn reflection_coefficients_beneath(
+        &self,
+        overlayers: &[(Complex<f64>, f64)],
+        wavelength_nm: f64,
+        incidence: f64,
+    ) -> (Complex<f64>, Complex<f64>) {
+        let films: Vec<(Complex<f64>, f64)> = overlayers
+            .iter()
+            .copied()
+            .chain(self.layers().iter().map(|l| (l.material.refractive_index(wavelength_nm), l.thickness)))
+            .collect();
+        let sin0 = incidence.sin();
+        let cos0 = Complex::new(incidence.cos(), 0.0);
+        let one = Complex::new(1.0, 0.0);
+        let i = Complex::new(0.0, 1.0);
+
+        // Cosine of the refraction angle inside a medium, branch with Im(n cos θ) <= 0
+        let cos_in = |n: Complex<f64>| {
+            let s = Complex::new(sin0, 0.0) / n;
+            let c = (one - s * s).sqrt();
+            if (n * c).im > 0.0 { -c } else { c }
+        };
+
+        let coefficient = |s_polarized: bool| {
+            let admittance = |n: Complex<f64>, cos: Complex<f64>| if s_polarized { n * cos } else { n / cos };
+            // Characteristic matrix of the stack, multiplied top-down
+            let (mut m11, mut m12, mut m21, mut m22) = (one, Complex::new(0.0, 0.0), Complex::new(0.0, 0.0), one);
+            for &(n, thickness) in &films {
+                let cos = cos_in(n);
+                let eta = admittance(n, cos);
+                let phase = n * cos * (2.0 * std::f64::consts::PI * thickness / wavelength_nm);
+                let (c, s) = (phase.cos(), phase.sin());
+                let (a11, a12, a21, a22) = (c, i * s / eta, i * eta * s, c);
+                (m11, m12, m21, m22) = (
+                    m11 * a11 + m12 * a21,
+                    m11 * a12 + m12 * a22,
+                    m21 * a11 + m22 * a21,
+                    m21 * a12 + m22 * a22,
+                );
+            }
+            let n_sub = self.substrate.refractive_index(wavelength_nm);
+            let eta_sub = admittance(n_sub, cos_in(n_sub));
+            let eta0 = admittance(one, cos0);
+            let b = m11 + m12 * eta_sub;
+            let c = m21 + m22 * eta_sub;
+            let y = c / b;
+            (eta0 - y) / (eta0 + y)
+        };
+
+        (coefficient(true), coefficient(false))
+    }
+
+    /// Unpolarized reflectance at the given wavelength (nm) and angle of incidence (rad)
+    pub fn reflectance_nm(&self, wavelength_nm: f64, incidence: f64) -> f64 {
+        let (rs, rp) = self.reflection_coefficients(wavelength_nm, incidence);
+        0.5 * (rs.norm_sqr() + rp.norm_sqr())
+    }
+}
+
+/// Reflection coefficients of a stack tabulated over wavelength and angle of incidence
+#[derive(Debug, Clone)]
+pub struct ReflectivityTable {
+    wavelengths: usize,
+    angles: usize,
+    rs: Vec<Complex<f32>>,
+    rp: Vec<Complex<f32>>,
+}
+
+impl ReflectivityTable {
+    pub fn compute(stack: &MultilayerStack) -> Self {
+        Self::compute_beneath(stack, &[])
+    }
+
+    /// Table of a stack under extra films of (complex index, thickness in nm)
+    pub fn compute_beneath(stack: &MultilayerStack, overlayers: &[(Complex<f64>, f64)]) -> Self {
+        let wavelengths = ((TABLE_MAX_NM - TABLE_MIN_NM) / TABLE_WAVELENGTH_STEP_NM).round() as usize + 1;
+        let angles = (TABLE_MAX_ANGLE_DEG / TABLE_ANGLE_STEP_DEG).round() as usize + 1;
+        let mut rs = Vec::with_capacity(wavelengths * angles);
+        let mut rp = Vec::with_capacity(wavelengths * angles);
+        for w in 0..wavelengths {
+            let wavelength = TABLE_MIN_NM + w as f64 * TABLE_WAVELENGTH_STEP_NM;
+            for a in 0..angles {
+                let incidence = (a as f64 * TABLE_ANGLE_STEP_DEG).to_radians();
+                let (s, p) = stack.reflection_coefficients_beneath(overlayers, wavelength, incidence);
+                rs.push(Complex::new(s.re as f32, s.im as f32));
+                rp.push(Complex::new(p.re as f32, p.im as f32));
+            }
+        }
+        Self { wavelengths, angles, rs, rp }
+    }
+
+    /// Bilinearly interpolated (r_s, r_p), or `None` outside the tabulated wavelengths
+    pub fn coefficients(&self, wavelength: f32, incidence: f32) -> Option<(Complex<f32>, Complex<f32>)> {
+        let nm = wavelength as f64 * 1e9;
+        if !(TABLE_MIN_NM..=TABLE_MAX_NM).contains(&nm) {
+            return None;
+        }
+        let x = ((nm - TABLE_MIN_NM) / TABLE_WAVELENGTH_STEP_NM).min((self.wavelengths - 1) as f64);
+        let y = (incidence.abs().to_degrees() as f64 / TABLE_ANGLE_STEP_DEG).min((self.angles - 1) as f64);
+        let (w0, a0) = (x.floor() as usize, y.floor() as usize);
+        let (w1, a1) = ((w0 + 1).min(self.wavelengths - 1), (a0 + 1).min(self.angles - 1));
+        let (fx, fy) = ((x - w0 as f64) as f32, (y - a0 as f64) as f32);
+
+        let sample = |values: &[Complex<f32>]| {
+            let at = |w: usize, a: usize| values[w * self.angles + a];
+            let low = at(w0, a0) * (1.0 - fy) + at(w0, a1) * fy;
+            let high = at(w1, a0) * (1.0 - fy) + at(w1, a1) * fy;
+            low * (1.0 - fx) + high * fx
+        };
+        Some((sample(&self.rs), sample(&self.rp)))
+    }
+
+    /// Unpolarized reflectance, or `None` outside the tabulated wavelengths
+    pub fn reflectance(&self, wavelength: f32, incidence: f32) -> Option<f32> {
+        self.coefficients(wavelength, incidence)
+            .map(|(rs, rp)| 0.5 * (rs.norm_sqr() + rp.norm_sqr()))
+    }
+
+    /// Wavelength (m) and value of the peak normal-incidence reflectance
+    pub fn peak(&self) -> (f32, f32) {
+        (0..self.wavelengths)
+            .map(|w| {
+                let i = w * self.angles;
+                let r = 0.5 * (self.rs[i].norm_sqr() + self.rp[i].norm_sqr());
+                (((TABLE_MIN_NM + w as f64 * TABLE_WAVELENGTH_STEP_NM) * 1e-9) as f32, r)
+            })
+            .fold((0.0, 0.0), |best, cur| if cur.1 > best.1 { cur } else { best })
+    }
+}
+
+/// Multilayer coating on a mirror; the lookup table is shared between clones
+#[derive(Component, Debug, Clone)]
+pub struct MultilayerCoating {
+    pub stack: MultilayerStack,
+    table: Arc<ReflectivityTable>,
+}
+
+impl MultilayerCoating {
+    pub fn new(stack: MultilayerStack) -> Self {
+        let table = Arc::new(ReflectivityTable::compute(&stack));
+        Self { stack, table }
+    }
+
+    pub fn table(&self) -> &ReflectivityTable {
+        &self.table
+    }
+
+    /// Amplitude coefficients (r_s, r_p) at the given wavelength (m) and angle of
+    /// incidence (rad), or `None` outside the tabulated band
+    pub fn coefficients(&self, wavelength: f32, incidence: f32) -> Option<(Complex<f32>, Complex<f32>)> {
+        self.table.coefficients(wavelength, incidence)
+    }
+
+    /// Unpolarized reflectance at the given wavelength (m) and angle of incidence (rad),
+    /// or `None` outside the tabulated band
+    pub fn reflectance(&self, wavelength: f32, incidence: f32) -> Option<f32> {
+        self.table.reflectance(wavelength, incidence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mo_si_normal_incidence_peak() {
+        let table = ReflectivityTable::compute(&MultilayerStack::mo_si());
+        let (wavelength, peak) = table.peak();
+        assert!((13.2e-9..13.8e-9).contains(&wavelength), "peak at {wavelength}");
+        assert!((0.60..0.76).contains(&peak), "peak reflectance {peak}");
+
+        // Bandwidth: well down 0.5 nm off the peak
+        let off_peak = table.reflectance(wavelength + 0.5e-9, 0.0).unwrap();
+        assert!(off_peak < 0.5 * peak);
+        assert!(table.reflectance(20e-9, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_angular_acceptance() {
+        let stack = MultilayerStack::mo_si();
+        let normal = stack.reflectance_nm(13.5, 0.0);
+        let oblique = stack.reflectance_nm(13.5, 20f64.to_radians());
+        assert!(normal > 0.5);
+        // The Bragg peak moves to shorter wavelengths off normal incidence
+        assert!(oblique < 0.5 * normal);
+    }
+
+    #[test]
+    fn test_interdiffusion_keeps_period_and_lowers_peak() {
+        let sharp = MultilayerStack { interdiffusion: 0.0, ..MultilayerStack::mo_si() };
+        let diffuse = MultilayerStack::mo_si();
+        let period = |stack: &MultilayerStack| {
+            let layers = stack.layers();
+            // Skip the cap; one period spans 4 films with interlayers
+            let films = if stack.interdiffusion > 0.0 { 4 } else { 2 };
+            layers[1..1 + films].iter().map(|l| l.thickness).sum::<f64>()
+        };
+        assert!((period(&sharp) - 6.9).abs() < 1e-9);
+        assert!((period(&diffuse) - 6.9).abs() < 1e-9);
+
+        let peak = |stack: &MultilayerStack| ReflectivityTable::compute(stack).peak().1;
+        assert!(peak(&diffuse) < peak(&sharp));
+    }
+}
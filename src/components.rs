@@ -102,17 +102,49 @@ impl Lifetime {
 
 #[derive(Component, Debug, Clone, Copy)]
 pub struct OpticalMaterial {
+    /// Peak reflectivity at `peak_wavelength`
     pub reflectivity: f32,
+    /// Absorption at `peak_wavelength`
     pub absorption: f32,
+    /// Wavelength of peak EUV reflectivity (m)
+    pub peak_wavelength: f32,
+    /// FWHM of the EUV reflectivity peak (m)
+    pub bandwidth: f32,
+    /// Reflectivity for DUV, visible and near-IR light (100 nm - 1 μm)
+    pub duv_reflectivity: f32,
+    /// Reflectivity for infrared light (> 1 μm)
+    pub ir_reflectivity: f32,
 }
 
 impl OpticalMaterial {
     pub const BRAGG_MIRROR: Self = Self {
         reflectivity: 0.70,
         absorption: 0.30,
+        peak_wavelength: 13.5e-9,
+        bandwidth: 0.5e-9,
+        duv_reflectivity: 0.55,
+        ir_reflectivity: 0.90,
     };
-    pub fn interact(&self, rng: &mut impl rand::Rng) -> bool {
-        rng.gen::<f32>() < self.reflectivity
+
+    /// Reflectivity at the given wavelength (m)
+    pub fn reflectivity_at(&self, wavelength: f32) -> f32 {
+        if wavelength < 100e-9 {
+            let x = (wavelength - self.peak_wavelength) / self.bandwidth;
+            self.reflectivity * (-4.0 * std::f32::consts::LN_2 * x * x).exp()
+        } else if wavelength < 1e-6 {
+            self.duv_reflectivity
+        } else {
+            self.ir_reflectivity
+        }
+    }
+
+    /// Absorption at the given wavelength; light the coating fails to reflect is absorbed
+    pub fn absorption_at(&self, wavelength: f32) -> f32 {
+        (self.absorption + self.reflectivity - self.reflectivity_at(wavelength)).clamp(0.0, 1.0)
+    }
+
+    pub fn interact(&self, wavelength: f32, rng: &mut impl rand::Rng) -> bool {
+        rng.gen::<f32>() < self.reflectivity_at(wavelength)
     }
 }
 
@@ -127,6 +159,15 @@ mod tests {
         assert_eq!(thermal.temperature, 294.15); // Should increase by 1K
     }
 
+    #[test]
+    fn test_bragg_mirror_spectral_response() {
+        let mirror = OpticalMaterial::BRAGG_MIRROR;
+        assert_eq!(mirror.reflectivity_at(13.5e-9), 0.70);
+        assert!(mirror.reflectivity_at(15.0e-9) < 0.01); // Outside the multilayer bandwidth
+        assert_eq!(mirror.reflectivity_at(10.6e-6), 0.90);
+        assert!((mirror.absorption_at(15.0e-9) - 1.0).abs() < 0.01);
+    }
+
     #[test]
     fn test_velocity_speed() {
         let vel = Velocity::new(3.0, 4.0, 0.0);
@@ -0,0 +1,210 @@
+//! Mirror contamination subsystem: tin film growth, hydrogen-radical cleaning
+//! and the resulting loss of EUV reflectivity
+//!
+//! Also projects collector lifetime over billions of pulses in accelerated mode
+
+use bevy_ecs::prelude::*;
+use crate::debris::{SurfaceDeposit, TIN_DENSITY};
+use crate::optics::MirrorSurface;
+use crate::source::SimulationTime;
+
+/// Tin film on a mirror and the cleaning that removes it
+#[derive(Component, Debug, Clone)]
+pub struct ContaminationState {
+    /// Area the deposited tin spreads over (m²)
+    pub deposition_area: f64,
+    /// Current tin film thickness (m)
+    pub tin_thickness: f64,
+    /// Hydrogen-radical etch rate (m/s of tin removed)
+    pub cleaning_rate: f64,
+    /// Total tin thickness removed by cleaning (m)
+    pub cleaned_thickness: f64,
+    /// Deposited mass already converted into film thickness (kg)
+    accounted_mass: f64,
+}
+
+impl ContaminationState {
+    /// 1/e intensity attenuation length of tin at 13.5 nm (m)
+    pub const TIN_ATTENUATION_LENGTH: f64 = 20e-9;
+    /// Default H-radical cleaning rate: 0.5 nm/min
+    pub const DEFAULT_CLEANING_RATE: f64 = 0.5e-9 / 60.0;
+
+    pub fn new(deposition_area: f64, cleaning_rate: f64) -> Self {
+        Self {
+            deposition_area,
+            tin_thickness: 0.0,
+            cleaning_rate,
+            cleaned_thickness: 0.0,
+            accounted_mass: 0.0,
+        }
+    }
+
+    /// Contamination state for a mirror, spreading deposits over its clear aperture
+    pub fn for_mirror(surface: &MirrorSurface, cleaning_rate: f64) -> Self {
+        Self::new(surface.aperture.area() as f64, cleaning_rate)
+    }
+
+    /// Adds a deposited tin mass as a uniform film
+    pub fn deposit_mass(&mut self, mass: f64) {
+        if self.deposition_area > 0.0 {
+            self.tin_thickness += mass / (TIN_DENSITY * self.deposition_area);
+        }
+    }
+
+    /// Etches the film for `seconds` of hydrogen-radical exposure
+    pub fn clean(&mut self, seconds: f64) {
+        let removed = (self.cleaning_rate * seconds).min(self.tin_thickness);
+        self.tin_thickness -= removed;
+        self.cleaned_thickness += removed;
+    }
+
+    /// Multiplier on the clean-mirror EUV reflectivity (light crosses the film twice)
+    pub fn reflectivity_factor(&self) -> f32 {
+        (-2.0 * self.tin_thickness / Self::TIN_ATTENUATION_LENGTH).exp() as f32
+    }
+
+    /// Reflectivity multiplier at a wavelength; thin metallic tin barely affects
+    /// DUV and longer wavelengths
+    pub fn reflectivity_factor_at(&self, wavelength: f32) -> f32 {
+        if wavelength < 100e-9 {
+            self.reflectivity_factor()
+        } else {
+            1.0
+        }
+    }
+}
+
+/// System that grows tin films from new deposits and applies cleaning
+pub fn contamination_system(
+    time: Res<SimulationTime>,
+    mut query: Query<(&SurfaceDeposit, &mut ContaminationState)>,
+) {
+    for (deposit, mut contamination) in query.iter_mut() {
+        let total = deposit.total_mass();
+        let new_mass = total - contamination.accounted_mass;
+        if new_mass > 0.0 {
+            contamination.deposit_mass(new_mass);
+            contamination.accounted_mass = total;
+        }
+        contamination.clean(time.delta_seconds as f64);
+    }
+}
+
+/// Result of an accelerated contamination run
+#[derive(Debug, Clone)]
+pub struct LifetimeProjection {
+    /// Pulses until reflectivity falls below the swap threshold, if within the horizon
+    pub pulses_to_swap: Option<u64>,
+    /// Operating time until swap (seconds)
+    pub time_to_swap: Option<f64>,
+    /// (pulses, reflectivity factor) samples along the run
+    pub reflectivity_curve: Vec<(u64, f32)>,
+    /// Tin thickness at the end of the run (m)
+    pub final_thickness: f64,
+}
+
+/// Accelerated-mode contamination run
+///
+/// Deposition and cleaning are both steady, so the film on a copy of `state`
+/// grows (or etches away) linearly with pulses and the crossing of
+/// `swap_threshold` (reflectivity relative to a clean mirror) is solved in
+/// closed form. The reflectivity curve has up to `curve_points` log-spaced
+/// samples up to the swap, or to `max_pulses` if the mirror lasts that long.
+pub fn project_contamination(
+    state: &ContaminationState,
+    mass_per_pulse: f64,
+    repetition_rate: f64,
+    max_pulses: u64,
+    curve_points: usize,
+    swap_threshold: f32,
+) -> LifetimeProjection {
+    let deposited = if state.deposition_area > 0.0 { mass_per_pulse / (TIN_DENSITY * state.deposition_area) } else { 0.0 };
+    let net_growth = deposited - state.cleaning_rate / repetition_rate; // m per pulse
+    let thickness_at = |pulses: u64| (state.tin_thickness + net_growth * pulses as f64).max(0.0);
+    let factor_at = |pulses: u64| ContaminationState { tin_thickness: thickness_at(pulses), ..state.clone() }.reflectivity_factor();
+
+    // Film thickness at which reflectivity reaches the threshold
+    let swap_thickness = -(swap_threshold as f64).ln() * ContaminationState::TIN_ATTENUATION_LENGTH / 2.0;
+    let pulses_to_swap = if state.tin_thickness > swap_thickness {
+        Some(0)
+    } else if net_growth > 0.0 {
+        // First pulse past the swap thickness
+        let pulses = ((swap_thickness - state.tin_thickness) / net_growth).floor() + 1.0;
+        Some(pulses as u64).filter(|&p| p <= max_pulses)
+    } else {
+        None
+    };
+
+    let end = pulses_to_swap.unwrap_or(max_pulses);
+    let samples = curve_points.max(2) - 1;
+    let mut curve = vec![(0, factor_at(0))];
+    for k in 0..samples {
+        let pulses = ((end as f64).powf((k + 1) as f64 / samples as f64).round() as u64).min(end);
+        if curve.last().is_some_and(|&(last, _)| pulses > last) {
+            curve.push((pulses, factor_at(pulses)));
+        }
+    }
+
+    LifetimeProjection {
+        pulses_to_swap,
+        time_to_swap: pulses_to_swap.map(|p| p as f64 / repetition_rate),
+        reflectivity_curve: curve,
+        final_thickness: thickness_at(end),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tin_film_reduces_reflectivity() {
+        let mut state = ContaminationState::new(1.0, 0.0);
+        assert_eq!(state.reflectivity_factor(), 1.0);
+
+        state.deposit_mass(1e-9 * TIN_DENSITY); // 1 nm over 1 m²
+        assert!((state.tin_thickness - 1e-9).abs() < 1e-15);
+        assert!((state.reflectivity_factor() - 0.905).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_cleaning_removes_deposit() {
+        let mut state = ContaminationState::new(1.0, 1e-9);
+        state.deposit_mass(2e-9 * TIN_DENSITY);
+        state.clean(1.5);
+        assert!((state.tin_thickness - 0.5e-9).abs() < 1e-15);
+        state.clean(10.0);
+        assert_eq!(state.tin_thickness, 0.0);
+        assert!((state.cleaned_thickness - 2e-9).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_projection_time_to_swap() {
+        let state = ContaminationState::new(1.0, 0.0);
+        // 1e-18 m of tin per pulse at 50 kHz; 10% loss needs ~1.05 nm
+        let mass_per_pulse = 1e-18 * TIN_DENSITY;
+        let projection = project_contamination(&state, mass_per_pulse, 50_000.0, 10_000_000_000, 64, 0.9);
+
+        let expected = -(0.9f64.ln()) * ContaminationState::TIN_ATTENUATION_LENGTH / 2.0 / 1e-18;
+        let pulses = projection.pulses_to_swap.unwrap() as f64;
+        // Exact up to the f32 threshold
+        assert!((pulses - expected).abs() <= expected * 1e-6);
+        assert!((projection.time_to_swap.unwrap() - pulses / 50_000.0).abs() < 1e-6);
+
+        // Log-spaced curve ending at the swap, reflectivity falling throughout
+        let curve = &projection.reflectivity_curve;
+        assert!(curve.len() <= 64);
+        assert_eq!(curve.last().unwrap().0, projection.pulses_to_swap.unwrap());
+        assert!(curve.windows(2).all(|w| w[1].0 > w[0].0 && w[1].1 <= w[0].1));
+        assert!(curve[curve.len() - 2].1 > 0.9);
+    }
+
+    #[test]
+    fn test_projection_cleaning_keeps_up() {
+        let deposition_rate = 1e-18 * 50_000.0; // m/s
+        let state = ContaminationState::new(1.0, deposition_rate * 2.0); // Cleaning outpaces deposition
+        let projection = project_contamination(&state, 1e-18 * TIN_DENSITY, 50_000.0, 1_000_000_000, 64, 0.9);
+        assert!(projection.pulses_to_swap.is_none());
+        assert_eq!(projection.final_thickness, 0.0);
+    }
+}
@@ -0,0 +1,486 @@
+//! Debris subsystem: tin ions, neutrals and microparticles leaving the plasma
+//!
+//! Debris is slowed by the hydrogen buffer gas and deposited on whatever
+//! mirror or chamber wall it reaches before thermalizing
+
+use bevy_ecs::prelude::*;
+use rand_distr::{Distribution, LogNormal, Normal};
+use crate::units::{Position3D, Distance};
+use crate::components::*;
+use crate::optics::MirrorSurface;
+use crate::plasma::{AnisotropicEmission, EmissionPattern};
+use crate::plasma::PlasmaAxis;
+use crate::source::{DropletState, SimulationTime};
+
+const ELECTRON_VOLT: f64 = 1.602e-19;
+const TIN_ATOM_MASS: f64 = 118.71 * 1.6605e-27; // kg
+pub const TIN_DENSITY: f64 = 7_310.0; // kg/m³
+const HYDROGEN_MOLECULE_MASS: f64 = 2.016 * 1.6605e-27; // kg
+const BOLTZMANN: f64 = 1.380_649e-23;
+
+/// Debris populations produced by a collapsing tin plasma
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebrisKind {
+    /// Fast tin ions (keV) from the expanding plasma
+    FastIon,
+    /// Neutral tin atoms (tens to hundreds of eV)
+    Neutral,
+    /// Liquid tin fragments (μm scale, ~100 m/s)
+    Microparticle,
+}
+
+impl DebrisKind {
+    pub const ALL: [DebrisKind; 3] = [DebrisKind::FastIon, DebrisKind::Neutral, DebrisKind::Microparticle];
+
+    fn index(&self) -> usize {
+        match self {
+            DebrisKind::FastIon => 0,
+            DebrisKind::Neutral => 1,
+            DebrisKind::Microparticle => 2,
+        }
+    }
+}
+
+/// Macro-particle representing many tin atoms (or fragments) of one debris population
+#[derive(Component, Debug, Clone, Copy)]
+pub struct DebrisParticle {
+    pub kind: DebrisKind,
+    /// Total tin mass represented (kg)
+    pub mass: f64,
+    /// Kinetic energy per atom (eV); for microparticles, per fragment
+    pub kinetic_energy_ev: f32,
+    /// Fragment radius (microparticles only)
+    pub fragment_radius: Distance,
+}
+
+impl DebrisParticle {
+    /// Mass of a single moving body (atom or fragment) in kg
+    fn body_mass(&self) -> f64 {
+        match self.kind {
+            DebrisKind::Microparticle => {
+                let r = self.fragment_radius.as_meters_f64();
+                TIN_DENSITY * 4.0 / 3.0 * std::f64::consts::PI * r * r * r
+            }
+            _ => TIN_ATOM_MASS,
+        }
+    }
+
+    /// Speed corresponding to a kinetic energy per body (m/s)
+    fn speed_for_energy(&self, energy_ev: f32) -> f32 {
+        (2.0 * energy_ev as f64 * ELECTRON_VOLT / self.body_mass()).sqrt() as f32
+    }
+
+    /// Kinetic energy per body for a speed (eV)
+    fn energy_for_speed(&self, speed: f32) -> f32 {
+        (0.5 * self.body_mass() * (speed as f64).powi(2) / ELECTRON_VOLT) as f32
+    }
+}
+
+/// Energy distribution and share of the droplet mass for one debris population
+#[derive(Debug, Clone, Copy)]
+pub struct DebrisPopulation {
+    /// Fraction of droplet mass carried by this population
+    pub mass_fraction: f32,
+    /// Macro-particles spawned per plasma event
+    pub macro_particles: u32,
+    /// Median kinetic energy (eV); ignored for microparticles
+    pub median_energy_ev: f32,
+    /// Log-normal width of the energy distribution
+    pub energy_spread: f32,
+    /// Angular distribution about the axis pointing back at the laser
+    pub pattern: AnisotropicEmission,
+}
+
+/// Configuration for debris generated at plasma collapse
+#[derive(Resource, Debug, Clone)]
+pub struct DebrisConfig {
+    pub fast_ions: DebrisPopulation,
+    pub neutrals: DebrisPopulation,
+    pub microparticles: DebrisPopulation,
+    /// Mean microparticle speed (m/s)
+    pub microparticle_speed: f32,
+    /// Microparticle speed std deviation (m/s)
+    pub microparticle_speed_jitter: f32,
+    /// Microparticle fragment radius
+    pub fragment_radius: Distance,
+}
+
+impl Default for DebrisConfig {
+    fn default() -> Self {
+        Self {
+            fast_ions: DebrisPopulation {
+                mass_fraction: 0.05,
+                macro_particles: 8,
+                median_energy_ev: 1_500.0,
+                energy_spread: 0.6,
+                pattern: AnisotropicEmission { a1: 0.8, a2: 0.0 }, // Expand toward the laser
+            },
+            neutrals: DebrisPopulation {
+                mass_fraction: 0.35,
+                macro_particles: 8,
+                median_energy_ev: 100.0,
+                energy_spread: 0.8,
+                pattern: AnisotropicEmission { a1: 0.4, a2: 0.0 },
+            },
+            microparticles: DebrisPopulation {
+                mass_fraction: 0.60,
+                macro_particles: 4,
+                median_energy_ev: 0.0,
+                energy_spread: 0.0,
+                pattern: AnisotropicEmission { a1: -0.8, a2: 0.0 }, // Pushed along the beam
+            },
+            microparticle_speed: 150.0,
+            microparticle_speed_jitter: 50.0,
+            fragment_radius: Distance::from_micrometers(1),
+        }
+    }
+}
+
+impl DebrisConfig {
+    pub fn population(&self, kind: DebrisKind) -> &DebrisPopulation {
+        match kind {
+            DebrisKind::FastIon => &self.fast_ions,
+            DebrisKind::Neutral => &self.neutrals,
+            DebrisKind::Microparticle => &self.microparticles,
+        }
+    }
+}
+
+/// Hydrogen buffer gas filling the source vessel
+#[derive(Resource, Debug, Clone)]
+pub struct BufferGasConfig {
+    /// H2 pressure (Pa)
+    pub pressure: f32,
+    /// Gas temperature (K)
+    pub temperature: f32,
+    /// Stopping coefficient κ in dE/dx = -κ·(n/n_ref)·√E, in √eV per metre per Pa
+    /// at the reference temperature; calibrated so a 3 keV tin ion stops in ~20 cm at 100 Pa
+    pub stopping_coefficient: f32,
+    /// Energy below which atoms are considered thermalized and carried off by the gas flow (eV)
+    pub thermal_energy_ev: f32,
+}
+
+impl Default for BufferGasConfig {
+    fn default() -> Self {
+        Self {
+            pressure: 100.0,
+            temperature: ThermalState::AMBIENT,
+            stopping_coefficient: 5.5,
+            thermal_energy_ev: 0.1,
+        }
+    }
+}
+
+impl BufferGasConfig {
+    /// Pressure scaled to the reference temperature, proportional to number density (Pa)
+    fn density_equivalent_pressure(&self) -> f32 {
+        self.pressure * ThermalState::AMBIENT / self.temperature
+    }
+
+    /// Kinetic energy per atom after travelling `path` metres through the gas (eV)
+    ///
+    /// With electronic stopping ∝ velocity, √E falls linearly with path length.
+    pub fn slow_atom(&self, energy_ev: f32, path: f32) -> f32 {
+        let sqrt_e = energy_ev.max(0.0).sqrt() - 0.5 * self.stopping_coefficient * self.density_equivalent_pressure() * path;
+        sqrt_e.max(0.0).powi(2)
+    }
+
+    /// Distance an atom with the given energy travels before stopping (m)
+    pub fn atom_range(&self, energy_ev: f32) -> f32 {
+        let loss = self.stopping_coefficient * self.density_equivalent_pressure();
+        if loss <= 0.0 {
+            return f32::INFINITY;
+        }
+        2.0 * energy_ev.max(0.0).sqrt() / loss
+    }
+
+    /// Epstein drag rate on a microparticle of the given radius (1/s)
+    pub fn epstein_drag_rate(&self, fragment_radius: Distance) -> f32 {
+        let r = fragment_radius.as_meters_f64();
+        if r <= 0.0 {
+            return 0.0;
+        }
+        let t = self.temperature as f64;
+        let gas_density = self.pressure as f64 * HYDROGEN_MOLECULE_MASS / (BOLTZMANN * t);
+        let thermal_speed = (8.0 * BOLTZMANN * t / (std::f64::consts::PI * HYDROGEN_MOLECULE_MASS)).sqrt();
+        (4.0 / 3.0 * gas_density * thermal_speed / (TIN_DENSITY * r)) as f32
+    }
+}
+
+/// Spherical vacuum vessel wall that collects anything not stopped earlier
+#[derive(Resource, Debug, Clone)]
+pub struct ChamberGeometry {
+    pub center: Position3D,
+    pub wall_radius: Distance,
+}
+
+impl Default for ChamberGeometry {
+    fn default() -> Self {
+        Self {
+            center: Position3D::zero(),
+            wall_radius: Distance::from_meters(6),
+        }
+    }
+}
+
+/// Tin accumulated on a surface
+#[derive(Component, Debug, Clone, Default)]
+pub struct SurfaceDeposit {
+    /// Deposited mass per debris population (kg), indexed like `DebrisKind::ALL`
+    pub mass_by_kind: [f64; 3],
+    /// Macro-particle impacts per debris population
+    pub impacts_by_kind: [u64; 3],
+}
+
+impl SurfaceDeposit {
+    pub fn record(&mut self, particle: &DebrisParticle) {
+        self.mass_by_kind[particle.kind.index()] += particle.mass;
+        self.impacts_by_kind[particle.kind.index()] += 1;
+    }
+
+    pub fn total_mass(&self) -> f64 {
+        self.mass_by_kind.iter().sum()
+    }
+}
+
+/// Running totals of debris generation, stopping and deposition
+#[derive(Resource, Default, Debug)]
+pub struct DebrisStatistics {
+    /// Mass released per debris population (kg)
+    pub released_mass: [f64; 3],
+    /// Mass thermalized by the buffer gas and carried off (kg)
+    pub thermalized_mass: [f64; 3],
+    /// Mass deposited on mirrors (kg)
+    pub mirror_deposited_mass: [f64; 3],
+    /// Mass deposited on the chamber wall (kg)
+    pub wall_deposited_mass: [f64; 3],
+    /// Debris macro-particles currently in flight
+    pub active_particles: u32,
+}
+
+impl DebrisStatistics {
+    pub fn total(values: &[f64; 3]) -> f64 {
+        values.iter().sum()
+    }
+
+    /// Fraction of released tin that ended up on mirrors
+    pub fn mirror_deposition_fraction(&self) -> f64 {
+        let released = Self::total(&self.released_mass);
+        if released > 0.0 {
+            Self::total(&self.mirror_deposited_mass) / released
+        } else {
+            0.0
+        }
+    }
+}
+
+/// System that fragments collapsing plasma into debris macro-particles
+pub fn plasma_to_debris_system(
+    mut commands: Commands,
+    time: Res<SimulationTime>,
+    config: Res<DebrisConfig>,
+    mut stats: ResMut<DebrisStatistics>,
+    mut query: Query<(&mut DropletState, &Lifetime, &Position, &Mass, &PlasmaAxis)>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (mut state, lifetime, pos, mass, axis) in query.iter_mut() {
+        // Plasma collapses on the tick its lifetime runs out
+        if *state != DropletState::Plasma || lifetime.remaining_seconds > time.delta_seconds {
+            continue;
+        }
+        *state = DropletState::Debris;
+
+        for kind in DebrisKind::ALL {
+            let population = config.population(kind);
+            if population.macro_particles == 0 {
+                continue;
+            }
+            let population_mass = mass.0 as f64 * population.mass_fraction as f64;
+            stats.released_mass[kind.index()] += population_mass;
+
+            for _ in 0..population.macro_particles {
+                let mut particle = DebrisParticle {
+                    kind,
+                    mass: population_mass / population.macro_particles as f64,
+                    kinetic_energy_ev: 0.0,
+                    fragment_radius: config.fragment_radius,
+                };
+                let speed = match kind {
+                    DebrisKind::Microparticle => {
+                        let speed = Normal::new(config.microparticle_speed, config.microparticle_speed_jitter)
+                            .map(|d| d.sample(&mut rng))
+                            .unwrap_or(config.microparticle_speed)
+                            .max(1.0);
+                        particle.kinetic_energy_ev = particle.energy_for_speed(speed);
+                        speed
+                    }
+                    _ => {
+                        let energy = LogNormal::new(population.median_energy_ev.ln(), population.energy_spread)
+                            .map(|d| d.sample(&mut rng))
+                            .unwrap_or(population.median_energy_ev);
+                        particle.kinetic_energy_ev = energy;
+                        particle.speed_for_energy(energy)
+                    }
+                };
+                let direction = population.pattern.sample_direction(axis.0, &mut rng);
+
+                commands.spawn((
+                    Position(pos.0),
+                    Velocity(direction * speed),
+                    particle,
+                    EntityType::Debris,
+                ));
+            }
+        }
+    }
+}
+
+/// System that slows debris in the buffer gas and removes thermalized atoms
+pub fn debris_stopping_system(
+    mut commands: Commands,
+    time: Res<SimulationTime>,
+    gas: Res<BufferGasConfig>,
+    mut stats: ResMut<DebrisStatistics>,
+    mut query: Query<(Entity, &mut Velocity, &mut DebrisParticle)>,
+) {
+    let mut active = 0;
+    for (entity, mut velocity, mut particle) in query.iter_mut() {
+        let speed = velocity.speed();
+        if speed <= 0.0 {
+            continue;
+        }
+
+        match particle.kind {
+            DebrisKind::Microparticle => {
+                let drag = gas.epstein_drag_rate(particle.fragment_radius);
+                velocity.0 *= (-drag * time.delta_seconds).exp();
+                particle.kinetic_energy_ev = particle.energy_for_speed(velocity.speed());
+                active += 1;
+            }
+            _ => {
+                let path = speed * time.delta_seconds;
+                let energy = gas.slow_atom(particle.kinetic_energy_ev, path);
+                if energy <= gas.thermal_energy_ev {
+                    stats.thermalized_mass[particle.kind.index()] += particle.mass;
+                    commands.entity(entity).despawn();
+                    continue;
+                }
+                particle.kinetic_energy_ev = energy;
+                velocity.0 = velocity.0 / speed * particle.speed_for_energy(energy);
+                active += 1;
+            }
+        }
+    }
+    stats.active_particles = active;
+}
+
+/// System that deposits debris on the first mirror or wall it reaches this tick
+pub fn debris_deposition_system(
+    mut commands: Commands,
+    time: Res<SimulationTime>,
+    chamber: Res<ChamberGeometry>,
+    mut stats: ResMut<DebrisStatistics>,
+    debris: Query<(Entity, &Position, &Velocity, &DebrisParticle)>,
+    mut mirrors: Query<(&Position, &MirrorSurface, &mut SurfaceDeposit)>,
+) {
+    let wall_radius = chamber.wall_radius.as_meters_f64() as f32;
+    let chamber_center = chamber.center.to_vec3();
+
+    for (entity, pos, velocity, particle) in debris.iter() {
+        let step = velocity.speed() * time.delta_seconds;
+        if step <= 0.0 {
+            continue;
+        }
+        let direction = velocity.0 / velocity.speed();
+
+        let nearest_mirror = mirrors
+            .iter_mut()
+            .filter_map(|(mirror_pos, surface, deposit)| {
+                match surface.geometry.ray_intersection(pos.0, direction) {
+                    (true, Some(point), t) if t <= step && surface.in_aperture(mirror_pos.0, point) => Some((t, deposit)),
+                    _ => None,
+                }
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+
+        if let Some((_, mut deposit)) = nearest_mirror {
+            deposit.record(particle);
+            stats.mirror_deposited_mass[particle.kind.index()] += particle.mass;
+            commands.entity(entity).despawn();
+        } else if (pos.0.to_vec3() + direction * step - chamber_center).length() >= wall_radius {
+            stats.wall_deposited_mass[particle.kind.index()] += particle.mass;
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ion_range_scales_with_pressure() {
+        let gas = BufferGasConfig::default();
+        let range = gas.atom_range(3_000.0);
+        assert!(range > 0.15 && range < 0.25); // ~20 cm at 100 Pa
+
+        let dense = BufferGasConfig { pressure: 200.0, ..gas.clone() };
+        assert!((dense.atom_range(3_000.0) - range / 2.0).abs() < 1e-4);
+
+        // Travelling the full range leaves no energy
+        assert!(gas.slow_atom(3_000.0, range) < 1e-3);
+        assert!(gas.slow_atom(3_000.0, range / 2.0) > 0.0);
+    }
+
+    #[test]
+    fn test_microparticle_drag_is_weak() {
+        let gas = BufferGasConfig::default();
+        let rate = gas.epstein_drag_rate(Distance::from_micrometers(1));
+        // μm tin fragments keep most of their speed over millisecond flights
+        assert!(rate > 1.0 && rate < 100.0);
+    }
+
+    #[test]
+    fn test_debris_deposits_on_mirror() {
+        let mut world = World::new();
+        world.insert_resource(SimulationTime::default());
+        world.insert_resource(ChamberGeometry::default());
+        world.insert_resource(DebrisStatistics::default());
+
+        let mirror = world.spawn((
+            Position(Position3D::zero()),
+            MirrorSurface {
+                geometry: crate::optics::SurfaceGeometry::Spherical {
+                    radius: Distance::from_meters(1),
+                    center: Position3D::zero(),
+                },
+                orientation: glam::Quat::IDENTITY,
+                aperture: crate::optics::Aperture::Circular { radius: 1.5 },
+            },
+            SurfaceDeposit::default(),
+        )).id();
+
+        let particle = DebrisParticle {
+            kind: DebrisKind::FastIon,
+            mass: 1e-12,
+            kinetic_energy_ev: 1_000.0,
+            fragment_radius: Distance::ZERO,
+        };
+        // 2 cm from the mirror, moving outward at 40 km/s (4 cm per tick)
+        world.spawn((
+            Position(Position3D::new(Distance::from_millimeters(980), Distance::ZERO, Distance::ZERO)),
+            Velocity::new(40_000.0, 0.0, 0.0),
+            particle,
+        ));
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(debris_deposition_system);
+        schedule.run(&mut world);
+
+        let deposit = world.get::<SurfaceDeposit>(mirror).unwrap();
+        assert_eq!(deposit.impacts_by_kind, [1, 0, 0]);
+        assert_eq!(deposit.total_mass(), 1e-12);
+        assert_eq!(world.query::<&DebrisParticle>().iter(&world).count(), 0);
+    }
+}
@@ -0,0 +1,344 @@
+//! Dose control subsystem: EUV energy sensing near the intermediate focus and
+//! pulse-by-pulse energy control over a moving exposure window
+
+use std::collections::VecDeque;
+use bevy_ecs::prelude::*;
+use rand_distr::{Distribution, Normal};
+use crate::plasma::PlasmaPulseEvent;
+use crate::units::{Distance, Position3D};
+
+/// Online mean / variance accumulator (Welford)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunningStatistics {
+    pub count: u64,
+    mean: f64,
+    m2: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl RunningStatistics {
+    pub fn push(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Combines with statistics accumulated separately (Chan et al.)
+    pub fn merge(&mut self, other: &RunningStatistics) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * (self.count as f64 * other.count as f64) / count as f64;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.count = count;
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Sample standard deviation
+    pub fn std_dev(&self) -> f64 {
+        if self.count > 1 {
+            (self.m2 / (self.count - 1) as f64).sqrt()
+        } else {
+            0.0
+        }
+    }
+
+    /// Largest deviation from zero in either direction
+    pub fn max_abs(&self) -> f64 {
+        self.min.abs().max(self.max.abs())
+    }
+}
+
+/// Marks a droplet whose main-pulse slot the dose controller skipped
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PulseSkipped;
+
+/// EUV energy sensor near the intermediate focus
+#[derive(Resource, Debug, Clone)]
+pub struct EuvEnergySensor {
+    /// Fraction of the in-band plasma energy (2π sr) reaching the intermediate focus
+    pub transmission: f32,
+    /// Relative measurement noise (1σ)
+    pub noise: f32,
+    /// Centre of the sensor head, which traced light can reach
+    pub position: Position3D,
+    /// Radius of the (spherical) sensor head
+    pub radius: Distance,
+}
+
+impl Default for EuvEnergySensor {
+    fn default() -> Self {
+        Self {
+            transmission: 0.2,
+            noise: 0.005, // 0.5% rms
+            // Off the laser axis on the laser side of the plasma, where emission peaks
+            position: Position3D::new(Distance::ZERO, Distance::from_millimeters(100), Distance::from_millimeters(-100)),
+            radius: Distance::from_millimeters(5),
+        }
+    }
+}
+
+impl EuvEnergySensor {
+    /// In-band energy delivered to the intermediate focus (J)
+    pub fn delivered(&self, in_band_energy_2pi: f32) -> f32 {
+        in_band_energy_2pi * self.transmission
+    }
+
+    /// Noisy reading of the delivered energy (J)
+    pub fn measure(&self, delivered: f32, rng: &mut impl rand::Rng) -> f32 {
+        let noise = Normal::new(0.0, self.noise).map(|d| d.sample(rng)).unwrap_or(0.0);
+        (delivered * (1.0 + noise)).max(0.0)
+    }
+}
+
+/// Dose-error statistics in the terms lithography engineers quote
+#[derive(Debug, Clone, Default)]
+pub struct DoseStatistics {
+    /// Pulses fired under dose control
+    pub pulses: u64,
+    /// Pulse slots deliberately skipped
+    pub skipped_pulses: u64,
+    /// Relative error of the delivered dose over each full moving window
+    pub window_dose_error: RunningStatistics,
+    /// Delivered per-pulse energy at the intermediate focus (J)
+    pub pulse_dose: RunningStatistics,
+    /// Commanded main-pulse energy (J)
+    pub pulse_energy: RunningStatistics,
+}
+
+impl DoseStatistics {
+    /// Mean window dose error (%)
+    pub fn mean_dose_error_percent(&self) -> f64 {
+        self.window_dose_error.mean() * 100.0
+    }
+
+    /// 3σ window dose error (%), the usual dose-stability figure
+    pub fn dose_stability_3sigma_percent(&self) -> f64 {
+        3.0 * self.window_dose_error.std_dev() * 100.0
+    }
+
+    /// Worst window dose error (%)
+    pub fn max_dose_error_percent(&self) -> f64 {
+        self.window_dose_error.max_abs() * 100.0
+    }
+
+    /// Pulse-to-pulse energy stability at the intermediate focus, 1σ (%)
+    pub fn pulse_stability_percent(&self) -> f64 {
+        let mean = self.pulse_dose.mean();
+        if mean > 0.0 {
+            self.pulse_dose.std_dev() / mean * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Moving-window dose controller
+///
+/// After every pulse the controller chooses the next main-pulse energy so the
+/// measured dose over the last `window_pulses` slots converges on the target.
+/// If even the minimum pulse energy would overdose the window, the next pulse is skipped.
+/// Lives on its laser channel entity, so every channel controls its own dose.
+#[derive(Component, Debug, Clone)]
+pub struct DoseController {
+    /// Pulses per exposure window (slit width in pulses)
+    pub window_pulses: usize,
+    /// Target in-band energy per pulse at the intermediate focus (J)
+    pub target_pulse_dose: f32,
+    /// Main-pulse energy before any feedback (J)
+    pub nominal_pulse_energy: f32,
+    /// Laser energy limits (J)
+    pub min_pulse_energy: f32,
+    pub max_pulse_energy: f32,
+    /// Fraction of the window error corrected on the next pulse
+    pub correction_gain: f32,
+    /// Smoothing factor for the dose-per-joule estimate
+    pub gain_smoothing: f32,
+    /// Estimated intermediate-focus dose per joule of main pulse
+    dose_per_joule: f32,
+    measured_window: VecDeque<f32>,
+    delivered_window: VecDeque<f32>,
+    next_pulse_energy: Option<f32>,
+    pub stats: DoseStatistics,
+}
+
+impl DoseController {
+    pub fn new(window_pulses: usize, target_pulse_dose: f32, nominal_pulse_energy: f32) -> Self {
+        Self {
+            window_pulses: window_pulses.max(1),
+            target_pulse_dose,
+            nominal_pulse_energy,
+            min_pulse_energy: nominal_pulse_energy * 0.5,
+            max_pulse_energy: nominal_pulse_energy * 1.5,
+            correction_gain: 0.5,
+            gain_smoothing: 0.2,
+            dose_per_joule: 0.0,
+            measured_window: VecDeque::with_capacity(window_pulses),
+            delivered_window: VecDeque::with_capacity(window_pulses),
+            next_pulse_energy: Some(nominal_pulse_energy),
+            stats: DoseStatistics::default(),
+        }
+    }
+
+    /// Energy for the next main pulse, or `None` if it should be skipped
+    pub fn next_pulse_energy(&self) -> Option<f32> {
+        self.next_pulse_energy
+    }
+
+    /// Records a fired pulse and plans the next one
+    pub fn record_pulse(&mut self, pulse_energy: f32, delivered: f32, measured: f32) {
+        if pulse_energy > 0.0 {
+            let observed = measured / pulse_energy;
+            self.dose_per_joule = if self.dose_per_joule > 0.0 {
+                self.dose_per_joule + self.gain_smoothing * (observed - self.dose_per_joule)
+            } else {
+                observed
+            };
+        }
+        self.stats.pulses += 1;
+        self.stats.pulse_energy.push(pulse_energy as f64);
+        self.stats.pulse_dose.push(delivered as f64);
+        self.push_slot(delivered, measured);
+    }
+
+    /// Records a skipped pulse slot
+    pub fn record_skip(&mut self) {
+        self.stats.skipped_pulses += 1;
+        self.push_slot(0.0, 0.0);
+    }
+
+    /// Starts a fresh exposure window, e.g. at the beginning of a burst
+    pub fn reset_window(&mut self) {
+        self.measured_window.clear();
+        self.delivered_window.clear();
+        self.plan_next();
+    }
+
+    fn push_slot(&mut self, delivered: f32, measured: f32) {
+        self.measured_window.push_back(measured);
+        self.delivered_window.push_back(delivered);
+        while self.measured_window.len() > self.window_pulses {
+            self.measured_window.pop_front();
+            self.delivered_window.pop_front();
+        }
+
+        if self.delivered_window.len() == self.window_pulses {
+            let target = self.target_pulse_dose as f64 * self.window_pulses as f64;
+            let delivered: f64 = self.delivered_window.iter().map(|&d| d as f64).sum();
+            self.stats.window_dose_error.push((delivered - target) / target);
+        }
+        self.plan_next();
+    }
+
+    fn plan_next(&mut self) {
+        if self.dose_per_joule <= 0.0 {
+            self.next_pulse_energy = Some(self.nominal_pulse_energy);
+            return;
+        }
+
+        // Dose the next pulse must supply so the window (with the oldest slot
+        // dropped) lands on target; missing slots count as on-target
+        let kept = self.measured_window.len().min(self.window_pulses - 1);
+        let recent: f32 = self.measured_window.iter().rev().take(kept).sum();
+        let needed = self.target_pulse_dose * (kept + 1) as f32 - recent;
+        let desired = self.target_pulse_dose + self.correction_gain * (needed - self.target_pulse_dose);
+
+        let min_dose = self.min_pulse_energy * self.dose_per_joule;
+        self.next_pulse_energy = if desired < 0.5 * min_dose {
+            None
+        } else {
+            Some((desired / self.dose_per_joule).clamp(self.min_pulse_energy, self.max_pulse_energy))
+        };
+    }
+}
+
+/// System that measures each plasma pulse at the intermediate focus and updates
+/// the controller of the channel that fired it
+pub fn dose_control_system(
+    mut pulses: EventReader<PlasmaPulseEvent>,
+    sensor: Res<EuvEnergySensor>,
+    mut controllers: Query<&mut DoseController>,
+) {
+    let mut rng = rand::thread_rng();
+    for pulse in pulses.read() {
+        let Ok(mut controller) = controllers.get_mut(pulse.channel) else {
+            continue;
+        };
+        let delivered = sensor.delivered(pulse.emission.energy_2pi_sr);
+        let measured = sensor.measure(delivered, &mut rng);
+        controller.record_pulse(pulse.emission.pulse_energy, delivered, measured);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_statistics() {
+        let mut stats = RunningStatistics::default();
+        for v in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            stats.push(v);
+        }
+        assert_eq!(stats.mean(), 5.0);
+        assert!((stats.std_dev() - 2.138).abs() < 1e-3);
+        assert_eq!(stats.max_abs(), 9.0);
+
+        let (mut first, mut second) = (RunningStatistics::default(), RunningStatistics::default());
+        for v in [2.0, 4.0, 4.0] {
+            first.push(v);
+        }
+        for v in [4.0, 5.0, 5.0, 7.0, 9.0] {
+            second.push(v);
+        }
+        first.merge(&second);
+        assert_eq!(first.count, 8);
+        assert!((first.mean() - 5.0).abs() < 1e-12);
+        assert!((first.std_dev() - stats.std_dev()).abs() < 1e-12);
+        assert_eq!(first.min, 2.0);
+    }
+
+    #[test]
+    fn test_controller_corrects_low_yield() {
+        let mut controller = DoseController::new(10, 1.0e-3, 0.4);
+        // Plant delivers 2 mJ/J: nominal 0.4 J only gives 0.8 mJ against a 1 mJ target
+        for _ in 0..200 {
+            let energy = controller.next_pulse_energy().unwrap();
+            let dose = energy * 2.0e-3;
+            controller.record_pulse(energy, dose, dose);
+        }
+        let energy = controller.next_pulse_energy().unwrap();
+        assert!((energy - 0.5).abs() < 1e-3);
+        assert!(controller.stats.window_dose_error.count > 0);
+    }
+
+    #[test]
+    fn test_controller_skips_when_overdosed() {
+        let mut controller = DoseController::new(4, 1.0e-3, 0.4);
+        // A huge pulse overdoses the window; even the minimum energy is too much
+        controller.record_pulse(0.4, 4.0e-3, 4.0e-3);
+        assert_eq!(controller.next_pulse_energy(), None);
+        controller.record_skip();
+        assert_eq!(controller.stats.skipped_pulses, 1);
+    }
+}
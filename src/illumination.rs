@@ -0,0 +1,476 @@
+//! Illuminator: field and pupil facet mirrors between the intermediate focus
+//! and the reticle, and the pupil fill they produce
+//!
+//! The field facet mirror (FFM) sits in the beam diverging from the
+//! intermediate focus (IF). Each field facet is a small concave mirror that
+//! images the IF onto one pupil facet, and each pupil facet images its field
+//! facet onto the reticle, so the slit is lit by the superposition of every
+//! field facet. The pupil facet mirror (PFM) lies in a pupil plane: where a
+//! pupil facet sits sets the angle its light reaches the reticle at, so
+//! choosing which pupil facets the field facets are tilted to sets the pupil
+//! fill. Field facets left without a lit pupil facet are parked, sending
+//! their light past the PFM.
+
+use std::f32::consts::{PI, TAU};
+use bevy_ecs::prelude::*;
+use glam::{Mat3, Quat, Vec2, Vec3};
+use crate::units::Position3D;
+use crate::components::*;
+use crate::debris::SurfaceDeposit;
+use crate::contamination::ContaminationState;
+use crate::coating::{MultilayerCoating, MultilayerStack};
+use crate::scatter::SurfaceRoughness;
+use crate::optics::{Aperture, MirrorSurface, SagProfile, SurfaceGeometry};
+use crate::raytracing::RayTracingConfig;
+use crate::raytracing::monitor::{Facet, FacetArray, FacetGrid, FacetShape, IlluminationMonitor, PupilFrame};
+use crate::reticle::Reticle;
+
+/// Pole placement of a dipole fill
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoleAxis {
+    /// Poles across the slit
+    X,
+    /// Poles along the scan
+    Y,
+}
+
+/// Illuminated region of the pupil in σ coordinates (1 = the reticle-side NA)
+#[derive(Debug, Clone, PartialEq)]
+pub enum PupilFill {
+    Conventional { sigma: f32 },
+    Annular { inner: f32, outer: f32 },
+    /// Two poles of an annulus, each `opening` degrees wide
+    Dipole { inner: f32, outer: f32, opening: f32, axis: PoleAxis },
+    /// Four poles of an annulus on the diagonals (quasar), each `opening` degrees wide
+    Quadrupole { inner: f32, outer: f32, opening: f32 },
+    /// Circular spots of the given radius at arbitrary pupil positions
+    Freeform { poles: Vec<(Vec2, f32)> },
+}
+
+impl PupilFill {
+    pub fn contains(&self, sigma: Vec2) -> bool {
+        // Whether `sigma` lies within `opening` degrees of one of `count` evenly spaced pole centres
+        let in_poles = |first: f32, count: usize, opening: f32| {
+            let spacing = TAU / count as f32;
+            let offset = (sigma.y.atan2(sigma.x) - first).rem_euclid(spacing);
+            offset.min(spacing - offset) <= opening.to_radians() / 2.0
+        };
+        let r = sigma.length();
+        match self {
+            PupilFill::Conventional { sigma } => r <= *sigma,
+            PupilFill::Annular { inner, outer } => r >= *inner && r <= *outer,
+            PupilFill::Dipole { inner, outer, opening, axis } => {
+                let first = match axis {
+                    PoleAxis::X => 0.0,
+                    PoleAxis::Y => PI / 2.0,
+                };
+                r >= *inner && r <= *outer && in_poles(first, 2, *opening)
+            }
+            PupilFill::Quadrupole { inner, outer, opening } => {
+                r >= *inner && r <= *outer && in_poles(PI / 4.0, 4, *opening)
+            }
+            PupilFill::Freeform { poles } => poles.iter().any(|(center, radius)| sigma.distance(*center) <= *radius),
+        }
+    }
+}
+
+/// Parses `conventional:σ`, `annular:σ_in,σ_out`, `dipole-x:σ_in,σ_out,opening`,
+/// `dipole-y:...`, `quadrupole:σ_in,σ_out,opening` (degrees) or
+/// `freeform:σx,σy,r;σx,σy,r;...`
+impl std::str::FromStr for PupilFill {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, values) = s.split_once(':').ok_or_else(|| format!("missing ':' in pupil fill '{s}'"))?;
+        let numbers = |text: &str| -> Result<Vec<f32>, String> {
+            text.split(',')
+                .map(|v| v.trim().parse::<f32>().map_err(|e| format!("bad number '{v}': {e}")))
+                .collect()
+        };
+        let ring = |inner: f32, outer: f32| {
+            if (0.0..outer).contains(&inner) {
+                Ok(())
+            } else {
+                Err(format!("pupil fill '{s}' needs 0 <= inner < outer"))
+            }
+        };
+        match (kind.trim(), numbers(values).as_deref()) {
+            ("conventional", Ok(&[sigma])) if sigma > 0.0 => Ok(PupilFill::Conventional { sigma }),
+            ("annular", Ok(&[inner, outer])) => ring(inner, outer).map(|_| PupilFill::Annular { inner, outer }),
+            (kind @ ("dipole-x" | "dipole-y"), Ok(&[inner, outer, opening])) => {
+                let axis = if kind == "dipole-x" { PoleAxis::X } else { PoleAxis::Y };
+                ring(inner, outer).map(|_| PupilFill::Dipole { inner, outer, opening, axis })
+            }
+            ("quadrupole", Ok(&[inner, outer, opening])) => {
+                ring(inner, outer).map(|_| PupilFill::Quadrupole { inner, outer, opening })
+            }
+            ("freeform", _) => {
+                let poles = values
+                    .split(';')
+                    .map(|pole| match numbers(pole)?.as_slice() {
+                        &[x, y, radius] => Ok((Vec2::new(x, y), radius)),
+                        _ => Err(format!("freeform pole '{pole}' needs σx, σy and a radius")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(PupilFill::Freeform { poles })
+            }
+            _ => Err(format!("unrecognised pupil fill '{s}'")),
+        }
+    }
+}
+
+/// Writes the form accepted by `FromStr`
+impl std::fmt::Display for PupilFill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PupilFill::Conventional { sigma } => write!(f, "conventional:{sigma}"),
+            PupilFill::Annular { inner, outer } => write!(f, "annular:{inner},{outer}"),
+            PupilFill::Dipole { inner, outer, opening, axis } => {
+                let axis = match axis {
+                    PoleAxis::X => "x",
+                    PoleAxis::Y => "y",
+                };
+                write!(f, "dipole-{axis}:{inner},{outer},{opening}")
+            }
+            PupilFill::Quadrupole { inner, outer, opening } => write!(f, "quadrupole:{inner},{outer},{opening}"),
+            PupilFill::Freeform { poles } => {
+                let poles: Vec<String> = poles.iter().map(|(c, r)| format!("{},{},{r}", c.x, c.y)).collect();
+                write!(f, "freeform:{}", poles.join(";"))
+            }
+        }
+    }
+}
+
+/// Layout and facet design of the illuminator
+///
+/// The chief ray leaves the intermediate focus along +x and zig-zags in the
+/// xy-plane: field facet mirror, pupil facet mirror, then the reticle. The
+/// slit runs along world z.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IlluminatorDesign {
+    pub intermediate_focus: Vec3,
+    /// Intermediate focus to the field facet mirror (m)
+    pub field_mirror_distance: f32,
+    /// Field facet mirror to the pupil facet mirror (m)
+    pub pupil_mirror_distance: f32,
+    /// Pupil facet mirror to the reticle (m)
+    pub reticle_distance: f32,
+    /// Chief-ray angle of incidence on both facet mirrors (deg)
+    pub facet_incidence: f32,
+    /// Chief-ray angle of incidence on the reticle (deg)
+    pub reticle_incidence: f32,
+    pub field_facets: FacetGrid,
+    pub pupil_facets: FacetGrid,
+    /// Reticle-side numerical aperture at σ = 1
+    pub numerical_aperture: f32,
+    pub slit_half_width: f32,
+    pub fill: PupilFill,
+}
+
+impl IlluminatorDesign {
+    /// Slit-shaped field facets imaged 1:1 onto the reticle, and a pupil
+    /// facet mirror filling NA 0.0825 (0.33 at the wafer over 4×)
+    pub fn behind(intermediate_focus: Position3D, fill: PupilFill) -> Self {
+        Self {
+            intermediate_focus: intermediate_focus.to_vec3(),
+            field_mirror_distance: 0.5,
+            pupil_mirror_distance: 1.0,
+            reticle_distance: 1.0,
+            facet_incidence: 10.0,
+            reticle_incidence: 6.0,
+            field_facets: FacetGrid {
+                columns: 4,
+                rows: 16,
+                half_size: Vec2::new(0.075, 0.018),
+                pitch: Vec2::new(0.152, 0.038),
+            },
+            pupil_facets: FacetGrid {
+                columns: 17,
+                rows: 17,
+                half_size: Vec2::splat(0.0045),
+                pitch: Vec2::splat(0.0097),
+            },
+            numerical_aperture: 0.0825,
+            slit_half_width: 0.07,
+            fill,
+        }
+    }
+
+    /// Places the mirrors and tilts the facets to light the pupil fill
+    ///
+    /// Lit pupil facets are spread evenly over the fill when there are more
+    /// of them than field facets; field facets left over are parked.
+    pub fn build(&self) -> Result<Illuminator, String> {
+        // Chief ray: each facet mirror turns it back by 180° − 2θ, the reticle the other way
+        let turn = |incidence: f32| Quat::from_rotation_z(PI - 2.0 * incidence.to_radians());
+        let to_field = Vec3::X;
+        let to_pupil = turn(self.facet_incidence) * to_field;
+        let to_reticle = turn(self.facet_incidence) * to_pupil;
+        let from_reticle = turn(self.reticle_incidence).inverse() * to_reticle;
+        let field_center = self.intermediate_focus + to_field * self.field_mirror_distance;
+        let pupil_center = field_center + to_pupil * self.pupil_mirror_distance;
+        let reticle_center = pupil_center + to_reticle * self.reticle_distance;
+
+        // Local z along the surface normal towards the incoming light, local x along the slit
+        let frame = |incoming: Vec3, outgoing: Vec3| {
+            let z = (outgoing - incoming).normalize();
+            let x = (Vec3::Z - z * z.z).normalize();
+            Quat::from_mat3(&Mat3::from_cols(x, z.cross(x), z))
+        };
+        let field_frame = frame(to_field, to_pupil);
+        let pupil_frame = frame(to_pupil, to_reticle);
+        let pupil = PupilFrame::new(pupil_center, to_reticle, Vec3::Z, self.numerical_aperture);
+
+        let mut field_facets = FacetArray::grid(&self.field_facets);
+        let mut pupil_facets = FacetArray::grid(&self.pupil_facets);
+        let field_world = |facet: &Facet| field_center + field_frame * facet.center.extend(0.0);
+        let pupil_world = |facet: &Facet| pupil_center + pupil_frame * facet.center.extend(0.0);
+
+        let lit: Vec<usize> = (0..pupil_facets.facets.len())
+            .filter(|&j| {
+                let position = pupil_world(&pupil_facets.facets[j]);
+                self.fill.contains(pupil.sigma(reticle_center, reticle_center - position))
+            })
+            .collect();
+        if lit.is_empty() {
+            return Err(format!("pupil fill {} lights no pupil facet", self.fill));
+        }
+        let field_count = field_facets.facets.len();
+        let pairs = field_count.min(lit.len());
+
+        // Field facets image the IF onto their pupil facet; a pupil facet is
+        // tilted and curved to image its field facet onto the reticle
+        let field_local = |point: Vec3| field_frame.inverse() * (point - field_center);
+        let mut used = vec![false; field_count];
+        for k in 0..pairs {
+            let i = k * field_count / pairs;
+            let j = lit[k * lit.len() / pairs];
+            used[i] = true;
+            let field_position = field_world(&field_facets.facets[i]);
+            let pupil_position = pupil_world(&pupil_facets.facets[j]);
+            field_facets.facets[i].shape = FacetShape::Ellipsoidal {
+                source: field_local(self.intermediate_focus),
+                target: field_local(pupil_position),
+            };
+            let (incoming, outgoing) = (pupil_position - field_position, reticle_center - pupil_position);
+            let normal = pupil_frame.inverse() * (outgoing.normalize() - incoming.normalize());
+            let (a, b) = (incoming.length(), outgoing.length());
+            pupil_facets.facets[j].shape = FacetShape::Spherical {
+                tilt: Facet::tilt_towards(normal.normalize()),
+                radius: 2.0 * a * b / (a + b),
+            };
+        }
+        // Parked facets send their light well clear of the pupil facet mirror
+        let pupil_extent = self.pupil_facets.half_extent();
+        let dump = pupil_center + pupil_frame * Vec3::new(0.0, 3.0 * pupil_extent.y, 0.0);
+        for (facet, _) in field_facets.facets.iter_mut().zip(&used).filter(|(_, used)| !**used) {
+            facet.shape = FacetShape::Ellipsoidal { source: field_local(self.intermediate_focus), target: field_local(dump) };
+        }
+
+        let flat = |position: Vec3, orientation: Quat, half_extent: Vec2| {
+            (Position3D::from_vec3(position), MirrorSurface {
+                geometry: SurfaceGeometry::Sag {
+                    vertex: Position3D::from_vec3(position),
+                    orientation,
+                    profile: SagProfile::conic(f64::INFINITY, 0.0, half_extent.length() as f64),
+                },
+                orientation,
+                aperture: Aperture::Rectangular { half_width: half_extent.x, half_height: half_extent.y },
+            })
+        };
+        let (field_position, field_surface) = flat(field_center, field_frame, self.field_facets.half_extent());
+        let (pupil_position, pupil_surface) = flat(pupil_center, pupil_frame, pupil_extent);
+        let reticle = flat(
+            reticle_center,
+            frame(to_reticle, from_reticle),
+            Vec2::splat(1.5 * self.slit_half_width),
+        );
+
+        Ok(Illuminator {
+            field_mirror: FacetMirror { position: field_position, surface: field_surface, facets: field_facets },
+            pupil_mirror: FacetMirror { position: pupil_position, surface: pupil_surface, facets: pupil_facets },
+            reticle,
+            monitor: IlluminationMonitor {
+                intermediate_focus_index: 0,
+                pupil,
+                pupil_bins: 21,
+                slit_center: reticle_center,
+                slit_axis: Vec3::Z,
+                slit_half_width: self.slit_half_width,
+                slit_bins: 20,
+            },
+            fill: self.fill.clone(),
+            lit_pupil_facets: pairs,
+            parked_field_facets: field_count - pairs,
+        })
+    }
+}
+
+/// Flat-based mirror carrying an array of facets
+#[derive(Debug, Clone)]
+pub struct FacetMirror {
+    pub position: Position3D,
+    pub surface: MirrorSurface,
+    pub facets: FacetArray,
+}
+
+/// Built illuminator, ready to spawn after the collector
+#[derive(Resource, Debug, Clone)]
+pub struct Illuminator {
+    pub field_mirror: FacetMirror,
+    pub pupil_mirror: FacetMirror,
+    /// Flat reticle plane closing the illuminator
+    pub reticle: (Position3D, MirrorSurface),
+    /// `intermediate_focus_index` is set when the illuminator joins a sequence
+    pub monitor: IlluminationMonitor,
+    pub fill: PupilFill,
+    pub lit_pupil_facets: usize,
+    pub parked_field_facets: usize,
+}
+
+/// Appends the facet mirrors and an unpatterned reticle to the declared
+/// sequence and starts monitoring the illumination at the reticle
+pub fn spawn_illuminator(
+    mut commands: Commands,
+    illuminator: Res<Illuminator>,
+    mut tracing: ResMut<RayTracingConfig>,
+) {
+    let coating = MultilayerCoating::new(MultilayerStack::mo_si());
+    let intermediate_focus_index = tracing.sequence.len();
+    for mirror in [&illuminator.field_mirror, &illuminator.pupil_mirror] {
+        let entity = commands.spawn((
+            Position(mirror.position),
+            ContaminationState::for_mirror(&mirror.surface, ContaminationState::DEFAULT_CLEANING_RATE),
+            mirror.surface.clone(),
+            mirror.facets.clone(),
+            OpticalMaterial::BRAGG_MIRROR,
+            coating.clone(),
+            SurfaceRoughness::PROJECTION,
+            ThermalState::new(ThermalState::AMBIENT, 2000.0),
+            SurfaceDeposit::default(),
+            EntityType::Mirror,
+        )).id();
+        tracing.sequence.push(entity);
+    }
+    let (position, surface) = &illuminator.reticle;
+    let reticle = commands.spawn((
+        Position(*position),
+        ContaminationState::for_mirror(surface, ContaminationState::DEFAULT_CLEANING_RATE),
+        surface.clone(),
+        OpticalMaterial::BRAGG_MIRROR,
+        Reticle::default(),
+        ThermalState::new(ThermalState::AMBIENT, 500.0),
+        SurfaceDeposit::default(),
+        EntityType::ReticleStage,
+    )).id();
+    tracing.reticle_index = Some(tracing.sequence.len());
+    tracing.sequence.push(reticle);
+    tracing.illumination = Some(IlluminationMonitor { intermediate_focus_index, ..illuminator.monitor.clone() });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pupil_fills() {
+        let dipole: PupilFill = "dipole-x:0.5,0.8,90".parse().unwrap();
+        assert!(dipole.contains(Vec2::new(0.7, 0.1)));
+        assert!(dipole.contains(Vec2::new(-0.7, -0.1)));
+        assert!(!dipole.contains(Vec2::new(0.0, 0.7)));
+        assert!(!dipole.contains(Vec2::new(0.3, 0.0)));
+        let quadrupole: PupilFill = "quadrupole:0.5,0.8,30".parse().unwrap();
+        assert!(quadrupole.contains(Vec2::splat(0.45)));
+        assert!(!quadrupole.contains(Vec2::new(0.65, 0.0)));
+        assert!("annular:0.8,0.5".parse::<PupilFill>().is_err());
+        for text in ["conventional:0.8", "annular:0.5,0.8", "dipole-y:0.6,0.9,60", "quadrupole:0.5,0.8,30", "freeform:0.5,0,0.1;-0.5,0,0.1"] {
+            let fill: PupilFill = text.parse().unwrap();
+            assert_eq!(fill.to_string(), text);
+        }
+    }
+
+    /// Follows a ray by specular reflection off a mirror, taking the normal from its facets
+    fn reflect(origin: Vec3, direction: Vec3, mirror: &FacetMirror) -> Option<(Vec3, Vec3)> {
+        let (_, point, _) = mirror.surface.geometry.ray_intersection(Position3D::from_vec3(origin), direction);
+        let point = point?;
+        let normal = mirror.facets.normal_at(&mirror.surface, mirror.position, point)?;
+        Some((point.to_vec3(), direction - 2.0 * direction.dot(normal) * normal))
+    }
+
+    #[test]
+    fn test_facets_image_intermediate_focus_into_the_fill() {
+        let intermediate_focus = Position3D::from_vec3(Vec3::new(0.5, 0.0, 0.0));
+        let fill: PupilFill = "dipole-x:0.5,0.8,90".parse().unwrap();
+        let illuminator = IlluminatorDesign::behind(intermediate_focus, fill.clone()).build().unwrap();
+        assert!(illuminator.lit_pupil_facets > 10);
+        assert_eq!(illuminator.lit_pupil_facets + illuminator.parked_field_facets, 64);
+
+        let source = intermediate_focus.to_vec3();
+        let reticle_center = illuminator.monitor.slit_center;
+        let mut delivered = 0;
+        for facet in &illuminator.field_mirror.facets.facets {
+            // Chief ray of each field facet, through its centre
+            let target = illuminator.field_mirror.position.to_vec3()
+                + illuminator.field_mirror.surface.orientation * facet.center.extend(0.0);
+            let (point, direction) = reflect(source, (target - source).normalize(), &illuminator.field_mirror).unwrap();
+            // Parked facets miss the pupil facet mirror
+            let Some((point, direction)) = reflect(point, direction, &illuminator.pupil_mirror) else { continue };
+            delivered += 1;
+            // Lands on the reticle centre from a direction inside the fill
+            let to_reticle = (reticle_center - point).normalize();
+            assert!(to_reticle.dot(direction.normalize()) > 1.0 - 1e-6);
+            assert!(fill.contains(illuminator.monitor.pupil.sigma(reticle_center, direction)));
+        }
+        assert_eq!(delivered, illuminator.lit_pupil_facets);
+    }
+
+    #[test]
+    fn test_traced_pupil_matches_fill() {
+        use bevy_ecs::system::RunSystemOnce;
+        use crate::raytracing::*;
+
+        let mut world = World::new();
+        world.insert_resource(RayTracingConfig::default());
+        let fill: PupilFill = "dipole-x:0.5,0.8,90".parse().unwrap();
+        let intermediate_focus = Position3D::from_vec3(Vec3::new(0.5, 0.0, 0.0));
+        world.insert_resource(IlluminatorDesign::behind(intermediate_focus, fill.clone()).build().unwrap());
+        world.run_system_once(spawn_illuminator).unwrap();
+        world.insert_resource(RayTracingStatistics::default());
+        world.insert_resource(MirrorBvh::default());
+        world.run_system_once(mirror_bvh_system).unwrap();
+
+        // Packets leave the intermediate focus on a grid spanning the field facet mirror
+        let mut batch = PhotonBatch::default();
+        for i in 0..200 {
+            for j in 0..200 {
+                let aim = Vec3::new(0.5, (i as f32 - 99.5) * 0.0035, (j as f32 - 99.5) * 0.0035);
+                batch.push(&PhotonPacket::new(intermediate_focus, aim, 1000));
+            }
+        }
+        world.insert_resource(batch);
+        world.run_system_once(ray_transport_system).unwrap();
+
+        let illumination = &world.resource::<RayTracingStatistics>().illumination;
+        // Two multilayer reflections, light between facets and the parked half of the field facets
+        let transmission = illumination.transmission();
+        assert!(transmission > 0.05 && transmission < 0.25, "{transmission}");
+        // Nearly all the light arrives from pupil bins that overlap the fill
+        let bins = illumination.pupil_bins;
+        let width = 2.0 / bins as f32;
+        let in_fill: f32 = illumination
+            .pupil_map
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                let corner = Vec2::new((index % bins) as f32, (index / bins) as f32) * width - 1.0;
+                [Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::ONE, Vec2::splat(0.5)]
+                    .iter()
+                    .any(|&c| fill.contains(corner + c * width))
+            })
+            .map(|(_, energy)| energy)
+            .sum();
+        let total: f32 = illumination.pupil_map.iter().sum();
+        assert!(in_fill > 0.95 * total, "{in_fill} of {total}");
+        assert!(illumination.slit_profile.iter().all(|&e| e > 0.0));
+    }
+}
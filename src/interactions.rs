@@ -3,23 +3,55 @@
 //! Handles ray-sphere/ray-disk collision detection and state transitions
 
 use bevy_ecs::prelude::*;
-use glam::Vec3;
 use crate::units::{Position3D, Distance};
 use crate::components::*;
-use crate::source::{DropletState, LaserBeam, SimulationTime};
+use crate::source::{DropletState, DropletStream, LaserBeam, SimulationTime, TargetsStream};
+use crate::burst::BurstScheduler;
+use crate::plasma::{MainPulseParameters, PlasmaAxis, PlasmaEmissionConfig, PlasmaPulseEvent};
+use crate::raytracing::{PhotonBatch, PhotonPacket, RayTracingConfig};
+use crate::polarization::JonesVector;
+use crate::targeting::{classify_miss, TargetingEvent, TargetingOutcome};
+
+type DropletQueryData<'a> = (
+    Entity,
+    &'a Position,
+    &'a Velocity,
+    &'a mut DropletState,
+    &'a mut CollisionShape,
+    Option<&'a DropletStream>,
+);
 
 /// System that detects laser-droplet collisions and updates droplet states
+///
+/// Every laser is resolved on its first evaluation and reported as a targeting event.
+/// A laser only sees droplets of the stream its channel targets.
+#[allow(clippy::too_many_arguments)]
 pub fn laser_droplet_interaction_system(
     mut commands: Commands,
-    mut droplets: Query<(Entity, &Position, &mut DropletState, &mut CollisionShape), With<EntityType>>,
+    mut droplets: Query<DropletQueryData, With<EntityType>>,
     mut lasers: Query<(Entity, &Position, &mut LaserBeam)>,
+    channels: Query<&TargetsStream>,
+    emission_config: Res<PlasmaEmissionConfig>,
+    bursts: Query<&BurstScheduler>,
+    mut pulse_events: EventWriter<PlasmaPulseEvent>,
+    mut targeting_events: EventWriter<TargetingEvent>,
 ) {
+    let mut rng = rand::thread_rng();
     for (laser_entity, laser_pos, mut laser) in lasers.iter_mut() {
         if laser.has_fired {
             continue;
         }
+        laser.has_fired = true;
+        let stream = channels.get(laser.channel).ok().map(|targets| targets.0);
+
+        let mut outcome = None;
+        // Nearest droplet (distance, offset-classified miss) in case nothing is hit
+        let mut nearest: Option<(f32, (TargetingOutcome, f32))> = None;
 
-        for (droplet_entity, droplet_pos, mut state, mut shape) in droplets.iter_mut() {
+        for (droplet_entity, droplet_pos, droplet_velocity, mut state, mut shape, droplet_stream) in droplets.iter_mut() {
+            if stream.is_some_and(|stream| droplet_stream.is_some_and(|d| d.0 != stream)) {
+                continue;
+            }
             // Check if laser hits droplet based on current shape
             let hit = match *shape {
                 CollisionShape::Sphere { radius } => {
@@ -31,45 +63,82 @@ pub fn laser_droplet_interaction_system(
                 _ => false,
             };
 
-            if hit {
-                laser.has_fired = true;
-
-                // State transition based on laser type and current state
-                match (*state, laser.is_prepulse) {
-                    // Pre-pulse hits spherical droplet -> pancake it
-                    (DropletState::Spherical, true) => {
-                        *state = DropletState::Pancaked;
-                        
-                        // Transform geometry from sphere to disk
-                        if let CollisionShape::Sphere { radius } = *shape {
-                            *shape = CollisionShape::Disk {
-                                radius: radius * 2, // Flatten increases surface area
-                                thickness: radius / 4, // Much thinner
-                            };
-                        }
+            if !hit {
+                let distance = laser_pos.0.to_vec3().distance(droplet_pos.0.to_vec3());
+                if nearest.is_none_or(|(d, _)| distance < d) {
+                    nearest = Some((distance, classify_miss(laser_pos.0.to_vec3(), droplet_pos.0.to_vec3(), droplet_velocity.0)));
+                }
+                continue;
+            }
+
+            // State transition based on laser type and current state
+            outcome = Some(match (*state, laser.is_prepulse) {
+                // Pre-pulse hits spherical droplet -> pancake it
+                (DropletState::Spherical, true) => {
+                    *state = DropletState::Pancaked;
+
+                    // Transform geometry from sphere to disk
+                    if let CollisionShape::Sphere { radius } = *shape {
+                        *shape = CollisionShape::Disk {
+                            radius: radius * 2, // Flatten increases surface area
+                            thickness: radius / 4, // Much thinner
+                        };
                     }
+                    TargetingOutcome::Hit
+                }
 
-                    // Main pulse hits pancaked droplet -> create plasma
-                    (DropletState::Pancaked, false) => {
-                        *state = DropletState::Plasma;
-                        
-                        // Spawn photon packets from the plasma
-                        spawn_photon_packets(
-                            &mut commands,
-                            droplet_pos.0,
-                            laser.power,
-                        );
-
-                        // Schedule droplet for debris conversion after plasma lifetime
-                        commands.entity(droplet_entity).insert(Lifetime::new(0.000_01));
+                // Main pulse hits pancaked droplet -> create plasma
+                (DropletState::Pancaked, false) => {
+                    *state = DropletState::Plasma;
+
+                    let target_diameter = match *shape {
+                        CollisionShape::Disk { radius, .. } => radius * 2,
+                        _ => Distance::ZERO,
+                    };
+                    let pulse = MainPulseParameters {
+                        energy: laser.energy,
+                        duration: laser.duration,
+                        spot_diameter: laser.spot_diameter,
+                        target_diameter,
+                    };
+                    let mut emission = emission_config.sample_in_band_emission(&pulse, &mut rng);
+                    if let Ok(burst) = bursts.get(laser.channel) {
+                        emission.scale_yield(burst.transient.yield_factor(laser.burst_pulse));
                     }
+                    pulse_events.send(PlasmaPulseEvent {
+                        emission,
+                        burst_pulse: laser.burst_pulse,
+                        channel: laser.channel,
+                        position: droplet_pos.0,
+                        axis: -laser.direction,
+                    });
 
-                    _ => {} // Invalid state transitions are ignored
+                    // Schedule droplet for debris conversion after plasma lifetime
+                    commands.entity(droplet_entity).insert((Lifetime::new(0.000_01), PlasmaAxis(-laser.direction)));
+                    TargetingOutcome::Hit
                 }
 
-                break; // Laser can only hit one droplet
-            }
+                // Main pulse before the pre-pulse has shaped the target
+                (DropletState::Spherical, false) => TargetingOutcome::Unshaped,
+
+                // Droplet already processed by a pulse of this type
+                _ => TargetingOutcome::DoubleHit,
+            });
+
+            break; // Laser can only hit one droplet
         }
+
+        let (outcome, offset) = match (outcome, nearest) {
+            (Some(outcome), _) => (outcome, 0.0),
+            (None, Some((_, miss))) => miss,
+            (None, None) => (TargetingOutcome::NoTarget, 0.0),
+        };
+        targeting_events.send(TargetingEvent {
+            outcome,
+            position: laser_pos.0,
+            offset,
+            is_prepulse: laser.is_prepulse,
+        });
     }
 }
 
@@ -114,53 +183,35 @@ fn ray_disk_intersection(
     yz_distance <= r
 }
 
-/// Spawns photon packets representing EUV light emission from plasma
-fn spawn_photon_packets(
-    commands: &mut Commands,
-    plasma_position: Position3D,
-    laser_power: f32,
+/// System that emits photon packets from each plasma pulse into the tracing batch
+///
+/// Packet count is derived from the in-band energy; each packet's wavelength is
+/// drawn from the emission spectrum and its photon weight from the radiated energy.
+/// Directions follow the configured emission pattern about the pulse's emission
+/// axis, which points back toward the incoming laser. Each pulse draws from a
+/// random stream seeded by the trace seed and its first packet's serial number,
+/// so a run's emitted light is reproducible.
+pub fn plasma_photon_emission_system(
+    mut pulses: EventReader<PlasmaPulseEvent>,
+    config: Res<PlasmaEmissionConfig>,
+    tracing: Res<RayTracingConfig>,
+    mut batch: ResMut<PhotonBatch>,
 ) {
-    const PACKET_COUNT: u32 = 1000;
-    const EUV_WAVELENGTH: f32 = 13.5e-9;
-
-    const PLANCK: f64 = 6.626e-34;
-    const LIGHT_SPEED: f64 = 3e8;
-    let photon_energy = (PLANCK * LIGHT_SPEED / EUV_WAVELENGTH as f64) as f32;
-
-    let euv_power = laser_power * 0.02;
-    let total_photons = (euv_power / photon_energy) as u64;
-
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
+    for pulse in pulses.read() {
+        let mut rng = tracing.emission_rng(batch.next_id());
+        let emission = &pulse.emission;
+        let packet_count = config.packet_count(config.in_band_energy_4pi(emission));
+        let packet_energy = config.radiated_energy(emission) / packet_count as f32;
 
-    for _ in 0..PACKET_COUNT {
-        let theta = rng.gen_range(0.0..std::f32::consts::TAU);
-        let phi = rng.gen_range(0.0..std::f32::consts::PI);
-        
-        let direction = Vec3::new(
-            phi.sin() * theta.cos(),
-            phi.sin() * theta.sin(),
-            phi.cos(),
-        );
-
-        let velocity = direction * 3e8;
-
-        commands.spawn((
-            Position(plasma_position),
-            Velocity(velocity),
-            crate::raytracing::PhotonPacket::new(total_photons / PACKET_COUNT as u64),
-            Lifetime::new(0.000_1),
-        ));
-    }
-}
-
-/// System that converts plasma back to debris after lifetime expires
-pub fn plasma_to_debris_system(
-    mut query: Query<(&mut DropletState, &Lifetime)>,
-) {
-    for (mut state, lifetime) in query.iter_mut() {
-        if *state == DropletState::Plasma && lifetime.remaining_seconds <= 0.0 {
-            *state = DropletState::Debris;
+        for _ in 0..packet_count {
+            let sample = config.spectrum.sample(&mut rng);
+            let photon_energy = PhotonPacket::photon_energy(sample.wavelength);
+            let photons = (packet_energy * sample.energy_weight / photon_energy) as u64;
+            let direction = config.pattern.sample_direction(pulse.axis, &mut rng);
+            let packet = PhotonPacket::with_wavelength(pulse.position, direction, photons, sample.wavelength);
+            // Plasma light is unpolarized: each packet takes a random state
+            let polarization = JonesVector::random(packet.direction, &mut rng);
+            batch.push(&packet.with_polarization(polarization));
         }
     }
 }
@@ -196,6 +247,91 @@ pub fn lifetime_system(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bevy_ecs::system::RunSystemOnce;
+    use glam::Vec3;
+
+    /// Directions of the packets one nominal pulse emits with the given trace seed
+    fn emitted_directions(seed: u64) -> Vec<Vec3> {
+        let mut world = World::new();
+        let config = PlasmaEmissionConfig::default();
+        let emission = config.in_band_emission(&MainPulseParameters {
+            energy: 0.4,
+            duration: 50e-9,
+            spot_diameter: Distance::from_micrometers(150),
+            target_diameter: Distance::from_micrometers(120),
+        });
+        let mut events = Events::<PlasmaPulseEvent>::default();
+        events.send(PlasmaPulseEvent {
+            emission,
+            burst_pulse: 0,
+            channel: Entity::PLACEHOLDER,
+            position: Position3D::zero(),
+            axis: -Vec3::Z,
+        });
+        world.insert_resource(events);
+        world.insert_resource(config);
+        world.insert_resource(RayTracingConfig { seed, ..Default::default() });
+        world.insert_resource(PhotonBatch::default());
+        world.run_system_once(plasma_photon_emission_system).unwrap();
+        world.resource::<PhotonBatch>().direction.clone()
+    }
+
+    #[test]
+    fn test_photon_emission_follows_trace_seed() {
+        let first = emitted_directions(7);
+        assert!(!first.is_empty());
+        assert_eq!(first, emitted_directions(7));
+        assert_ne!(first, emitted_directions(8));
+    }
+
+    #[test]
+    fn test_lasers_only_hit_their_own_stream() {
+        let mut world = World::new();
+        world.insert_resource(PlasmaEmissionConfig::default());
+        world.insert_resource(Events::<PlasmaPulseEvent>::default());
+        world.insert_resource(Events::<TargetingEvent>::default());
+        let first = world.spawn_empty().id();
+        let second = world.spawn_empty().id();
+        // Both streams' droplets sit at the same spot, the second stream's spawned first
+        let droplet = |world: &mut World, stream| {
+            world.spawn((
+                Position(Position3D::zero()),
+                Velocity(Vec3::X * 100.0),
+                DropletState::Spherical,
+                CollisionShape::Sphere { radius: Distance::from_micrometers(15) },
+                DropletStream(stream),
+                EntityType::TinDroplet,
+            )).id()
+        };
+        let second_droplet = droplet(&mut world, second);
+        let first_droplet = droplet(&mut world, first);
+        // The first channel pre-pulses; the second fires a main pulse at its still-round droplet
+        for (stream, is_prepulse) in [(first, true), (second, false)] {
+            let channel = world.spawn(TargetsStream(stream)).id();
+            world.spawn((
+                Position(Position3D::zero()),
+                LaserBeam {
+                    power: LaserBeam::PRE_PULSE_POWER,
+                    energy: 0.01,
+                    duration: 10e-9,
+                    spot_diameter: Distance::from_micrometers(100),
+                    direction: -Vec3::Z,
+                    is_prepulse,
+                    burst_pulse: 0,
+                    channel,
+                    has_fired: false,
+                },
+            ));
+        }
+        world.run_system_once(laser_droplet_interaction_system).unwrap();
+
+        assert_eq!(*world.get::<DropletState>(first_droplet).unwrap(), DropletState::Pancaked);
+        assert_eq!(*world.get::<DropletState>(second_droplet).unwrap(), DropletState::Spherical);
+        let events = world.resource::<Events<TargetingEvent>>();
+        let mut outcomes: Vec<_> = events.iter_current_update_events().map(|e| (e.is_prepulse, e.outcome)).collect();
+        outcomes.sort_by_key(|&(is_prepulse, _)| is_prepulse);
+        assert_eq!(outcomes, vec![(false, TargetingOutcome::Unshaped), (true, TargetingOutcome::Hit)]);
+    }
 
     #[test]
     fn test_ray_sphere_intersection() {
@@ -0,0 +1,29 @@
+//! Simulation library behind the `lithos` binary; exposed so benchmarks can
+//! drive individual subsystems
+
+pub mod units;
+pub mod components;
+pub mod source;
+pub mod plasma;
+pub mod debris;
+pub mod contamination;
+pub mod dose;
+pub mod burst;
+pub mod targeting;
+pub mod interactions;
+pub mod coating;
+pub mod polarization;
+pub mod scatter;
+pub mod zernike;
+pub mod optics;
+pub mod illumination;
+pub mod reticle;
+pub mod prescription;
+pub mod projection;
+pub mod wavefront;
+pub mod spot;
+pub mod analysis;
+pub mod bvh;
+pub mod raytracing;
+pub mod thermal;
+pub mod profiler;
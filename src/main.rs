@@ -1,24 +1,38 @@
-mod units;
-mod components;
-mod source;
-mod interactions;
-mod optics;
-mod raytracing;
-mod thermal;
-mod profiler;
-
 use bevy_ecs::prelude::*;
-use units::*;
-use components::*;
-use source::*;
-use interactions::*;
-use optics::*;
-use raytracing::*;
-use thermal::*;
-use profiler::*;
+use bevy_ecs::system::RunSystemOnce;
+use lithos::units::*;
+use lithos::components::*;
+use lithos::source::*;
+use lithos::plasma::*;
+use lithos::debris::*;
+use lithos::contamination::*;
+use lithos::dose::*;
+use lithos::burst::*;
+use lithos::targeting::*;
+use lithos::interactions::*;
+use lithos::coating::*;
+use lithos::polarization::StokesVector;
+use lithos::scatter::SurfaceRoughness;
+use lithos::optics::*;
+use lithos::illumination::{IlluminatorDesign, Illuminator, PupilFill, spawn_illuminator};
+use lithos::reticle::{Absorber, AbsorberPattern, Reticle};
+use lithos::prescription::{Prescription, spawn_prescription};
+use lithos::projection::ProjectionBox;
+use lithos::raytracing::*;
+use lithos::thermal::*;
+use lithos::profiler::*;
 use std::time::Instant;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|command| command == "analyze") {
+        if let Err(e) = lithos::analysis::run(&args[1..]) {
+            eprintln!("lithos analyze: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     println!("╔═══════════════════════════════════════════════════════╗");
     println!("║  LITHOS - EUV Lithography Simulator                  ║");
     println!("║  High-Performance Terminal Mode                      ║");
@@ -27,48 +41,330 @@ fn main() {
     let mut world = World::new();
     
     world.insert_resource(SimulationTime::default());
-    world.insert_resource(DropletGeneratorConfig::default());
-    world.insert_resource(DropletGeneratorState::default());
-    world.insert_resource(LaserTargetingSystem::default());
+    let generator = DropletGeneratorConfig::default();
+    let primary_generator = world.spawn((generator.clone(), DropletGeneratorState::default())).id();
+    // Optional standby nozzle 2 mm off-axis, aimed through the same focal point
+    let nozzle_swap_time = std::env::var("LITHOS_NOZZLE_SWAP_MS").ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .map(|ms| ms * 1e-3);
+    let standby_generator = nozzle_swap_time.map(|_| {
+        let spawn_position = Position3D::new(Distance::from_millimeters(-50), Distance::from_millimeters(2), Distance::ZERO);
+        let standby = DropletGeneratorConfig {
+            spawn_position,
+            spawn_direction: -spawn_position.to_vec3().normalize(),
+            ..generator.clone()
+        };
+        world.spawn((standby, DropletGeneratorState { enabled: false, ..Default::default() })).id()
+    });
+    let mut targeting = LaserTargetingSystem::default();
+    if let Some(ns) = std::env::var("LITHOS_TIMING_JITTER_NS").ok().and_then(|v| v.parse::<f32>().ok()) {
+        targeting.timing_jitter = ns * 1e-9;
+    }
+    let laser_channel = world.spawn((targeting.clone(), TargetsStream(primary_generator))).id();
+    let mut emission_config = PlasmaEmissionConfig::default();
+    if let Some(ce) = std::env::var("LITHOS_CONVERSION_EFFICIENCY").ok().and_then(|v| v.parse::<f32>().ok()) {
+        emission_config.conversion = Box::new(ConstantConversionEfficiency(ce));
+    }
+    match std::env::var("LITHOS_EMISSION_PATTERN").as_deref() {
+        Ok("isotropic") => emission_config.pattern = Box::new(IsotropicEmission),
+        Ok(coefficients) => {
+            let parsed: Result<Vec<f32>, _> = coefficients.split(',').map(|c| c.trim().parse::<f32>()).collect();
+            match parsed.as_deref() {
+                Ok(&[a1, a2]) => match AnisotropicEmission::new(a1, a2) {
+                    Some(pattern) => emission_config.pattern = Box::new(pattern),
+                    None => eprintln!("Ignoring LITHOS_EMISSION_PATTERN: intensity goes negative"),
+                },
+                Ok(_) => eprintln!("Ignoring LITHOS_EMISSION_PATTERN: expected 'isotropic' or two coefficients 'a1,a2'"),
+                Err(e) => eprintln!("Ignoring LITHOS_EMISSION_PATTERN: {e}"),
+            }
+        }
+        Err(_) => {}
+    }
+    // Dose target defaults to the nominal yield of a pancaked droplet at the fixed main-pulse energy
+    let sensor = EuvEnergySensor::default();
+    let nominal_energy = LaserBeam::MAIN_PULSE_POWER * generator.period;
+    let nominal_emission = emission_config.in_band_emission(&MainPulseParameters {
+        energy: nominal_energy,
+        duration: targeting.pulse_duration,
+        spot_diameter: targeting.spot_diameter,
+        target_diameter: generator.radius * 4,
+    });
+    let target_dose = std::env::var("LITHOS_DOSE_TARGET_MJ").ok()
+        .and_then(|v| v.parse::<f32>().ok())
+        .map(|mj| mj * 1e-3)
+        .unwrap_or_else(|| sensor.delivered(nominal_emission.energy_2pi_sr));
+    let dose_window = std::env::var("LITHOS_DOSE_WINDOW_PULSES").ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(40);
+    let burst_pulses = std::env::var("LITHOS_BURST_DROPLETS").ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(100);
+    let burst_gap = std::env::var("LITHOS_BURST_GAP_US").ok()
+        .and_then(|v| v.parse::<f32>().ok())
+        .map(|us| us * 1e-6)
+        .unwrap_or(500e-6);
+    world.entity_mut(laser_channel).insert((
+        DoseController::new(dose_window, target_dose, nominal_energy),
+        BurstScheduler::new(burst_pulses, burst_gap),
+        BurstStatistics::default(),
+    ));
+    world.insert_resource(sensor);
+    world.insert_resource(emission_config);
+    world.insert_resource(EmissionStatistics::default());
+    world.insert_resource(Events::<PlasmaPulseEvent>::default());
+    world.insert_resource(Events::<TargetingEvent>::default());
+    world.insert_resource(TargetingStatistics::default());
+    world.insert_resource(CatchBasin::default());
+    world.insert_resource(DebrisConfig::default());
+    let mut buffer_gas = BufferGasConfig::default();
+    if let Some(pressure) = std::env::var("LITHOS_H2_PRESSURE_PA").ok().and_then(|v| v.parse::<f32>().ok()) {
+        buffer_gas.pressure = pressure;
+    }
+    world.insert_resource(buffer_gas);
+    world.insert_resource(ChamberGeometry::default());
+    world.insert_resource(DebrisStatistics::default());
     world.insert_resource(RayTracingStatistics::default());
+    world.insert_resource(PhotonBatch::default());
+    world.insert_resource(MirrorBvh::default());
     world.insert_resource(ThermalStatistics::default());
 
-    world.spawn((
-        Position(Position3D::zero()),
-        MirrorSurface {
-            geometry: SurfaceGeometry::Spherical {
-                radius: Distance::from_meters(5),
-                center: Position3D::zero(),
+    let cleaning_rate = std::env::var("LITHOS_CLEANING_RATE_NM_PER_MIN").ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .map(|nm_per_min| nm_per_min * 1e-9 / 60.0)
+        .unwrap_or(ContaminationState::DEFAULT_CLEANING_RATE);
+    let mut tracing = RayTracingConfig::default();
+    if let Ok(mode) = std::env::var("LITHOS_TRACING") {
+        match mode.parse::<TracingMode>() {
+            Ok(mode) => tracing.mode = mode,
+            Err(e) => eprintln!("Ignoring LITHOS_TRACING: {e}"),
+        }
+    }
+    if std::env::var("LITHOS_BVH").is_ok_and(|v| v == "off") {
+        tracing.use_bvh = false;
+    }
+    if let Some(seed) = std::env::var("LITHOS_TRACE_SEED").ok().and_then(|v| v.parse::<u64>().ok()) {
+        tracing.seed = seed;
+    }
+    world.insert_resource(tracing);
+
+    let collector = std::env::var("LITHOS_COLLECTOR").ok();
+    let prescription = std::env::var("LITHOS_PRESCRIPTION").ok().map(|path| {
+        std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| text.parse::<Prescription>())
+            .unwrap_or_else(|e| {
+                eprintln!("Cannot load LITHOS_PRESCRIPTION {path}: {e}");
+                std::process::exit(1);
+            })
+    });
+    if let Some(prescription) = prescription {
+        // Mirrors in file order, declared as the sequential tracing order
+        world.insert_resource(prescription);
+        world.run_system_once(spawn_prescription).expect("prescription spawns");
+    } else if collector.as_deref() == Some("system") {
+        // Collector plus projection mirrors, declared in order for sequential tracing
+        world.insert_resource(OpticalSystemConfig::default());
+        let sequence = world.run_system_once(spawn_optical_system).expect("optical system spawns");
+        world.resource_mut::<RayTracingConfig>().sequence = sequence;
+    } else if collector.as_deref() == Some("illuminator") {
+        // Collector, then the field and pupil facet mirrors ending at a flat reticle
+        let config = OpticalSystemConfig { projection_mirrors: Vec::new(), ..Default::default() };
+        let spec = &config.collector_mirror;
+        let intermediate_focus = Position3D::from_vec3(spec.position.to_vec3() + glam::Vec3::X * spec.focal_length);
+        let fill = match std::env::var("LITHOS_PUPIL_FILL").map(|spec| spec.parse::<PupilFill>()) {
+            Ok(Ok(fill)) => fill,
+            Ok(Err(e)) => {
+                eprintln!("Ignoring LITHOS_PUPIL_FILL: {e}");
+                PupilFill::Annular { inner: 0.5, outer: 0.8 }
+            }
+            Err(_) => PupilFill::Annular { inner: 0.5, outer: 0.8 },
+        };
+        world.insert_resource(config);
+        let sequence = world.run_system_once(spawn_optical_system).expect("optical system spawns");
+        world.resource_mut::<RayTracingConfig>().sequence = sequence;
+        match IlluminatorDesign::behind(intermediate_focus, fill).build() {
+            Ok(illuminator) => {
+                world.insert_resource(illuminator);
+                world.run_system_once(spawn_illuminator).expect("illuminator spawns");
+            }
+            Err(e) => eprintln!("Cannot build illuminator: {e}"),
+        }
+    } else {
+        let mut mirror_surface = match collector.as_deref() {
+            // Plasma at the first focus, intermediate focus 1 m downstream along +X,
+            // the same focal axis as the system collector
+            Some("ellipsoid") => {
+                let intermediate_focus = Position3D::new(Distance::from_meters(1), Distance::ZERO, Distance::ZERO);
+                let geometry = SurfaceGeometry::ellipsoid_from_foci(Position3D::zero(), intermediate_focus, 0.75)
+                    .expect("semi-major axis exceeds half the focal separation");
+                let outer_radius = match &geometry {
+                    SurfaceGeometry::Ellipsoid { semi_axes, .. } => semi_axes.y,
+                    _ => 0.0,
+                };
+                // Central hole passes the drive laser
+                MirrorSurface {
+                    geometry,
+                    // Local z along the focal axis
+                    orientation: glam::Quat::from_rotation_arc(glam::Vec3::Z, glam::Vec3::X),
+                    aperture: Aperture::Annular { inner_radius: 0.05, outer_radius },
+                }
+            }
+            _ => MirrorSurface {
+                geometry: SurfaceGeometry::Spherical {
+                    radius: Distance::from_meters(5),
+                    center: Position3D::zero(),
+                },
+                orientation: glam::Quat::IDENTITY,
+                aperture: Aperture::Circular { radius: 5.0 },
             },
-            orientation: glam::Quat::IDENTITY,
-            radius: Distance::from_meters(5),
-        },
-        OpticalMaterial::BRAGG_MIRROR,
-        ThermalState::new(293.15, 5000.0),
-    ));
+        };
+        if let Ok(spec) = std::env::var("LITHOS_COLLECTOR_APERTURE") {
+            match spec.parse::<Aperture>() {
+                Ok(aperture) => mirror_surface.aperture = aperture,
+                Err(e) => eprintln!("Ignoring LITHOS_COLLECTOR_APERTURE: {e}"),
+            }
+        }
+        let mirror = world.spawn((
+            Position(Position3D::zero()),
+            ContaminationState::for_mirror(&mirror_surface, cleaning_rate),
+            mirror_surface,
+            OpticalMaterial::BRAGG_MIRROR,
+            MultilayerCoating::new(MultilayerStack::mo_si()),
+            ThermalState::new(293.15, 5000.0),
+            SurfaceDeposit::default(),
+        )).id();
+        world.resource_mut::<RayTracingConfig>().sequence = vec![mirror];
+    }
+
+    // The collector, first in the sequence, is perfectly specular unless
+    // roughness is asked for (a prescription's own roughness stands when unset)
+    if let Ok(spec) = std::env::var("LITHOS_COLLECTOR_ROUGHNESS") {
+        let roughness = match spec.as_str() {
+            "none" => Ok(None),
+            spec => spec.parse::<SurfaceRoughness>().map(Some),
+        };
+        match (roughness, world.resource::<RayTracingConfig>().sequence.first().copied()) {
+            (Ok(Some(roughness)), Some(collector)) => { world.entity_mut(collector).insert(roughness); }
+            (Ok(None), Some(collector)) => { world.entity_mut(collector).remove::<SurfaceRoughness>(); }
+            (Ok(_), None) => {}
+            (Err(e), _) => eprintln!("Ignoring LITHOS_COLLECTOR_ROUGHNESS: {e}"),
+        }
+    }
+
+    // Absorber overrides apply to whichever reticle the system ends at
+    let pattern = std::env::var("LITHOS_RETICLE_PATTERN").ok().and_then(|spec| {
+        AbsorberPattern::load(&spec).map_err(|e| eprintln!("Ignoring LITHOS_RETICLE_PATTERN: {e}")).ok()
+    });
+    let absorber = std::env::var("LITHOS_RETICLE_ABSORBER").ok().and_then(|spec| {
+        spec.parse::<Absorber>().map_err(|e| eprintln!("Ignoring LITHOS_RETICLE_ABSORBER: {e}")).ok()
+    });
+    if pattern.is_some() || absorber.is_some() {
+        let tracing = world.resource::<RayTracingConfig>();
+        let reticle = tracing.reticle_index.and_then(|index| tracing.sequence.get(index).copied());
+        match reticle.and_then(|entity| Some((entity, world.get::<Reticle>(entity)?.clone()))) {
+            Some((entity, reticle)) => {
+                world.entity_mut(entity).insert(Reticle::new(
+                    reticle.blank.stack,
+                    absorber.unwrap_or(reticle.absorber),
+                    pattern.unwrap_or(reticle.pattern),
+                ));
+            }
+            None => eprintln!("Ignoring LITHOS_RETICLE_PATTERN and LITHOS_RETICLE_ABSORBER: no coated reticle in this system"),
+        }
+    }
+
+    if let Ok(path) = std::env::var("LITHOS_EXPORT_PRESCRIPTION") {
+        let text = Prescription::from_world(&mut world).to_string();
+        match std::fs::write(&path, text) {
+            Ok(()) => println!("Prescription written to {path}"),
+            Err(e) => eprintln!("Cannot write prescription {path}: {e}"),
+        }
+    }
+
+    let mut projection = ProjectionBox::HIGH_NA;
+    if let Ok(spec) = std::env::var("LITHOS_DEMAGNIFICATION") {
+        let parsed: Vec<f32> = spec.split(',').filter_map(|v| v.trim().parse().ok()).collect();
+        match parsed[..] {
+            [x, y] if x > 0.0 && y > 0.0 => projection = ProjectionBox::anamorphic(x, y),
+            _ => eprintln!("Ignoring LITHOS_DEMAGNIFICATION: expected two positive factors 'x,y'"),
+        }
+    }
+    // Full-size reticle field, 104 mm across the slit by 132 mm along the scan
+    let reticle_field = glam::Vec2::new(0.104, 0.132);
+    // With the object, image and pupil placed, the box is fitted to chief rays
+    // through the spawned mirrors instead
+    let chief_rays = world.get_resource::<Prescription>().and_then(|p| p.projection_optics().ok()).map(|mut optics| {
+        let tracing = world.resource::<RayTracingConfig>();
+        optics.mirrors = tracing.sequence.iter()
+            .filter_map(|&mirror| Some((world.get::<Position>(mirror)?.0, world.get::<MirrorSurface>(mirror)?.clone())))
+            .collect();
+        optics.trace_field_grid(reticle_field / 2.0, 7)
+    });
+    let fit = chief_rays.as_ref().map(|rays| (ProjectionBox::fit(rays), rays));
+    let projection_source = match fit {
+        Some((Some(fitted), rays)) => {
+            let corner = (reticle_field / 2.0).abs();
+            let source = format!("fitted to {} chief rays, max residual {:.2} nm, telecentricity {:.2} mrad at the field corner",
+                rays.len(), fitted.max_residual(rays) * 1e9, fitted.telecentricity_error(corner) * 1e3);
+            projection = fitted;
+            source
+        }
+        Some((None, rays)) => {
+            eprintln!("Cannot fit the projection box: {} of 49 chief rays reach the image", rays.len());
+            "nominal".to_string()
+        }
+        None => "nominal".to_string(),
+    };
+    let image_field = (projection.map(reticle_field / 2.0) - projection.map(-reticle_field / 2.0)).abs() * 1e3;
+    let demagnification = projection.demagnification();
+    world.insert_resource(projection);
 
     let mirror_count = world.query::<&MirrorSurface>().iter(&world).count();
     println!("System Initialization:");
     println!("  └─ Mirrors spawned: {}", mirror_count);
     println!("  └─ Droplet frequency: 50 kHz");
     println!("  └─ Simulation tick: 1 μs");
-    println!("  └─ Mirror radius: 5.0 m");
+    println!("  └─ Projection: {:.2}×/{:.2}× anamorphic, 104 × 132 mm reticle field → {:.1} × {:.1} mm at wafer ({})",
+        demagnification.x, demagnification.y, image_field.x, image_field.y, projection_source);
+    println!("  └─ Mirror radius: {:.3} m", world.query::<&MirrorSurface>().iter(&world).map(|m| m.aperture.outer_radius()).fold(0.0, f32::max));
+    if let Some(coating) = world.query::<&MultilayerCoating>().iter(&world).next() {
+        let (peak_wavelength, peak) = coating.table().peak();
+        println!("  └─ Coating: {} × {:.2} nm multilayer, peak R {:.1}% at {:.2} nm",
+            coating.stack.periods, coating.stack.period_thickness(), peak * 100.0, peak_wavelength * 1e9);
+    }
     println!();
 
     let mut schedule = Schedule::default();
+    // Interactions are evaluated against this tick's positions before anything moves
+    // (droplets travel 100 μm per tick); light emitted this tick is traced to its fate within the tick
     schedule.add_systems((
-        droplet_generator_system,
-        laser_targeting_system,
-        physics_movement_system,
-        laser_droplet_interaction_system,
-        plasma_to_debris_system,
-        photon_mirror_interaction_system,
-        photon_cleanup_system,
-        thermal_dissipation_system,
-        raytracing_statistics_system,
-        thermal_statistics_system,
-        lifetime_system,
-    ));
+        (
+            burst_scheduler_system,
+            droplet_generator_system,
+            laser_targeting_system,
+            laser_droplet_interaction_system,
+            emission_statistics_system,
+            dose_control_system,
+            burst_statistics_system,
+            catch_basin_system,
+            targeting_statistics_system,
+            plasma_photon_emission_system,
+        ).chain(),
+        (
+            plasma_to_debris_system,
+            debris_stopping_system,
+            debris_deposition_system,
+            contamination_system,
+            mirror_bvh_system,
+            ray_transport_system,
+            physics_movement_system,
+            thermal_dissipation_system,
+            thermal_statistics_system,
+            lifetime_system,
+            plasma_event_update_system,
+            targeting_event_update_system,
+        ).chain(),
+    ).chain());
 
     let start_time = Instant::now();
     let mut tick_count = 0u64;
@@ -79,7 +375,7 @@ fn main() {
 
     println!("Starting simulation... (50ms total, reporting every 5ms)\n");
     println!("{:<8} {:<12} {:<10} {:<10} {:<12} {:<12} {:<10} {:<10} {:<12}", 
-        "Tick", "Time(μs)", "Droplets", "Photons", "Reflections", "Absorptions", "MaxTemp", "AvgBounce", "TickTime(μs)");
+        "Tick", "Time(μs)", "Droplets", "Packets", "Reflections", "Absorptions", "MaxTemp", "AvgBounce", "TickTime(μs)");
     println!("{}", "─".repeat(110));
 
     while tick_count < max_ticks {
@@ -92,15 +388,24 @@ fn main() {
         tick_times.push(tick_duration);
         tick_count += 1;
 
+        if let (Some(swap_time), Some(standby)) = (nozzle_swap_time, standby_generator) {
+            if world.resource::<SimulationTime>().total_seconds >= swap_time
+                && world.get::<TargetsStream>(laser_channel).is_some_and(|s| s.0 == primary_generator)
+            {
+                hot_swap_stream(&mut world, primary_generator, standby);
+                println!("  ⇄ Nozzle hot-swap at {:.3} ms: laser retargeted to standby generator", swap_time * 1e3);
+            }
+        }
+
         if tick_count % report_interval == 0 {
-            let droplet_count = world.resource::<DropletGeneratorState>().droplet_count;
+            let droplet_count: u64 = world.query::<&DropletGeneratorState>().iter(&world).map(|s| s.droplet_count).sum();
             let total_reflections = world.resource::<RayTracingStatistics>().total_reflections;
             let total_absorptions = world.resource::<RayTracingStatistics>().total_absorptions;
-            let average_bounces = world.resource::<RayTracingStatistics>().average_bounces;
+            let average_bounces = world.resource::<RayTracingStatistics>().average_bounces();
             let max_temperature = world.resource::<ThermalStatistics>().max_temperature;
             let sim_time = world.resource::<SimulationTime>().total_seconds * 1e6;
             
-            let photons = world.query::<&PhotonPacket>().iter(&world).count();
+            let packets = world.resource::<RayTracingStatistics>().traced_packets;
             
             let avg_tick_time = if tick_times.len() >= 1000 {
                 let recent: f64 = tick_times.iter().rev().take(1000).map(|d| d.as_secs_f64()).sum();
@@ -113,7 +418,7 @@ fn main() {
                 tick_count,
                 sim_time,
                 droplet_count,
-                photons,
+                packets,
                 total_reflections,
                 total_absorptions,
                 max_temperature,
@@ -124,6 +429,13 @@ fn main() {
     }
 
     let elapsed = start_time.elapsed();
+    let generator_counts: Vec<u64> = world.query::<&DropletGeneratorState>().iter(&world).map(|s| s.droplet_count).collect();
+    let heaviest_deposit = world.query::<&SurfaceDeposit>().iter(&world).map(|d| d.total_mass()).fold(0.0, f64::max);
+    let mut contaminated_mirrors: Vec<(Entity, SurfaceDeposit, ContaminationState)> = world
+        .query::<(Entity, &SurfaceDeposit, &ContaminationState)>()
+        .iter(&world)
+        .map(|(entity, deposit, contamination)| (entity, deposit.clone(), contamination.clone()))
+        .collect();
     let ray_stats = world.resource::<RayTracingStatistics>();
     let thermal_stats = world.resource::<ThermalStatistics>();
     let sim_time = world.resource::<SimulationTime>().total_seconds;
@@ -161,18 +473,198 @@ fn main() {
     println!("│  └─ Ticks/second: {:.2} M", tick_count as f64 / elapsed.as_secs_f64() / 1e6);
 
     println!("\n┌─ Source Statistics");
-    let droplet_count = world.resource::<DropletGeneratorState>().droplet_count;
-    println!("│  ├─ Droplets generated: {}", droplet_count);
-    println!("│  ├─ Plasma events: ~{}", droplet_count / 2);
-    println!("│  └─ Expected photon packets: ~{}", droplet_count * 500);
+    println!("│  ├─ Droplets generated: {} ({} generator{}: {:?})", generator_counts.iter().sum::<u64>(),
+        generator_counts.len(), if generator_counts.len() == 1 { "" } else { "s" }, generator_counts);
+    let emission_stats = world.resource::<EmissionStatistics>();
+    println!("│  ├─ Plasma events: {}", emission_stats.pulse_count);
+    println!("│  ├─ Average conversion efficiency: {:.2}%", emission_stats.average_conversion_efficiency() * 100.0);
+    println!("│  ├─ In-band energy per pulse (2π sr): {:.3} mJ", emission_stats.average_in_band_energy() * 1e3);
+    println!("│  ├─ Total radiated energy (4π sr, all bands): {:.3} J", emission_stats.total_radiated_energy);
+    let pattern = &world.resource::<PlasmaEmissionConfig>().pattern;
+    println!("│  ├─ Emission anisotropy I(0°)/I(90°)/I(180°): {:.2} / {:.2} / {:.2}",
+        pattern.relative_intensity(1.0), pattern.relative_intensity(0.0), pattern.relative_intensity(-1.0));
+    println!("│  └─ In-band source power (2π sr): {:.1} W",
+        emission_stats.average_in_band_energy() * generator.frequency as f64);
+
+    let dose = world.get::<DoseController>(laser_channel).unwrap();
+    let dose_stats = &dose.stats;
+    println!("\n┌─ Dose Control ({}-pulse window, target {:.3} mJ/pulse at IF)", dose.window_pulses, dose.target_pulse_dose * 1e3);
+    println!("│  ├─ Pulses fired / skipped: {} / {}", dose_stats.pulses, dose_stats.skipped_pulses);
+    println!("│  ├─ Windows evaluated: {}", dose_stats.window_dose_error.count);
+    println!("│  ├─ Mean dose error: {:+.3}%", dose_stats.mean_dose_error_percent());
+    println!("│  ├─ Dose stability (3σ): {:.3}%", dose_stats.dose_stability_3sigma_percent());
+    println!("│  ├─ Max dose error: {:.3}%", dose_stats.max_dose_error_percent());
+    println!("│  ├─ Pulse-to-pulse energy at IF (1σ): {:.2}%", dose_stats.pulse_stability_percent());
+    println!("│  └─ Main-pulse energy: {:.3} J avg ({:.3}–{:.3} J)",
+        dose_stats.pulse_energy.mean(), dose_stats.pulse_energy.min, dose_stats.pulse_energy.max);
+
+    let scheduler = world.get::<BurstScheduler>(laser_channel).unwrap();
+    let burst_stats = world.get::<BurstStatistics>(laser_channel).unwrap();
+    let period = generator.period;
+    println!("\n┌─ Burst Operation ({} pulses per burst, {:.0} μs gap, nominal duty cycle {:.1}%)",
+        scheduler.pulses_per_burst, scheduler.inter_burst_gap * 1e6, scheduler.duty_cycle(period) * 100.0);
+    println!("│  ├─ Bursts: {} (now {})", burst_stats.bursts.len(),
+        match scheduler.phase() { BurstPhase::Firing => "firing", BurstPhase::Idle { .. } => "idle" });
+    println!("│  ├─ Measured duty cycle: {:.1}%", burst_stats.duty_cycle(sim_time) * 100.0);
+    if let Some(last) = burst_stats.bursts.iter().rev().find(|b| b.end_time.is_some()) {
+        println!("│  ├─ Last complete burst #{}: {} fired, {} skipped, {:.3} J laser, {:.1} W in-band while firing",
+            last.index, last.pulses_fired, last.pulses_skipped, last.laser_energy, last.in_band_power(sim_time));
+    }
+    let profile = &burst_stats.in_band_by_pulse;
+    let samples: Vec<String> = [0usize, 5, 10, 25, 50, 99]
+        .iter()
+        .filter(|&&k| k < profile.len() && profile[k].count > 0)
+        .map(|&k| format!("#{}: {:.2}", k, profile[k].mean() * 1e3))
+        .collect();
+    println!("│  ├─ In-band mJ by pulse in burst: {}", samples.join(", "));
+    println!("│  └─ Time-averaged in-band power (thermal load): {:.1} W", burst_stats.average_in_band_power(sim_time));
+
+    let targeting_stats = world.resource::<TargetingStatistics>();
+    println!("\n┌─ Targeting Diagnostics (timing jitter {:.0} ns)", targeting.timing_jitter * 1e9);
+    println!("│  ├─ Pulses resolved: {} ({:.3}% hit)", targeting_stats.pulses(), targeting_stats.hit_rate() * 100.0);
+    for outcome in TargetingOutcome::ALL {
+        let last = targeting_stats.last_position(outcome)
+            .map(|p| {
+                let v = p.to_vec3() * 1e3;
+                format!(", last at ({:.3}, {:.3}, {:.3}) mm", v.x, v.y, v.z)
+            })
+            .unwrap_or_default();
+        println!("│  ├─ {}: {}{}", outcome.label(), targeting_stats.count(outcome), last);
+    }
+    println!("│  └─ Misses: {} pre-pulse, mean offset {:+.2} μm (σ {:.2} μm)",
+        targeting_stats.prepulse_misses, targeting_stats.miss_offset.mean() * 1e6, targeting_stats.miss_offset.std_dev() * 1e6);
 
-    println!("\n┌─ Optical Statistics");
+    let nominal_pulse = MainPulseParameters {
+        energy: LaserBeam::MAIN_PULSE_POWER * generator.period,
+        duration: targeting.pulse_duration,
+        spot_diameter: targeting.spot_diameter,
+        target_diameter: targeting.spot_diameter,
+    };
+    let curve = source_power_curve(
+        world.resource::<PlasmaEmissionConfig>().conversion.as_ref(),
+        &nominal_pulse,
+        &[0.1, 0.2, 0.3, 0.4, 0.5, 0.6],
+        generator.frequency,
+    );
+    println!("\n┌─ Source Power Curve (matched target, {:.0} kHz)", generator.frequency / 1e3);
+    for (i, (energy, power)) in curve.iter().enumerate() {
+        let branch = if i + 1 == curve.len() { "└─" } else { "├─" };
+        println!("│  {} {:.2} J/pulse → {:.1} W in-band", branch, energy, power);
+    }
+
+    let tracing = world.resource::<RayTracingConfig>();
+    println!("\n┌─ Optical Statistics ({:?}, {} mirrors in sequence)", tracing.mode, tracing.sequence.len());
+    let mirror_bvh = world.resource::<MirrorBvh>();
+    println!("│  ├─ Mirror BVH: {} ({} surfaces: {} mirrors, {} walls and sensors; {} rebuilds)",
+        if tracing.use_bvh { "on" } else { "off" }, mirror_bvh.bvh.len(), mirror_bvh.entities.len(),
+        mirror_bvh.obstacles.len(), mirror_bvh.rebuilds);
     println!("│  ├─ Total reflections: {}", ray_stats.total_reflections);
     println!("│  ├─ Total absorptions: {}", ray_stats.total_absorptions);
     println!("│  ├─ Reflection ratio: {:.1}%", 
         ray_stats.total_reflections as f64 / (ray_stats.total_reflections + ray_stats.total_absorptions) as f64 * 100.0);
-    println!("│  ├─ Active photon packets: {}", ray_stats.active_photon_packets);
-    println!("│  └─ Average bounces/packet: {:.2}", ray_stats.average_bounces);
+    println!("│  ├─ In-band heat load: {:.3} J", ray_stats.in_band_heat);
+    println!("│  ├─ Out-of-band heat load: {:.3} J", ray_stats.out_of_band_heat);
+    println!("│  ├─ Packets traced: {} ({} escaped, {} at bounce limit)",
+        ray_stats.traced_packets, ray_stats.escaped_packets, ray_stats.bounce_limited_packets);
+    println!("│  ├─ Chamber wall: {:.3} J; EUV sensor: {} packets, {:.3} mJ",
+        ray_stats.wall_energy, ray_stats.sensor_packets, ray_stats.sensor_energy * 1e3);
+    println!("│  ├─ Scattered reflections: {} ({:.2}% of reflections)", ray_stats.total_scattered,
+        ray_stats.total_scattered as f64 / ray_stats.total_reflections.max(1) as f64 * 100.0);
+    println!("│  ├─ Delivered through sequence: {} packets, {:.3} J", ray_stats.delivered_packets, ray_stats.delivered_energy);
+    println!("│  ├─ Flare after the last mirror: {:.2}% of delivered energy", ray_stats.flare_fraction() * 100.0);
+    println!("│  ├─ Time of flight to last mirror: {:.2} ± {:.2} ns",
+        ray_stats.delivered_time_of_flight.mean(), ray_stats.delivered_time_of_flight.std_dev());
+    let polarization = |stokes: &StokesVector| {
+        if stokes.s0 > 0.0 {
+            format!("DOP {:.1}% (linear {:.1}%)", stokes.degree_of_polarization() * 100.0,
+                stokes.degree_of_linear_polarization() * 100.0)
+        } else {
+            "no light".to_string()
+        }
+    };
+    if tracing.reticle_index.is_some() {
+        println!("│  ├─ Reticle polarization: {}", polarization(&ray_stats.reticle_polarization));
+    }
+    println!("│  ├─ Polarization after the last mirror: {}", polarization(&ray_stats.delivered_polarization));
+    println!("│  {} Average bounces/packet: {:.2}", if ray_stats.per_mirror.is_empty() { "└─" } else { "├─" }, ray_stats.average_bounces());
+    // Mirrors are named by their position in the tracing sequence, by entity index outside it
+    let sequence_position = |entity: Entity| tracing.sequence.iter().position(|&mirror| mirror == entity);
+    let mirror_order = |entity: Entity| (sequence_position(entity).is_none(), sequence_position(entity), entity.index());
+    let mirror_label = |entity: Entity| match sequence_position(entity) {
+        Some(position) => format!("Mirror {position}"),
+        None => format!("Mirror (entity {})", entity.index()),
+    };
+    let mut per_mirror: Vec<_> = ray_stats.per_mirror.iter().collect();
+    per_mirror.sort_by_key(|(entity, _)| mirror_order(**entity));
+    for (i, (entity, mirror)) in per_mirror.iter().enumerate() {
+        let branch = if i + 1 == per_mirror.len() { "└─" } else { "├─" };
+        println!("│  {} {}: {} reflected ({} scattered), {} absorbed, {} vignetted ({:.2}%, {:.3} J), {} out of sequence",
+            branch, mirror_label(**entity), mirror.reflections, mirror.scattered, mirror.absorptions, mirror.vignetted,
+            mirror.vignetting_fraction() * 100.0, mirror.vignetted_energy, mirror.out_of_sequence);
+    }
+
+    if let Some(illuminator) = world.get_resource::<Illuminator>() {
+        let illumination = &ray_stats.illumination;
+        println!("\n┌─ Illumination ({} fill, {} pupil facets lit, {} field facets parked)",
+            illuminator.fill, illuminator.lit_pupil_facets, illuminator.parked_field_facets);
+        println!("│  ├─ IF → reticle transmission: {:.1}% ({:.3} J of {:.3} J)", illumination.transmission() * 100.0,
+            illumination.reticle_energy, illumination.intermediate_focus_energy);
+        println!("│  ├─ Slit non-uniformity: {:.1}% across ±{:.0} mm",
+            illumination.slit_uniformity() * 100.0, illuminator.monitor.slit_half_width * 1e3);
+        println!("│  └─ Pupil intensity at the reticle (σ from -1 to 1, σy = +1 at top):");
+        for row in illumination.pupil_rows() {
+            println!("│       |{}|", row);
+        }
+    }
+
+    let reticle_stats = &ray_stats.reticle;
+    let reticle = world.iter_entities().find_map(|entity| Some((entity.get::<Reticle>()?, entity.get::<ThermalState>()?)));
+    if let Some((reticle, thermal)) = reticle.filter(|_| reticle_stats.incident() > 0.0) {
+        let (absorber, blank) = reticle_stats.reflectances();
+        println!("\n┌─ Reticle ({:.0} nm absorber, δ = {}, β = {})", reticle.absorber.thickness, reticle.absorber.delta, reticle.absorber.beta);
+        println!("│  ├─ In-band light on absorber: {:.1}% of {:.3} J", reticle_stats.absorber_coverage() * 100.0, reticle_stats.incident());
+        println!("│  ├─ Reflectance: blank {:.1}%, absorber {:.2}% (contrast {:.3})",
+            blank * 100.0, absorber * 100.0, reticle_stats.contrast());
+        println!("│  ├─ Heat absorbed: {:.3} J ({:.3} J in absorber, {:.3} J in blank)",
+            reticle_stats.heat(), reticle_stats.absorber_heat, reticle_stats.blank_heat);
+        println!("│  └─ Temperature: {:.2} K (+{:.3} K)", thermal.temperature, thermal.temperature - ThermalState::AMBIENT);
+    }
+
+    let debris_stats = world.resource::<DebrisStatistics>();
+    let gas = world.resource::<BufferGasConfig>();
+    println!("\n┌─ Debris Statistics (H2 at {:.0} Pa)", gas.pressure);
+    println!("│  ├─ Tin released: {:.3} ng", DebrisStatistics::total(&debris_stats.released_mass) * 1e12);
+    println!("│  ├─ Thermalized by buffer gas: {:.3} ng", DebrisStatistics::total(&debris_stats.thermalized_mass) * 1e12);
+    println!("│  ├─ Deposited on mirrors: {:.3} ng ({:.2}%)",
+        DebrisStatistics::total(&debris_stats.mirror_deposited_mass) * 1e12, debris_stats.mirror_deposition_fraction() * 100.0);
+    println!("│  ├─ Deposited on chamber wall: {:.3} ng", DebrisStatistics::total(&debris_stats.wall_deposited_mass) * 1e12);
+    println!("│  ├─ Heaviest mirror deposit: {:.3} ng", heaviest_deposit * 1e12);
+    println!("│  ├─ Fast-ion range at 1.5 keV: {:.1} cm", gas.atom_range(1_500.0) * 100.0);
+    println!("│  └─ Debris particles in flight: {}", debris_stats.active_particles);
+
+    let plasma_pulses = world.resource::<EmissionStatistics>().pulse_count;
+    let repetition_rate = generator.frequency as f64;
+    println!("\n┌─ Collector Lifetime Projection (accelerated, swap at 10% reflectivity loss)");
+    contaminated_mirrors.sort_by_key(|(entity, _, _)| mirror_order(*entity));
+    for (i, (entity, deposit, contamination)) in contaminated_mirrors.iter().enumerate() {
+        let branch = if i + 1 == contaminated_mirrors.len() { "└─" } else { "├─" };
+        let mass_per_pulse = if plasma_pulses > 0 { deposit.total_mass() / plasma_pulses as f64 } else { 0.0 };
+        let projection = project_contamination(contamination, mass_per_pulse, repetition_rate, 1_000_000_000_000, 64, 0.9);
+        let lifetime = match projection.time_to_swap {
+            Some(seconds) => format!("swap after {:.3e} pulses ({:.1} h)", projection.pulses_to_swap.unwrap_or(0) as f64, seconds / 3600.0),
+            None => "no swap within 1e12 pulses".to_string(),
+        };
+        println!("│  {} {}: film {:.3} pm now (R × {:.4}), cleaned {:.3} pm, {}",
+            branch, mirror_label(*entity), contamination.tin_thickness * 1e12, contamination.reflectivity_factor(),
+            contamination.cleaned_thickness * 1e12, lifetime);
+        let curve = &projection.reflectivity_curve;
+        let samples: Vec<String> = (1..=4)
+            .map(|k| curve[(curve.len() - 1) * k / 4])
+            .map(|(pulses, factor)| format!("{:.2e}: {:.3}", pulses as f64, factor))
+            .collect();
+        let stem = if i + 1 == contaminated_mirrors.len() { " " } else { "│" };
+        println!("│  {}    R × (pulses) {} — final film {:.3} nm", stem, samples.join(", "), projection.final_thickness * 1e9);
+    }
 
     println!("\n┌─ Thermal Statistics");
     println!("│  ├─ Max temperature: {:.2} K", thermal_stats.max_temperature);
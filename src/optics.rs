@@ -1,21 +1,172 @@
 //! Optical subsystem: Mirrors, reflectors, and light transport
 
 use bevy_ecs::prelude::*;
-use glam::{Vec3, Quat};
+use glam::{DQuat, DVec2, DVec3, Mat3, Vec2, Vec3, Quat};
+use crate::bvh::Aabb;
 use crate::units::{Position3D, Distance};
 use crate::components::*;
 use crate::thermal::CoolingSystem;
+use crate::debris::SurfaceDeposit;
+use crate::contamination::ContaminationState;
+use crate::coating::{MultilayerCoating, MultilayerStack};
+use crate::scatter::SurfaceRoughness;
+use crate::zernike;
 
 #[derive(Component, Debug, Clone)]
 pub struct MirrorSurface {
     pub geometry: SurfaceGeometry,
+    /// Rotation from the mirror's local frame (z = optical axis) to the world
     pub orientation: Quat,
-    pub radius: Distance,
+    /// Clear aperture in the local xy-plane, centred on the mirror's position
+    pub aperture: Aperture,
+}
+
+impl MirrorSurface {
+    /// Projects a world point onto the aperture plane in the mirror's local frame
+    pub fn aperture_coordinates(&self, mirror_position: Position3D, point: Position3D) -> Vec2 {
+        let local = self.orientation.inverse() * (point.to_vec3() - mirror_position.to_vec3());
+        local.truncate()
+    }
+
+    /// True if a surface point lies inside the clear aperture
+    pub fn in_aperture(&self, mirror_position: Position3D, point: Position3D) -> bool {
+        self.aperture.contains(self.aperture_coordinates(mirror_position, point))
+    }
+}
+
+/// Clear-aperture shapes in the mirror's local xy-plane (meters)
+#[derive(Debug, Clone)]
+pub enum Aperture {
+    Circular {
+        radius: f32,
+    },
+    /// Annulus with a central obscuration (e.g. the collector's laser hole)
+    Annular {
+        inner_radius: f32,
+        outer_radius: f32,
+    },
+    Rectangular {
+        half_width: f32,
+        half_height: f32,
+    },
+    /// Simple polygon, vertices in order
+    Polygon {
+        vertices: Vec<Vec2>,
+    },
+}
+
+/// Parses `circle:r`, `annulus:r_inner,r_outer`, `rect:half_width,half_height`
+/// or `polygon:x1,y1;x2,y2;...` (meters)
+impl std::str::FromStr for Aperture {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, values) = s.split_once(':').ok_or_else(|| format!("missing ':' in aperture '{s}'"))?;
+        let numbers = |text: &str| -> Result<Vec<f32>, String> {
+            text.split(',')
+                .map(|v| v.trim().parse::<f32>().map_err(|e| format!("bad number '{v}': {e}")))
+                .collect()
+        };
+        match (kind.trim(), numbers(values).as_deref()) {
+            ("circle", Ok(&[radius])) => Ok(Aperture::Circular { radius }),
+            ("annulus", Ok(&[inner_radius, outer_radius])) if inner_radius < outer_radius => {
+                Ok(Aperture::Annular { inner_radius, outer_radius })
+            }
+            ("rect", Ok(&[half_width, half_height])) => Ok(Aperture::Rectangular { half_width, half_height }),
+            ("polygon", _) => {
+                let vertices = values
+                    .split(';')
+                    .map(|pair| match numbers(pair)?.as_slice() {
+                        &[x, y] => Ok(Vec2::new(x, y)),
+                        _ => Err(format!("polygon vertex '{pair}' needs two coordinates")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if vertices.len() < 3 {
+                    return Err("polygon needs at least three vertices".to_string());
+                }
+                Ok(Aperture::Polygon { vertices })
+            }
+            _ => Err(format!("unrecognised aperture '{s}'")),
+        }
+    }
+}
+
+/// Writes the form accepted by `FromStr`
+impl std::fmt::Display for Aperture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Aperture::Circular { radius } => write!(f, "circle:{radius}"),
+            Aperture::Annular { inner_radius, outer_radius } => write!(f, "annulus:{inner_radius},{outer_radius}"),
+            Aperture::Rectangular { half_width, half_height } => write!(f, "rect:{half_width},{half_height}"),
+            Aperture::Polygon { vertices } => {
+                let pairs: Vec<String> = vertices.iter().map(|v| format!("{},{}", v.x, v.y)).collect();
+                write!(f, "polygon:{}", pairs.join(";"))
+            }
+        }
+    }
+}
+
+impl Aperture {
+    pub fn contains(&self, p: Vec2) -> bool {
+        match self {
+            Aperture::Circular { radius } => p.length_squared() <= radius * radius,
+            Aperture::Annular { inner_radius, outer_radius } => {
+                let r2 = p.length_squared();
+                r2 >= inner_radius * inner_radius && r2 <= outer_radius * outer_radius
+            }
+            Aperture::Rectangular { half_width, half_height } => {
+                p.x.abs() <= *half_width && p.y.abs() <= *half_height
+            }
+            Aperture::Polygon { vertices } => {
+                // Even-odd crossing test
+                let mut inside = false;
+                let n = vertices.len();
+                for i in 0..n {
+                    let a = vertices[i];
+                    let b = vertices[(i + n - 1) % n];
+                    if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+                        inside = !inside;
+                    }
+                }
+                inside
+            }
+        }
+    }
+
+    /// Clear area (m²)
+    pub fn area(&self) -> f32 {
+        use std::f32::consts::PI;
+        match self {
+            Aperture::Circular { radius } => PI * radius * radius,
+            Aperture::Annular { inner_radius, outer_radius } => {
+                PI * (outer_radius * outer_radius - inner_radius * inner_radius)
+            }
+            Aperture::Rectangular { half_width, half_height } => 4.0 * half_width * half_height,
+            Aperture::Polygon { vertices } => {
+                // Shoelace formula
+                let n = vertices.len();
+                let twice_area: f32 = (0..n).map(|i| vertices[i].perp_dot(vertices[(i + 1) % n])).sum();
+                twice_area.abs() / 2.0
+            }
+        }
+    }
+
+    /// Radius of the smallest centred circle enclosing the aperture (m)
+    pub fn outer_radius(&self) -> f32 {
+        match self {
+            Aperture::Circular { radius } => *radius,
+            Aperture::Annular { outer_radius, .. } => *outer_radius,
+            Aperture::Rectangular { half_width, half_height } => Vec2::new(*half_width, *half_height).length(),
+            Aperture::Polygon { vertices } => vertices.iter().map(|v| v.length()).fold(0.0, f32::max),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum SurfaceGeometry {
+    /// Ellipsoid centred midway between its foci; local x runs along the focal axis
     Ellipsoid {
+        /// Semi-axes in the local frame
         semi_axes: Vec3,
         focus1: Position3D,
         focus2: Position3D,
@@ -27,21 +178,289 @@ pub enum SurfaceGeometry {
     Planar {
         normal: Vec3,
     },
+    /// General asphere or freeform described by its sag along the local z-axis
+    Sag {
+        vertex: Position3D,
+        /// Rotation from the surface's local frame (z = surface axis) to the world
+        orientation: Quat,
+        profile: SagProfile,
+    },
+}
+
+/// Conic base plus even asphere and freeform departures, z = sag(x, y)
+///
+/// Lengths are in meters; evaluation runs in f64 because high-order
+/// coefficients multiply large powers of the radial coordinate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SagProfile {
+    /// Vertex curvature 1/R (1/m); zero for a flat base
+    pub curvature: f64,
+    /// Conic constant: 0 sphere, -1 paraboloid, < -1 hyperboloid
+    pub conic: f64,
+    /// Coefficients of r⁴, r⁶, r⁸, ...
+    pub even_asphere: Vec<f64>,
+    pub freeform: Freeform,
+    /// Radius of the surface in its local xy-plane, also the normalisation
+    /// radius of the freeform terms (m)
+    pub semi_diameter: f64,
+}
+
+/// Departure from the rotationally symmetric part of a sag profile
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Freeform {
+    #[default]
+    None,
+    /// Coefficients (m) of Noll-ordered Zernike terms, starting at j = 1
+    Zernike(Vec<f64>),
+    /// Σ c·xⁱyʲ over normalised coordinates
+    XyPolynomial(Vec<XyTerm>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct XyTerm {
+    pub x_power: u32,
+    pub y_power: u32,
+    /// Sag contribution at the normalised point (1, 1) (m)
+    pub coefficient: f64,
+}
+
+impl SagProfile {
+    /// Conic surface of revolution with vertex radius `radius` (infinite for flat)
+    pub fn conic(radius: f64, conic: f64, semi_diameter: f64) -> Self {
+        Self {
+            curvature: if radius.is_infinite() { 0.0 } else { 1.0 / radius },
+            conic,
+            even_asphere: Vec::new(),
+            freeform: Freeform::None,
+            semi_diameter,
+        }
+    }
+
+    /// Sag and its gradient at local (x, y), or `None` beyond the conic's edge
+    pub fn sag_and_gradient(&self, x: f64, y: f64) -> Option<(f64, DVec2)> {
+        let c = self.curvature;
+        let r2 = x * x + y * y;
+        let root = 1.0 - (1.0 + self.conic) * c * c * r2;
+        if root < 0.0 {
+            return None;
+        }
+        let root = root.sqrt();
+        let mut sag = c * r2 / (1.0 + root);
+        // d(sag)/dr = c·r / root, so each Cartesian slope is c·x / root
+        let mut gradient = DVec2::new(x, y) * (c / root);
+
+        let mut power = r2;
+        for (i, coefficient) in self.even_asphere.iter().enumerate() {
+            // Term A·r^(2i+4), slope (2i+4)·A·r^(2i+2)·(x, y)/r²
+            let exponent = 2.0 * i as f64 + 4.0;
+            gradient += DVec2::new(x, y) * (coefficient * exponent * power);
+            power *= r2;
+            sag += coefficient * power;
+        }
+
+        let scale = self.semi_diameter;
+        let (u, v) = (x / scale, y / scale);
+        match &self.freeform {
+            Freeform::None => {}
+            Freeform::Zernike(coefficients) => {
+                for (j, coefficient) in (1..).zip(coefficients) {
+                    let (value, slope) = zernike::zernike(j, u, v);
+                    sag += coefficient * value;
+                    gradient += DVec2::from(slope) * (coefficient / scale);
+                }
+            }
+            Freeform::XyPolynomial(terms) => {
+                for term in terms {
+                    let (i, j) = (term.x_power as i32, term.y_power as i32);
+                    sag += term.coefficient * u.powi(i) * v.powi(j);
+                    if i > 0 {
+                        gradient.x += term.coefficient * f64::from(i) * u.powi(i - 1) * v.powi(j) / scale;
+                    }
+                    if j > 0 {
+                        gradient.y += term.coefficient * f64::from(j) * u.powi(i) * v.powi(j - 1) / scale;
+                    }
+                }
+            }
+        }
+        Some((sag, gradient))
+    }
+
+    pub fn sag(&self, x: f64, y: f64) -> Option<f64> {
+        self.sag_and_gradient(x, y).map(|(sag, _)| sag)
+    }
+
+    /// Unit normal (-∂z/∂x, -∂z/∂y, 1) in the local frame
+    pub fn normal(&self, x: f64, y: f64) -> DVec3 {
+        match self.sag_and_gradient(x, y) {
+            Some((_, gradient)) => DVec3::new(-gradient.x, -gradient.y, 1.0).normalize(),
+            None => DVec3::Z,
+        }
+    }
+
+    /// Lowest and highest sag over the disk, sampled on a polar grid and padded
+    fn sag_range(&self) -> (f64, f64) {
+        const RINGS: usize = 64;
+        const SPOKES: usize = 64;
+        let mut low = 0.0f64;
+        let mut high = 0.0f64;
+        for ring in 1..=RINGS {
+            let r = self.semi_diameter * ring as f64 / RINGS as f64;
+            for spoke in 0..SPOKES {
+                let angle = std::f64::consts::TAU * spoke as f64 / SPOKES as f64;
+                if let Some(sag) = self.sag(r * angle.cos(), r * angle.sin()) {
+                    low = low.min(sag);
+                    high = high.max(sag);
+                }
+            }
+        }
+        let pad = 0.05 * (high - low) + 1e-6;
+        (low - pad, high + pad)
+    }
+
+    /// Nearest crossing at or beyond the ray origin, in the local frame
+    ///
+    /// Starts from the exact conic crossing and refines it with Newton steps
+    /// on the full sag. Crossings outside the semi-diameter are rejected.
+    fn ray_intersection(&self, origin: DVec3, direction: DVec3) -> Option<f64> {
+        const MAX_ITERATIONS: usize = 50;
+        const TOLERANCE: f64 = 1e-12;
+
+        let mut t = self.base_conic_intersection(origin, direction).or_else(|| {
+            // Crossing of the vertex plane, or the origin itself for rays parallel to it
+            (direction.z.abs() > 1e-12).then(|| -origin.z / direction.z).filter(|t| *t >= 0.0)
+        }).unwrap_or(0.0);
+
+        let mut converged = false;
+        for _ in 0..MAX_ITERATIONS {
+            let p = origin + direction * t;
+            let (sag, gradient) = self.sag_and_gradient(p.x, p.y)?;
+            let residual = p.z - sag;
+            let slope = direction.z - gradient.dot(direction.truncate());
+            if slope.abs() < 1e-15 {
+                return None;
+            }
+            let step = residual / slope;
+            t -= step;
+            if step.abs() < TOLERANCE {
+                converged = true;
+                break;
+            }
+        }
+
+        let p = origin + direction * t;
+        let inside = p.truncate().length() <= self.semi_diameter;
+        (converged && t >= 0.0 && inside).then_some(t)
+    }
+
+    /// Nearest crossing with the conic base on its vertex branch
+    fn base_conic_intersection(&self, origin: DVec3, direction: DVec3) -> Option<f64> {
+        // c(x² + y² + (1+k)z²) - 2z = 0 along the ray
+        let c = self.curvature;
+        let k1 = 1.0 + self.conic;
+        let (o, d) = (origin, direction);
+        let a = c * (d.x * d.x + d.y * d.y + k1 * d.z * d.z);
+        let b = 2.0 * c * (o.x * d.x + o.y * d.y + k1 * o.z * d.z) - 2.0 * d.z;
+        let c_term = c * (o.x * o.x + o.y * o.y + k1 * o.z * o.z) - 2.0 * o.z;
+
+        let roots = if a.abs() < 1e-15 {
+            if b.abs() < 1e-15 {
+                return None;
+            }
+            [-c_term / b, f64::INFINITY]
+        } else {
+            let discriminant = b * b - 4.0 * a * c_term;
+            if discriminant < 0.0 {
+                return None;
+            }
+            let sqrt_disc = discriminant.sqrt();
+            let (t1, t2) = ((-b - sqrt_disc) / (2.0 * a), (-b + sqrt_disc) / (2.0 * a));
+            [t1.min(t2), t1.max(t2)]
+        };
+
+        // The quadric's far sheet also satisfies the equation; keep roots on the sag branch
+        roots.into_iter().filter(|t| t.is_finite() && *t >= 0.0).find(|t| {
+            let p = o + d * *t;
+            let r2 = p.x * p.x + p.y * p.y;
+            let root = 1.0 - k1 * c * c * r2;
+            root >= 0.0 && (p.z - c * r2 / (1.0 + root.sqrt())).abs() <= 1e-9 * (1.0 + p.z.abs())
+        })
+    }
 }
 
 impl SurfaceGeometry {
+    /// Ellipsoid of revolution that images `focus1` onto `focus2`
+    ///
+    /// Returns `None` if the semi-major axis is shorter than half the focal separation.
+    pub fn ellipsoid_from_foci(focus1: Position3D, focus2: Position3D, semi_major_axis: f32) -> Option<Self> {
+        let half_separation = focus1.to_vec3().distance(focus2.to_vec3()) / 2.0;
+        if semi_major_axis <= half_separation {
+            return None;
+        }
+        let semi_minor_axis = (semi_major_axis * semi_major_axis - half_separation * half_separation).sqrt();
+        Some(SurfaceGeometry::Ellipsoid {
+            semi_axes: Vec3::new(semi_major_axis, semi_minor_axis, semi_minor_axis),
+            focus1,
+            focus2,
+        })
+    }
+
+    /// Centre and local-to-world rotation of an ellipsoid defined by its foci
+    fn ellipsoid_frame(focus1: Position3D, focus2: Position3D) -> (Vec3, Quat) {
+        let f1 = focus1.to_vec3();
+        let f2 = focus2.to_vec3();
+        let axis = (f2 - f1).normalize_or_zero();
+        let rotation = if axis == Vec3::ZERO {
+            Quat::IDENTITY
+        } else {
+            Quat::from_rotation_arc(Vec3::X, axis)
+        };
+        ((f1 + f2) * 0.5, rotation)
+    }
+
+    /// World-space bounds of the whole surface, or `None` if it is unbounded
+    ///
+    /// Bounds enclose every point `ray_intersection` can return, including
+    /// points outside the clear aperture, so culling never changes which
+    /// crossings a ray finds.
+    pub fn bounds(&self) -> Option<Aabb> {
+        match self {
+            SurfaceGeometry::Spherical { radius, center } => Some(Aabb::from_center_half_extents(
+                center.to_vec3(),
+                Vec3::splat(radius.as_meters_f64() as f32),
+            )),
+            SurfaceGeometry::Ellipsoid { semi_axes, focus1, focus2 } => {
+                let (center, rotation) = Self::ellipsoid_frame(*focus1, *focus2);
+                // Half-extent along each world axis of the rotated ellipsoid
+                let axes = Mat3::from_quat(rotation) * Mat3::from_diagonal(*semi_axes);
+                let rows = axes.transpose();
+                let half_extents = Vec3::new(rows.x_axis.length(), rows.y_axis.length(), rows.z_axis.length());
+                Some(Aabb::from_center_half_extents(center, half_extents))
+            }
+            SurfaceGeometry::Planar { .. } => None,
+            SurfaceGeometry::Sag { vertex, orientation, profile } => {
+                let (low, high) = profile.sag_range();
+                let semi_diameter = profile.semi_diameter as f32;
+                let local_center = Vec3::new(0.0, 0.0, ((low + high) / 2.0) as f32);
+                let local_half = Vec3::new(semi_diameter, semi_diameter, ((high - low) / 2.0) as f32);
+                let rotation = Mat3::from_quat(*orientation);
+                let abs = Mat3::from_cols(rotation.x_axis.abs(), rotation.y_axis.abs(), rotation.z_axis.abs());
+                Some(Aabb::from_center_half_extents(
+                    vertex.to_vec3() + *orientation * local_center,
+                    abs * local_half,
+                ))
+            }
+        }
+    }
+
     pub fn normal_at(&self, point: Position3D) -> Vec3 {
         match self {
-            SurfaceGeometry::Ellipsoid { semi_axes, focus1, .. } => {
-                let center = focus1.to_vec3();
-                let p = point.to_vec3() - center;
-                
-                let normal = Vec3::new(
-                    2.0 * p.x / (semi_axes.x * semi_axes.x),
-                    2.0 * p.y / (semi_axes.y * semi_axes.y),
-                    2.0 * p.z / (semi_axes.z * semi_axes.z),
-                );
-                normal.normalize()
+            SurfaceGeometry::Ellipsoid { semi_axes, focus1, focus2 } => {
+                let (center, rotation) = Self::ellipsoid_frame(*focus1, *focus2);
+                let p = rotation.inverse() * (point.to_vec3() - center);
+
+                // Gradient of the implicit surface in the local frame, rotated back
+                let normal = p / (*semi_axes * *semi_axes);
+                (rotation * normal).normalize()
             }
             SurfaceGeometry::Spherical { center, .. } => {
                 let p = point.to_vec3();
@@ -49,6 +468,10 @@ impl SurfaceGeometry {
                 (p - c).normalize()
             }
             SurfaceGeometry::Planar { normal } => *normal,
+            SurfaceGeometry::Sag { vertex, orientation, profile } => {
+                let p = (orientation.inverse() * (point.to_vec3() - vertex.to_vec3())).as_dvec3();
+                (*orientation * profile.normal(p.x, p.y).as_vec3()).normalize()
+            }
         }
     }
 
@@ -64,14 +487,152 @@ impl SurfaceGeometry {
             SurfaceGeometry::Planar { normal } => {
                 Self::ray_plane_intersection(ray_origin, ray_direction, *normal)
             }
-            SurfaceGeometry::Ellipsoid { semi_axes, focus1, .. } => {
-                let max_axis = semi_axes.x.max(semi_axes.y).max(semi_axes.z);
-                let approx_radius = Distance::from_meters_f64(max_axis as f64);
-                Self::ray_sphere_intersection(ray_origin, ray_direction, *focus1, approx_radius)
+            SurfaceGeometry::Ellipsoid { semi_axes, focus1, focus2 } => {
+                Self::ray_ellipsoid_intersection(ray_origin, ray_direction, *semi_axes, *focus1, *focus2)
+            }
+            SurfaceGeometry::Sag { .. } => {
+                let d = ray_direction.normalize();
+                match self.ray_intersection_f64(ray_origin.to_dvec3(), d.as_dvec3()) {
+                    Some(t) => {
+                        let hit_point = ray_origin.to_vec3() + d * t as f32;
+                        (true, Some(Position3D::from_vec3(hit_point)), t as f32)
+                    }
+                    None => (false, None, f32::INFINITY),
+                }
+            }
+        }
+    }
+
+    /// Double-precision counterpart of [`Self::ray_intersection`] for image
+    /// analysis, where path differences of a nanometer matter; returns the
+    /// distance to the nearest crossing at or beyond the origin
+    pub fn ray_intersection_f64(&self, origin: DVec3, direction: DVec3) -> Option<f64> {
+        let d = direction.normalize();
+        // Nearest non-negative root of a·t² + b·t + c = 0, far root for origins inside
+        let nearest_root = |a: f64, b: f64, c: f64| {
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return None;
+            }
+            let sqrt_disc = discriminant.sqrt();
+            let t_near = (-b - sqrt_disc) / (2.0 * a);
+            let t_far = (-b + sqrt_disc) / (2.0 * a);
+            let t = if t_near >= 0.0 { t_near } else { t_far };
+            (t >= 0.0).then_some(t)
+        };
+        match self {
+            SurfaceGeometry::Spherical { radius, center } => {
+                let oc = origin - center.to_dvec3();
+                let r = radius.as_meters_f64();
+                nearest_root(1.0, 2.0 * oc.dot(d), oc.dot(oc) - r * r)
+            }
+            SurfaceGeometry::Ellipsoid { semi_axes, focus1, focus2 } => {
+                let (center, rotation) = Self::ellipsoid_frame_f64(*focus1, *focus2);
+                let inverse = rotation.inverse();
+                let axes = Self::ellipsoid_axes_f64(*semi_axes, *focus1, *focus2);
+                let o = inverse * (origin - center) / axes;
+                let ds = inverse * d / axes;
+                nearest_root(ds.dot(ds), 2.0 * o.dot(ds), o.dot(o) - 1.0)
+            }
+            SurfaceGeometry::Planar { normal } => {
+                let n = normal.as_dvec3().normalize();
+                let denom = n.dot(d);
+                if denom.abs() < 1e-12 {
+                    return None;
+                }
+                let t = -n.dot(origin) / denom;
+                (t >= 0.0).then_some(t)
+            }
+            SurfaceGeometry::Sag { vertex, orientation, profile } => {
+                let inverse = orientation.as_dquat().inverse();
+                let local_origin = inverse * (origin - vertex.to_dvec3());
+                profile.ray_intersection(local_origin, (inverse * d).normalize())
+            }
+        }
+    }
+
+    /// Double-precision counterpart of [`Self::normal_at`]
+    pub fn normal_at_f64(&self, point: DVec3) -> DVec3 {
+        match self {
+            SurfaceGeometry::Ellipsoid { semi_axes, focus1, focus2 } => {
+                let (center, rotation) = Self::ellipsoid_frame_f64(*focus1, *focus2);
+                let axes = Self::ellipsoid_axes_f64(*semi_axes, *focus1, *focus2);
+                let p = rotation.inverse() * (point - center);
+                (rotation * (p / (axes * axes))).normalize()
+            }
+            SurfaceGeometry::Spherical { center, .. } => (point - center.to_dvec3()).normalize(),
+            SurfaceGeometry::Planar { normal } => normal.as_dvec3().normalize(),
+            SurfaceGeometry::Sag { vertex, orientation, profile } => {
+                let rotation = orientation.as_dquat();
+                let p = rotation.inverse() * (point - vertex.to_dvec3());
+                (rotation * profile.normal(p.x, p.y)).normalize()
             }
         }
     }
 
+    /// Semi-axes with the minor axes of an ellipsoid of revolution recomputed
+    /// in f64, so its foci sit exactly at `focus1` and `focus2`
+    fn ellipsoid_axes_f64(semi_axes: Vec3, focus1: Position3D, focus2: Position3D) -> DVec3 {
+        let axes = semi_axes.as_dvec3();
+        if semi_axes.y != semi_axes.z {
+            return axes;
+        }
+        let half_separation = focus1.to_dvec3().distance(focus2.to_dvec3()) / 2.0;
+        let minor = (axes.x * axes.x - half_separation * half_separation).sqrt();
+        DVec3::new(axes.x, minor, minor)
+    }
+
+    fn ellipsoid_frame_f64(focus1: Position3D, focus2: Position3D) -> (DVec3, DQuat) {
+        let f1 = focus1.to_dvec3();
+        let f2 = focus2.to_dvec3();
+        let axis = (f2 - f1).normalize_or_zero();
+        let rotation = if axis == DVec3::ZERO {
+            DQuat::IDENTITY
+        } else {
+            DQuat::from_rotation_arc(DVec3::X, axis)
+        };
+        ((f1 + f2) * 0.5, rotation)
+    }
+
+    fn ray_ellipsoid_intersection(
+        ray_origin: Position3D,
+        ray_direction: Vec3,
+        semi_axes: Vec3,
+        focus1: Position3D,
+        focus2: Position3D,
+    ) -> (bool, Option<Position3D>, f32) {
+        let (center, rotation) = Self::ellipsoid_frame(focus1, focus2);
+        let d = ray_direction.normalize();
+
+        // Scale the local frame so the ellipsoid becomes the unit sphere;
+        // the ray parameter t is unchanged by the linear map
+        let inverse = rotation.inverse();
+        let o = inverse * (ray_origin.to_vec3() - center) / semi_axes;
+        let ds = inverse * d / semi_axes;
+
+        let a = ds.dot(ds);
+        let b = 2.0 * o.dot(ds);
+        let c_term = o.dot(o) - 1.0;
+
+        let discriminant = b * b - 4.0 * a * c_term;
+        if discriminant < 0.0 {
+            return (false, None, f32::INFINITY);
+        }
+
+        // Far root when the origin lies inside (e.g. light leaving the plasma at focus1)
+        let sqrt_disc = discriminant.sqrt();
+        let t_near = (-b - sqrt_disc) / (2.0 * a);
+        let t_far = (-b + sqrt_disc) / (2.0 * a);
+        let t = if t_near >= 0.0 { t_near } else { t_far };
+
+        if t < 0.0 {
+            return (false, None, f32::INFINITY);
+        }
+
+        let hit_point = ray_origin.to_vec3() + d * t;
+        (true, Some(Position3D::from_vec3(hit_point)), t)
+    }
+
     fn ray_sphere_intersection(
         ray_origin: Position3D,
         ray_direction: Vec3,
@@ -94,8 +655,13 @@ impl SurfaceGeometry {
             return (false, None, f32::INFINITY);
         }
 
-        let t = (-b - discriminant.sqrt()) / (2.0 * a);
-        
+        // Nearest intersection in front of the origin; the far root applies
+        // when the origin lies inside the sphere
+        let sqrt_disc = discriminant.sqrt();
+        let t_near = (-b - sqrt_disc) / (2.0 * a);
+        let t_far = (-b + sqrt_disc) / (2.0 * a);
+        let t = if t_near >= 0.0 { t_near } else { t_far };
+
         if t < 0.0 {
             return (false, None, f32::INFINITY);
         }
@@ -138,10 +704,13 @@ pub struct OpticalSystemConfig {
 
 #[derive(Debug, Clone)]
 pub struct CollectorMirrorSpec {
+    /// Plasma position, the collector's first focus
     pub position: Position3D,
     pub semi_major_axis: f32,
-    pub semi_minor_axis: f32,
+    /// Distance from the plasma to the intermediate focus along +X
     pub focal_length: f32,
+    /// Radius of the central hole that passes the drive laser (m)
+    pub obscuration_radius: f32,
 }
 
 #[derive(Debug, Clone)]
@@ -158,14 +727,16 @@ impl Default for OpticalSystemConfig {
             collector_mirror: CollectorMirrorSpec {
                 position: Position3D::zero(),
                 semi_major_axis: 0.3,
-                semi_minor_axis: 0.2,
                 focal_length: 0.5,
+                obscuration_radius: 0.02,
             },
             projection_mirrors: vec![
+                // 1 m past the intermediate focus, its focal length, so the
+                // beam leaves collimated; folded 14° back towards the second
                 MirrorSpec {
                     id: 1,
                     position: Position3D::new(
-                        Distance::from_meters(1),
+                        Distance::from_millimeters(1500),
                         Distance::ZERO,
                         Distance::ZERO,
                     ),
@@ -175,11 +746,11 @@ impl Default for OpticalSystemConfig {
                 MirrorSpec {
                     id: 2,
                     position: Position3D::new(
-                        Distance::from_meters(2),
                         Distance::from_millimeters(500),
+                        Distance::from_millimeters(250),
                         Distance::ZERO,
                     ),
-                    radius: Distance::from_millimeters(150),
+                    radius: Distance::from_millimeters(200),
                     curvature_radius: Distance::from_meters(3),
                 },
             ],
@@ -187,57 +758,94 @@ impl Default for OpticalSystemConfig {
     }
 }
 
+/// Spawns the collector and projection mirrors and returns them, in that
+/// order, as the sequence for sequential ray tracing
 pub fn spawn_optical_system(
     mut commands: Commands,
     config: Res<OpticalSystemConfig>,
-) {
+) -> Vec<Entity> {
     let collector = &config.collector_mirror;
-    
-    commands.spawn((
-        Position(collector.position),
-        MirrorSurface {
-            geometry: SurfaceGeometry::Ellipsoid {
-                semi_axes: Vec3::new(
-                    collector.semi_major_axis,
-                    collector.semi_minor_axis,
-                    collector.semi_minor_axis,
-                ),
-                focus1: Position3D::zero(),
-                focus2: Position3D::new(
-                    Distance::from_meters_f64(collector.focal_length as f64),
-                    Distance::ZERO,
-                    Distance::ZERO,
-                ),
-            },
-            orientation: Quat::IDENTITY,
-            radius: Distance::from_meters_f64(collector.semi_major_axis as f64),
+    let intermediate_focus = Position3D::from_vec3(
+        collector.position.to_vec3() + Vec3::X * collector.focal_length,
+    );
+    let geometry = SurfaceGeometry::ellipsoid_from_foci(
+        collector.position,
+        intermediate_focus,
+        collector.semi_major_axis,
+    )
+    .expect("collector semi-major axis must exceed half the focal length");
+    let outer_radius = match &geometry {
+        SurfaceGeometry::Ellipsoid { semi_axes, .. } => semi_axes.y,
+        _ => collector.semi_major_axis,
+    };
+    let collector_surface = MirrorSurface {
+        geometry,
+        // Local z along the focal axis
+        orientation: Quat::from_rotation_arc(Vec3::Z, Vec3::X),
+        aperture: Aperture::Annular {
+            inner_radius: collector.obscuration_radius,
+            outer_radius,
         },
+    };
+
+    // Every mirror shares one coating design and its reflectivity table
+    let coating = MultilayerCoating::new(MultilayerStack::mo_si());
+
+    let mut sequence = Vec::with_capacity(1 + config.projection_mirrors.len());
+    sequence.push(commands.spawn((
+        Position(collector.position),
+        ContaminationState::for_mirror(&collector_surface, ContaminationState::DEFAULT_CLEANING_RATE),
+        collector_surface,
         OpticalMaterial::BRAGG_MIRROR,
+        coating.clone(),
         ThermalState::new(ThermalState::AMBIENT, 5000.0),
+        SurfaceDeposit::default(),
         EntityType::Mirror,
-    ));
+    )).id());
 
-    for mirror_spec in config.projection_mirrors.iter() {
-        commands.spawn((
-            Position(mirror_spec.position),
-            MirrorSurface {
-                geometry: SurfaceGeometry::Spherical {
-                    radius: mirror_spec.curvature_radius,
-                    center: mirror_spec.position,
-                },
-                orientation: Quat::IDENTITY,
-                radius: mirror_spec.radius,
+    // Projection mirrors follow the collector in the order of their ids. The
+    // chief ray runs from the intermediate focus through each vertex in turn:
+    // every mirror faces the ray arriving from the previous vertex and folds
+    // it onto the next, and the last sends it straight back.
+    let mut projection_mirrors: Vec<&MirrorSpec> = config.projection_mirrors.iter().collect();
+    projection_mirrors.sort_by_key(|spec| spec.id);
+    for (index, mirror_spec) in projection_mirrors.iter().enumerate() {
+        let vertex = mirror_spec.position.to_vec3();
+        let previous = index.checked_sub(1).map_or(intermediate_focus, |i| projection_mirrors[i].position);
+        let incoming = (vertex - previous.to_vec3()).normalize();
+        let outgoing = projection_mirrors.get(index + 1).map_or(-incoming, |next| (next.position.to_vec3() - vertex).normalize());
+        // Local z: the vertex normal, on the concave side towards the light
+        let normal = (outgoing - incoming).normalize();
+        let surface = MirrorSurface {
+            geometry: SurfaceGeometry::Spherical {
+                radius: mirror_spec.curvature_radius,
+                center: Position3D::from_vec3(vertex + normal * mirror_spec.curvature_radius.as_meters_f64() as f32),
             },
+            orientation: Quat::from_rotation_arc(Vec3::Z, normal),
+            aperture: Aperture::Circular {
+                radius: mirror_spec.radius.as_meters_f64() as f32,
+            },
+        };
+        sequence.push(commands.spawn((
+            Position(mirror_spec.position),
+            ContaminationState::for_mirror(&surface, ContaminationState::DEFAULT_CLEANING_RATE),
+            surface,
             OpticalMaterial::BRAGG_MIRROR,
+            coating.clone(),
+            SurfaceRoughness::PROJECTION,
             ThermalState::new(ThermalState::AMBIENT, 2000.0),
+            SurfaceDeposit::default(),
             EntityType::Mirror,
-        ));
+        )).id());
     }
+    sequence
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
 
     #[test]
     fn test_sphere_normal() {
@@ -258,6 +866,234 @@ mod tests {
         assert!(normal.y.abs() < 1e-6);
     }
 
+    fn reflect(direction: Vec3, normal: Vec3) -> Vec3 {
+        direction - 2.0 * direction.dot(normal) * normal
+    }
+
+    #[test]
+    fn test_ellipsoid_focus_to_focus() {
+        // Tilted focal axis to exercise the local frame
+        let focus1 = Position3D::new(Distance::from_millimeters(100), Distance::from_millimeters(-50), Distance::ZERO);
+        let focus2 = Position3D::new(Distance::from_millimeters(900), Distance::from_millimeters(400), Distance::from_millimeters(300));
+        let a = 0.75;
+        let geometry = SurfaceGeometry::ellipsoid_from_foci(focus1, focus2, a).unwrap();
+        let f1 = focus1.to_vec3();
+        let f2 = focus2.to_vec3();
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..500 {
+            let direction = crate::plasma::sample_uniform_sphere(&mut rng);
+            let (hit, point, t) = geometry.ray_intersection(focus1, direction);
+            assert!(hit && t > 0.0);
+            let p = point.unwrap().to_vec3();
+
+            // On the surface: distances to the foci sum to 2a
+            assert!((p.distance(f1) + p.distance(f2) - 2.0 * a).abs() < 1e-4);
+
+            // Reflected ray passes through the second focus
+            let normal = geometry.normal_at(Position3D::from_vec3(p));
+            let reflected = reflect(direction, normal);
+            let to_focus = f2 - p;
+            let miss_distance = (to_focus - reflected * to_focus.dot(reflected)).length();
+            assert!(miss_distance < 1e-4, "reflected ray misses focus2 by {miss_distance} m");
+            assert!(to_focus.dot(reflected) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_ellipsoid_rejects_short_axis() {
+        let focus2 = Position3D::new(Distance::from_meters(1), Distance::ZERO, Distance::ZERO);
+        assert!(SurfaceGeometry::ellipsoid_from_foci(Position3D::zero(), focus2, 0.4).is_none());
+
+        let geometry = SurfaceGeometry::ellipsoid_from_foci(Position3D::zero(), focus2, 0.6).unwrap();
+        // Vertex beyond focus2 on the major axis: normal points outward along +X
+        let vertex = Position3D::from_vec3(Vec3::new(1.1, 0.0, 0.0));
+        assert!((geometry.normal_at(vertex) - Vec3::X).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_ellipsoid_bounds() {
+        let focus2 = Position3D::new(Distance::ZERO, Distance::ZERO, Distance::from_meters(1));
+        let geometry = SurfaceGeometry::ellipsoid_from_foci(Position3D::zero(), focus2, 0.75).unwrap();
+        let bounds = geometry.bounds().unwrap();
+        let b = (0.75f32 * 0.75 - 0.25).sqrt();
+        assert!((bounds.min - Vec3::new(-b, -b, -0.25)).length() < 1e-5);
+        assert!((bounds.max - Vec3::new(b, b, 1.25)).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_sag_known_values() {
+        let r = 0.05f64;
+        // Sphere: R - √(R² - r²); paraboloid: r²/2R; hyperboloid k = -2: (√(R² + r²) - R)
+        let radius = 0.4;
+        let sphere = SagProfile::conic(radius, 0.0, 0.1);
+        assert!((sphere.sag(r, 0.0).unwrap() - (radius - (radius * radius - r * r).sqrt())).abs() < 1e-15);
+        let parabola = SagProfile::conic(radius, -1.0, 0.1);
+        assert!((parabola.sag(0.0, r).unwrap() - r * r / (2.0 * radius)).abs() < 1e-15);
+        let hyperbola = SagProfile::conic(radius, -2.0, 0.1);
+        assert!((hyperbola.sag(r, 0.0).unwrap() - ((radius * radius + r * r).sqrt() - radius)).abs() < 1e-15);
+        // Beyond the edge of a hemisphere the sag is undefined
+        assert!(sphere.sag(0.5, 0.0).is_none());
+
+        let mut asphere = SagProfile::conic(f64::INFINITY, 0.0, 0.1);
+        asphere.even_asphere = vec![2.0, -3.0];
+        assert!((asphere.sag(r, 0.0).unwrap() - (2.0 * r.powi(4) - 3.0 * r.powi(6))).abs() < 1e-15);
+
+        // Zernike defocus of 1 nm RMS at the rim: √3 nm
+        let mut freeform = SagProfile::conic(f64::INFINITY, 0.0, 0.1);
+        freeform.freeform = Freeform::Zernike(vec![0.0, 0.0, 0.0, 1e-9]);
+        assert!((freeform.sag(0.0, 0.1).unwrap() - 3f64.sqrt() * 1e-9).abs() < 1e-18);
+    }
+
+    fn freeform_profile() -> SagProfile {
+        SagProfile {
+            curvature: 1.0 / 0.8,
+            conic: -0.7,
+            even_asphere: vec![1.5e-2, -4.0e-1],
+            freeform: Freeform::XyPolynomial(vec![
+                XyTerm { x_power: 2, y_power: 1, coefficient: 2e-5 },
+                XyTerm { x_power: 0, y_power: 3, coefficient: -1e-5 },
+            ]),
+            semi_diameter: 0.12,
+        }
+    }
+
+    #[test]
+    fn test_sag_gradient_matches_finite_difference() {
+        let mut zernike_profile = freeform_profile();
+        zernike_profile.freeform = Freeform::Zernike(vec![0.0, 1e-6, -2e-6, 3e-6, 0.0, 5e-7, 0.0, -8e-7]);
+        let h = 1e-7;
+        for profile in [freeform_profile(), zernike_profile] {
+            for &(x, y) in &[(0.03, -0.02), (-0.09, 0.05), (0.0, 0.11)] {
+                let (_, gradient) = profile.sag_and_gradient(x, y).unwrap();
+                let dx = (profile.sag(x + h, y).unwrap() - profile.sag(x - h, y).unwrap()) / (2.0 * h);
+                let dy = (profile.sag(x, y + h).unwrap() - profile.sag(x, y - h).unwrap()) / (2.0 * h);
+                assert!((gradient.x - dx).abs() < 1e-7 && (gradient.y - dy).abs() < 1e-7);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sag_sphere_matches_spherical_geometry() {
+        // Concave sphere of radius 2 m with its vertex at the origin, facing -z
+        let radius = 2.0;
+        let sag = SurfaceGeometry::Sag {
+            vertex: Position3D::zero(),
+            orientation: Quat::IDENTITY,
+            profile: SagProfile::conic(radius, 0.0, 0.3),
+        };
+        let sphere = SurfaceGeometry::Spherical {
+            radius: Distance::from_meters(2),
+            center: Position3D::from_vec3(Vec3::new(0.0, 0.0, 2.0)),
+        };
+        for &(x, y) in &[(0.0, 0.0), (0.1, 0.05), (-0.2, 0.15)] {
+            let origin = Position3D::from_vec3(Vec3::new(x, y, -1.0));
+            let direction = Vec3::new(0.02, -0.01, 1.0).normalize();
+            let (hit, point, t) = sag.ray_intersection(origin, direction);
+            let (_, sphere_point, sphere_t) = sphere.ray_intersection(origin, direction);
+            assert!(hit);
+            assert!((t - sphere_t).abs() < 1e-5);
+            let point = point.unwrap();
+            assert!((point.to_vec3() - sphere_point.unwrap().to_vec3()).length() < 1e-5);
+            // Sphere normals point away from the centre, sag normals toward +z
+            assert!((sag.normal_at(point) + sphere.normal_at(point)).length() < 1e-4);
+        }
+        // Outside the semi-diameter
+        let (hit, _, _) = sag.ray_intersection(Position3D::from_vec3(Vec3::new(0.35, 0.0, -1.0)), Vec3::Z);
+        assert!(!hit);
+    }
+
+    #[test]
+    fn test_freeform_intersection_lies_on_surface() {
+        // Tilted and decentred freeform
+        let vertex = Position3D::from_vec3(Vec3::new(0.5, -0.2, 1.0));
+        let orientation = Quat::from_rotation_y(0.3) * Quat::from_rotation_x(-0.1);
+        let profile = freeform_profile();
+        let geometry = SurfaceGeometry::Sag { vertex, orientation, profile: profile.clone() };
+        let bounds = geometry.bounds().unwrap();
+
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..200 {
+            let aim = Vec3::new(rng.gen_range(-0.08..0.08), rng.gen_range(-0.08..0.08), 0.0);
+            let start = Vec3::new(rng.gen_range(-0.05..0.05), rng.gen_range(-0.05..0.05), -0.6);
+            let origin = vertex.to_vec3() + orientation * start;
+            let direction = (orientation * (aim - start)).normalize();
+            let (hit, point, _) = geometry.ray_intersection(Position3D::from_vec3(origin), direction);
+            assert!(hit);
+            let point = point.unwrap().to_vec3();
+            assert!(point.cmpge(bounds.min).all() && point.cmple(bounds.max).all());
+
+            let local = (orientation.inverse() * (point - vertex.to_vec3())).as_dvec3();
+            assert!((local.z - profile.sag(local.x, local.y).unwrap()).abs() < 1e-6);
+            let normal = geometry.normal_at(Position3D::from_vec3(point));
+            let expected = orientation * profile.normal(local.x, local.y).as_vec3();
+            assert!((normal - expected).length() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_aperture_shapes() {
+        let annulus = Aperture::Annular { inner_radius: 0.1, outer_radius: 0.5 };
+        assert!(!annulus.contains(Vec2::new(0.05, 0.0)));
+        assert!(annulus.contains(Vec2::new(0.0, 0.3)));
+        assert!(!annulus.contains(Vec2::new(0.4, 0.4)));
+
+        let rectangle = Aperture::Rectangular { half_width: 0.2, half_height: 0.1 };
+        assert!(rectangle.contains(Vec2::new(-0.19, 0.09)));
+        assert!(!rectangle.contains(Vec2::new(0.0, 0.11)));
+        assert!((rectangle.area() - 0.08).abs() < 1e-6);
+
+        // L-shaped polygon: unit square minus its upper-right quarter
+        let polygon = Aperture::Polygon {
+            vertices: vec![
+                Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(1.0, 0.5),
+                Vec2::new(0.5, 0.5), Vec2::new(0.5, 1.0), Vec2::new(0.0, 1.0),
+            ],
+        };
+        assert!(polygon.contains(Vec2::new(0.25, 0.75)));
+        assert!(!polygon.contains(Vec2::new(0.75, 0.75)));
+        assert!((polygon.area() - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_parse_aperture() {
+        assert!(matches!("annulus:0.05,0.5".parse(), Ok(Aperture::Annular { .. })));
+        assert!(matches!("rect:0.2,0.1".parse(), Ok(Aperture::Rectangular { .. })));
+        let polygon: Aperture = "polygon:0,0;1,0;0,1".parse().unwrap();
+        assert!((polygon.area() - 0.5).abs() < 1e-6);
+        assert!("annulus:0.5,0.1".parse::<Aperture>().is_err());
+        assert!("polygon:0,0;1,0".parse::<Aperture>().is_err());
+
+        for spec in ["circle:0.25", "annulus:0.05,0.5", "rect:0.2,0.1", "polygon:0,0;1,0;0,1"] {
+            assert_eq!(spec.parse::<Aperture>().unwrap().to_string(), spec);
+        }
+    }
+
+    #[test]
+    fn test_aperture_in_local_frame() {
+        // Mirror facing +X: local x/y span world -z/y
+        let surface = MirrorSurface {
+            geometry: SurfaceGeometry::Planar { normal: Vec3::X },
+            orientation: Quat::from_rotation_arc(Vec3::Z, Vec3::X),
+            aperture: Aperture::Rectangular { half_width: 0.1, half_height: 0.3 },
+        };
+        let position = Position3D::new(Distance::from_meters(1), Distance::ZERO, Distance::ZERO);
+        let point = |y: f32, z: f32| Position3D::from_vec3(Vec3::new(1.0, y, z));
+        assert!(surface.in_aperture(position, point(0.25, 0.05)));
+        assert!(!surface.in_aperture(position, point(0.05, 0.25)));
+    }
+
+    #[test]
+    fn test_ray_sphere_hit_from_inside() {
+        let (hit, point, t) = SurfaceGeometry::ray_sphere_intersection(
+            Position3D::zero(), Vec3::Y, Position3D::zero(), Distance::from_meters(2)
+        );
+
+        assert!(hit);
+        assert!((t - 2.0).abs() < 1e-6);
+        assert!((point.unwrap().to_vec3().y - 2.0).abs() < 1e-6);
+    }
+
     #[test]
     fn test_ray_sphere_hit() {
         let origin = Position3D::new(
@@ -0,0 +1,842 @@
+//! Plasma emission subsystem: conversion efficiency, spectrum, angular
+//! distribution and in-band EUV yield
+//!
+//! Maps main-pulse laser parameters to the in-band (2% bandwidth) EUV energy
+//! radiated by the tin plasma, and derives photon packet counts, weights,
+//! wavelengths and directions from it
+
+use bevy_ecs::prelude::*;
+use glam::Vec3;
+use rand::Rng;
+use crate::units::{Distance, Position3D};
+
+/// Main-pulse parameters that drive the laser-produced plasma
+#[derive(Debug, Clone, Copy)]
+pub struct MainPulseParameters {
+    /// Pulse energy delivered to the focal spot (J)
+    pub energy: f32,
+    /// Pulse duration (seconds, FWHM)
+    pub duration: f32,
+    /// Laser focal spot diameter
+    pub spot_diameter: Distance,
+    /// Diameter of the (pancaked) tin target at the moment of the main pulse
+    pub target_diameter: Distance,
+}
+
+impl MainPulseParameters {
+    /// Peak intensity averaged over the focal spot (W/m²)
+    pub fn intensity(&self) -> f32 {
+        let r = self.spot_diameter.as_meters_f64() / 2.0;
+        let area = std::f64::consts::PI * r * r;
+        if area <= 0.0 || self.duration <= 0.0 {
+            return 0.0;
+        }
+        (self.energy as f64 / (self.duration as f64 * area)) as f32
+    }
+
+    /// Fraction of the focal spot covered by the target
+    pub fn target_coupling(&self) -> f32 {
+        let spot = self.spot_diameter.as_meters_f64();
+        if spot <= 0.0 {
+            return 0.0;
+        }
+        let ratio = (self.target_diameter.as_meters_f64() / spot).min(1.0);
+        (ratio * ratio) as f32
+    }
+}
+
+/// In-band EUV emission produced by a single main pulse
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InBandEmission {
+    /// Main-pulse energy (J)
+    pub pulse_energy: f32,
+    /// Conversion efficiency into 2% bandwidth, 2π sr
+    pub conversion_efficiency: f32,
+    /// In-band energy radiated into the 2π sr hemisphere facing the laser (J)
+    pub energy_2pi_sr: f32,
+}
+
+impl InBandEmission {
+    /// Scales the in-band yield (and hence the effective CE) by `factor`, clamped at zero
+    pub fn scale_yield(&mut self, factor: f32) {
+        let factor = factor.max(0.0);
+        self.energy_2pi_sr *= factor;
+        self.conversion_efficiency *= factor;
+    }
+}
+
+/// Maps main-pulse parameters to conversion efficiency (CE)
+///
+/// CE is quoted the way source vendors do: in-band (13.5 nm ± 1%) energy
+/// into 2π sr divided by the main-pulse energy.
+pub trait ConversionEfficiencyModel: std::fmt::Debug + Send + Sync {
+    fn conversion_efficiency(&self, pulse: &MainPulseParameters) -> f32;
+
+    fn in_band_emission(&self, pulse: &MainPulseParameters) -> InBandEmission {
+        let ce = self.conversion_efficiency(pulse).clamp(0.0, 1.0);
+        InBandEmission {
+            pulse_energy: pulse.energy,
+            conversion_efficiency: ce,
+            energy_2pi_sr: pulse.energy * ce,
+        }
+    }
+}
+
+/// Fixed CE regardless of pulse parameters
+#[derive(Debug, Clone, Copy)]
+pub struct ConstantConversionEfficiency(pub f32);
+
+impl ConversionEfficiencyModel for ConstantConversionEfficiency {
+    fn conversion_efficiency(&self, _pulse: &MainPulseParameters) -> f32 {
+        self.0
+    }
+}
+
+/// CE interpolated from a measured CE-versus-intensity curve, scaled by how
+/// much of the focal spot the target intercepts
+#[derive(Debug, Clone)]
+pub struct TabulatedConversionEfficiency {
+    /// (intensity W/m², CE) pairs sorted by intensity
+    pub intensity_curve: Vec<(f32, f32)>,
+}
+
+impl TabulatedConversionEfficiency {
+    /// CO2-laser driven tin plasma, CE peaking near 5% around 6e13 W/m²
+    pub fn co2_tin() -> Self {
+        Self {
+            intensity_curve: vec![
+                (1e12, 0.002),
+                (5e12, 0.012),
+                (1e13, 0.025),
+                (3e13, 0.042),
+                (6e13, 0.050),
+                (1e14, 0.047),
+                (3e14, 0.036),
+                (1e15, 0.022),
+            ],
+        }
+    }
+
+    /// CE at a given intensity, linearly interpolated in log-intensity
+    pub fn ce_at_intensity(&self, intensity: f32) -> f32 {
+        let curve = &self.intensity_curve;
+        let (first, last) = match (curve.first(), curve.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return 0.0,
+        };
+        if intensity <= first.0 {
+            return first.1;
+        }
+        if intensity >= last.0 {
+            return last.1;
+        }
+
+        let x = intensity.log10();
+        for window in curve.windows(2) {
+            let (i0, ce0) = window[0];
+            let (i1, ce1) = window[1];
+            if intensity <= i1 {
+                let t = (x - i0.log10()) / (i1.log10() - i0.log10());
+                return ce0 + t * (ce1 - ce0);
+            }
+        }
+        last.1
+    }
+}
+
+impl ConversionEfficiencyModel for TabulatedConversionEfficiency {
+    fn conversion_efficiency(&self, pulse: &MainPulseParameters) -> f32 {
+        self.ce_at_intensity(pulse.intensity()) * pulse.target_coupling()
+    }
+}
+
+/// Coarse spectral regions used to attribute optical losses and heating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpectralBand {
+    /// 13.5 nm ± 1% (2% bandwidth) usable by the projection optics
+    InBand,
+    /// EUV outside the 2% window (< 100 nm)
+    OutOfBandEuv,
+    /// Deep ultraviolet (100-400 nm)
+    Duv,
+    /// Visible and near infrared (400 nm - 1 μm)
+    VisibleNir,
+    /// Infrared, dominated by scattered CO2 drive-laser light (> 1 μm)
+    Infrared,
+}
+
+impl SpectralBand {
+    pub const IN_BAND_MIN: f32 = 13.365e-9;
+    pub const IN_BAND_MAX: f32 = 13.635e-9;
+
+    pub fn classify(wavelength: f32) -> Self {
+        if (Self::IN_BAND_MIN..=Self::IN_BAND_MAX).contains(&wavelength) {
+            SpectralBand::InBand
+        } else if wavelength < 100e-9 {
+            SpectralBand::OutOfBandEuv
+        } else if wavelength < 400e-9 {
+            SpectralBand::Duv
+        } else if wavelength < 1e-6 {
+            SpectralBand::VisibleNir
+        } else {
+            SpectralBand::Infrared
+        }
+    }
+
+    pub fn is_in_band(&self) -> bool {
+        *self == SpectralBand::InBand
+    }
+}
+
+/// Wavelength bin of the emission spectrum with a flat spectral density inside it
+#[derive(Debug, Clone, Copy)]
+pub struct SpectralBin {
+    /// Lower wavelength edge (m)
+    pub min_wavelength: f32,
+    /// Upper wavelength edge (m)
+    pub max_wavelength: f32,
+    /// Fraction of total radiated energy in this bin
+    pub energy_fraction: f32,
+}
+
+impl SpectralBin {
+    pub const fn new(min_wavelength: f32, max_wavelength: f32, energy_fraction: f32) -> Self {
+        Self { min_wavelength, max_wavelength, energy_fraction }
+    }
+
+    /// Energy fraction of this bin falling inside the 2% in-band window
+    fn in_band_energy(&self) -> f32 {
+        let lo = self.min_wavelength.max(SpectralBand::IN_BAND_MIN);
+        let hi = self.max_wavelength.min(SpectralBand::IN_BAND_MAX);
+        let width = self.max_wavelength - self.min_wavelength;
+        if hi <= lo || width <= 0.0 {
+            return 0.0;
+        }
+        self.energy_fraction * (hi - lo) / width
+    }
+
+    fn is_in_band(&self) -> bool {
+        self.min_wavelength >= SpectralBand::IN_BAND_MIN && self.max_wavelength <= SpectralBand::IN_BAND_MAX
+    }
+}
+
+/// A sampled packet wavelength and its energy weight
+#[derive(Debug, Clone, Copy)]
+pub struct SpectralSample {
+    /// Wavelength (m)
+    pub wavelength: f32,
+    /// Packet energy relative to an unbiased packet (total energy / packet count)
+    pub energy_weight: f32,
+}
+
+/// Plasma emission spectrum used to sample packet wavelengths
+///
+/// Bins are sampled with a bias toward the in-band window so the useful light
+/// is well resolved; packet energies are re-weighted so the sampled energy per
+/// bin stays unbiased.
+#[derive(Debug, Clone)]
+pub struct EmissionSpectrum {
+    bins: Vec<SpectralBin>,
+    /// Fraction of packets drawn from in-band bins (0 samples purely by energy)
+    pub in_band_sampling_fraction: f32,
+}
+
+impl EmissionSpectrum {
+    /// Builds a spectrum from its bins, which must carry some positive energy
+    pub fn new(bins: Vec<SpectralBin>, in_band_sampling_fraction: f32) -> Result<Self, String> {
+        if bins.is_empty() {
+            return Err("emission spectrum has no bins".to_string());
+        }
+        if let Some(bin) = bins.iter().find(|b| !b.energy_fraction.is_finite() || b.energy_fraction < 0.0 || b.max_wavelength < b.min_wavelength) {
+            return Err(format!("invalid spectral bin {bin:?}"));
+        }
+        let total: f32 = bins.iter().map(|b| b.energy_fraction).sum();
+        if !(total > 0.0 && total.is_finite()) {
+            return Err(format!("emission spectrum total energy fraction must be positive, got {total}"));
+        }
+        Ok(Self { bins, in_band_sampling_fraction })
+    }
+
+    pub fn bins(&self) -> &[SpectralBin] {
+        &self.bins
+    }
+
+    /// Tin LPP spectrum: broad 4d-4f unresolved transition array around 13.5 nm,
+    /// DUV/visible continuum and scattered 10.6 μm CO2 drive-laser light
+    pub fn tin_lpp() -> Self {
+        Self {
+            bins: vec![
+                SpectralBin::new(5.0e-9, SpectralBand::IN_BAND_MIN, 0.30),
+                SpectralBin::new(SpectralBand::IN_BAND_MIN, SpectralBand::IN_BAND_MAX, 0.06),
+                SpectralBin::new(SpectralBand::IN_BAND_MAX, 40e-9, 0.24),
+                SpectralBin::new(130e-9, 400e-9, 0.10),
+                SpectralBin::new(400e-9, 1000e-9, 0.10),
+                SpectralBin::new(10.55e-6, 10.65e-6, 0.20),
+            ],
+            in_band_sampling_fraction: 0.5,
+        }
+    }
+
+    fn total_energy_fraction(&self) -> f32 {
+        self.bins.iter().map(|b| b.energy_fraction).sum()
+    }
+
+    /// Fraction of the total radiated energy that lies in the 2% in-band window
+    pub fn in_band_fraction(&self) -> f32 {
+        let total = self.total_energy_fraction();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        self.bins.iter().map(|b| b.in_band_energy()).sum::<f32>() / total
+    }
+
+    /// Probability of drawing each bin
+    fn sampling_probabilities(&self) -> Vec<f32> {
+        let total = self.total_energy_fraction();
+        let in_band: f32 = self.bins.iter().filter(|b| b.is_in_band()).map(|b| b.energy_fraction).sum();
+        let out_of_band = total - in_band;
+        let bias = self.in_band_sampling_fraction.clamp(0.0, 1.0);
+
+        self.bins
+            .iter()
+            .map(|b| {
+                if bias <= 0.0 || in_band <= 0.0 || out_of_band <= 0.0 {
+                    b.energy_fraction / total
+                } else if b.is_in_band() {
+                    bias * b.energy_fraction / in_band
+                } else {
+                    (1.0 - bias) * b.energy_fraction / out_of_band
+                }
+            })
+            .collect()
+    }
+
+    /// Draws a packet wavelength and its energy weight
+    pub fn sample(&self, rng: &mut impl Rng) -> SpectralSample {
+        let total = self.total_energy_fraction();
+        let probabilities = self.sampling_probabilities();
+        let mut u = rng.gen::<f32>();
+
+        // Rounding can leave u past the last bin; fall back to the last one that can be drawn
+        let mut chosen = probabilities.iter().rposition(|p| *p > 0.0).expect("spectrum has positive energy");
+        for (i, p) in probabilities.iter().enumerate() {
+            if u < *p {
+                chosen = i;
+                break;
+            }
+            u -= p;
+        }
+
+        let bin = &self.bins[chosen];
+        let wavelength = rng.gen_range(bin.min_wavelength..=bin.max_wavelength);
+        SpectralSample {
+            wavelength,
+            energy_weight: bin.energy_fraction / total / probabilities[chosen],
+        }
+    }
+}
+
+impl Default for EmissionSpectrum {
+    fn default() -> Self {
+        Self::tin_lpp()
+    }
+}
+
+/// Angular distribution of plasma emission
+///
+/// Directions are expressed relative to the emission axis, which points from
+/// the plasma back toward the incoming main pulse (the hot, laser-facing side).
+pub trait EmissionPattern: std::fmt::Debug + Send + Sync {
+    /// Radiant intensity at polar angle θ from the axis, relative to an
+    /// isotropic emitter of the same total power
+    fn relative_intensity(&self, cos_theta: f32) -> f32;
+
+    /// Draws a unit emission direction
+    fn sample_direction(&self, axis: Vec3, rng: &mut dyn rand::RngCore) -> Vec3;
+
+    /// Fraction of the total emitted energy going into the 2π sr hemisphere around the axis
+    fn laser_hemisphere_fraction(&self) -> f32;
+}
+
+/// Uniformly distributed direction on the unit sphere
+pub fn sample_uniform_sphere(rng: &mut dyn rand::RngCore) -> Vec3 {
+    // Uniform in cos θ (not θ) gives equal probability per solid angle
+    let cos_theta = rng.gen_range(-1.0f32..=1.0);
+    let phi = rng.gen_range(0.0..std::f32::consts::TAU);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+/// Rotates a direction given in the axis frame (axis = +Z) into world space
+fn from_axis_frame(axis: Vec3, local: Vec3) -> Vec3 {
+    let axis = axis.normalize();
+    let (u, v) = axis.any_orthonormal_pair();
+    u * local.x + v * local.y + axis * local.z
+}
+
+/// Equal intensity in every direction
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IsotropicEmission;
+
+impl EmissionPattern for IsotropicEmission {
+    fn relative_intensity(&self, _cos_theta: f32) -> f32 {
+        1.0
+    }
+
+    fn sample_direction(&self, _axis: Vec3, rng: &mut dyn rand::RngCore) -> Vec3 {
+        sample_uniform_sphere(rng)
+    }
+
+    fn laser_hemisphere_fraction(&self) -> f32 {
+        0.5
+    }
+}
+
+/// Axially symmetric emission I(θ) ∝ 1 + a1·cos θ + a2·cos² θ about the laser axis
+///
+/// Positive `a1` favours the laser-facing side, as measured for CO2-driven tin
+/// plasmas; `a2` flattens (negative) or sharpens (positive) the lobe.
+#[derive(Debug, Clone, Copy)]
+pub struct AnisotropicEmission {
+    pub a1: f32,
+    pub a2: f32,
+}
+
+impl AnisotropicEmission {
+    /// Returns `None` if the intensity would go negative somewhere on the sphere
+    pub fn new(a1: f32, a2: f32) -> Option<Self> {
+        let pattern = Self { a1, a2 };
+        (pattern.min_unnormalized() >= 0.0).then_some(pattern)
+    }
+
+    /// Tin LPP driven by a CO2 main pulse, ~30% brighter toward the laser than sideways
+    pub fn co2_tin() -> Self {
+        Self { a1: 0.3, a2: -0.1 }
+    }
+
+    fn unnormalized(&self, mu: f32) -> f32 {
+        1.0 + self.a1 * mu + self.a2 * mu * mu
+    }
+
+    /// Candidate extrema of the quadratic on [-1, 1]
+    fn extrema_candidates(&self) -> [f32; 3] {
+        let vertex = if self.a2 != 0.0 {
+            (-self.a1 / (2.0 * self.a2)).clamp(-1.0, 1.0)
+        } else {
+            1.0
+        };
+        [self.unnormalized(-1.0), self.unnormalized(1.0), self.unnormalized(vertex)]
+    }
+
+    fn min_unnormalized(&self) -> f32 {
+        self.extrema_candidates().into_iter().fold(f32::INFINITY, f32::min)
+    }
+
+    fn max_unnormalized(&self) -> f32 {
+        self.extrema_candidates().into_iter().fold(0.0, f32::max)
+    }
+
+    /// Mean of the unnormalized intensity over the sphere
+    fn sphere_mean(&self) -> f32 {
+        1.0 + self.a2 / 3.0
+    }
+}
+
+impl EmissionPattern for AnisotropicEmission {
+    fn relative_intensity(&self, cos_theta: f32) -> f32 {
+        self.unnormalized(cos_theta) / self.sphere_mean()
+    }
+
+    fn sample_direction(&self, axis: Vec3, rng: &mut dyn rand::RngCore) -> Vec3 {
+        // Rejection sampling against the uniform sphere
+        let max = self.max_unnormalized();
+        loop {
+            let local = sample_uniform_sphere(rng);
+            if rng.gen::<f32>() * max <= self.unnormalized(local.z) {
+                return from_axis_frame(axis, local);
+            }
+        }
+    }
+
+    fn laser_hemisphere_fraction(&self) -> f32 {
+        // ∫0..1 (1 + a1 μ + a2 μ²) dμ over ∫-1..1 of the same
+        (1.0 + self.a1 / 2.0 + self.a2 / 3.0) / (2.0 * self.sphere_mean())
+    }
+}
+
+/// Plasma emission configuration
+#[derive(Resource, Debug)]
+pub struct PlasmaEmissionConfig {
+    /// Conversion efficiency model
+    pub conversion: Box<dyn ConversionEfficiencyModel>,
+    /// Spectral distribution of the radiated energy
+    pub spectrum: EmissionSpectrum,
+    /// Angular distribution of the radiated energy
+    pub pattern: Box<dyn EmissionPattern>,
+    /// Desired energy carried by each photon packet (J)
+    pub target_packet_energy: f32,
+    /// Lower bound on packets spawned per pulse
+    pub min_packets: u32,
+    /// Upper bound on packets spawned per pulse
+    pub max_packets: u32,
+    /// Relative pulse-to-pulse fluctuation of the in-band yield (1σ)
+    pub pulse_to_pulse_jitter: f32,
+}
+
+impl Default for PlasmaEmissionConfig {
+    fn default() -> Self {
+        Self {
+            conversion: Box::new(TabulatedConversionEfficiency::co2_tin()),
+            spectrum: EmissionSpectrum::default(),
+            pattern: Box::new(AnisotropicEmission::co2_tin()),
+            target_packet_energy: 2e-5, // ~1000 packets for a 0.4 J main pulse
+            min_packets: 100,
+            max_packets: 5000,
+            pulse_to_pulse_jitter: 0.05, // ~5% rms shot-to-shot yield variation
+        }
+    }
+}
+
+impl PlasmaEmissionConfig {
+    pub fn in_band_emission(&self, pulse: &MainPulseParameters) -> InBandEmission {
+        self.conversion.in_band_emission(pulse)
+    }
+
+    /// In-band emission for a single shot, including pulse-to-pulse yield jitter
+    pub fn sample_in_band_emission(&self, pulse: &MainPulseParameters, rng: &mut impl Rng) -> InBandEmission {
+        let mut emission = self.in_band_emission(pulse);
+        if self.pulse_to_pulse_jitter > 0.0 {
+            emission.scale_yield(1.0 + self.pulse_to_pulse_jitter * rng.sample::<f32, _>(rand_distr::StandardNormal));
+        }
+        emission
+    }
+
+    /// Number of packets spawned for a pulse with the given in-band energy
+    pub fn packet_count(&self, in_band_energy: f32) -> u32 {
+        if self.target_packet_energy <= 0.0 {
+            return self.max_packets;
+        }
+        let count = (in_band_energy / self.target_packet_energy).round() as u32;
+        count.clamp(self.min_packets, self.max_packets)
+    }
+
+    /// In-band energy radiated into the full sphere (J)
+    pub fn in_band_energy_4pi(&self, emission: &InBandEmission) -> f32 {
+        let hemisphere_fraction = self.pattern.laser_hemisphere_fraction();
+        if hemisphere_fraction > 0.0 {
+            emission.energy_2pi_sr / hemisphere_fraction
+        } else {
+            0.0
+        }
+    }
+
+    /// Total energy radiated across the whole spectrum into 4π sr (J)
+    pub fn radiated_energy(&self, emission: &InBandEmission) -> f32 {
+        let in_band_fraction = self.spectrum.in_band_fraction();
+        if in_band_fraction > 0.0 {
+            self.in_band_energy_4pi(emission) / in_band_fraction
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Plume axis of a plasma, pointing back toward the laser that created it
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PlasmaAxis(pub Vec3);
+
+/// Fired for every main pulse that produces plasma
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlasmaPulseEvent {
+    pub emission: InBandEmission,
+    /// Index of the pulse within its burst (0 = first)
+    pub burst_pulse: u32,
+    /// Laser channel that fired the main pulse
+    pub channel: Entity,
+    /// Where the plasma formed
+    pub position: Position3D,
+    /// Emission-pattern axis, pointing back toward the incoming laser
+    pub axis: Vec3,
+}
+
+/// System that swaps the plasma pulse event buffers once per tick
+pub fn plasma_event_update_system(mut events: ResMut<Events<PlasmaPulseEvent>>) {
+    events.update();
+}
+
+/// Running totals of plasma emission
+#[derive(Resource, Default, Debug)]
+pub struct EmissionStatistics {
+    /// Main pulses that produced plasma
+    pub pulse_count: u64,
+    /// Total main-pulse energy delivered (J)
+    pub total_pulse_energy: f64,
+    /// Total in-band energy into 2π sr (J)
+    pub total_in_band_energy: f64,
+    /// Total energy radiated across the spectrum into 4π sr (J)
+    pub total_radiated_energy: f64,
+    /// Most recent pulse emission
+    pub last_emission: Option<InBandEmission>,
+}
+
+impl EmissionStatistics {
+    pub fn record(&mut self, emission: &InBandEmission, radiated_energy: f32) {
+        self.pulse_count += 1;
+        self.total_pulse_energy += emission.pulse_energy as f64;
+        self.total_in_band_energy += emission.energy_2pi_sr as f64;
+        self.total_radiated_energy += radiated_energy as f64;
+        self.last_emission = Some(*emission);
+    }
+
+    /// Mean conversion efficiency over all pulses
+    pub fn average_conversion_efficiency(&self) -> f64 {
+        if self.total_pulse_energy > 0.0 {
+            self.total_in_band_energy / self.total_pulse_energy
+        } else {
+            0.0
+        }
+    }
+
+    /// Mean in-band energy per pulse (J, 2π sr)
+    pub fn average_in_band_energy(&self) -> f64 {
+        if self.pulse_count > 0 {
+            self.total_in_band_energy / self.pulse_count as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// System that accumulates emission statistics from plasma pulses
+pub fn emission_statistics_system(
+    mut pulses: EventReader<PlasmaPulseEvent>,
+    config: Res<PlasmaEmissionConfig>,
+    mut stats: ResMut<EmissionStatistics>,
+) {
+    for pulse in pulses.read() {
+        stats.record(&pulse.emission, config.radiated_energy(&pulse.emission));
+    }
+}
+
+/// In-band source power (W, 2π sr) versus main-pulse energy at a fixed repetition rate
+///
+/// Every other pulse parameter is taken from `base`.
+pub fn source_power_curve(
+    model: &dyn ConversionEfficiencyModel,
+    base: &MainPulseParameters,
+    pulse_energies: &[f32],
+    repetition_rate: f32,
+) -> Vec<(f32, f32)> {
+    pulse_energies
+        .iter()
+        .map(|&energy| {
+            let pulse = MainPulseParameters { energy, ..*base };
+            let emission = model.in_band_emission(&pulse);
+            (energy, emission.energy_2pi_sr * repetition_rate)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nominal_pulse() -> MainPulseParameters {
+        MainPulseParameters {
+            energy: 0.4,
+            duration: 50e-9,
+            spot_diameter: Distance::from_micrometers(150),
+            target_diameter: Distance::from_micrometers(150),
+        }
+    }
+
+    #[test]
+    fn test_tabulated_ce_interpolation() {
+        let model = TabulatedConversionEfficiency::co2_tin();
+        assert_eq!(model.ce_at_intensity(6e13), 0.050);
+        assert_eq!(model.ce_at_intensity(1e10), 0.002); // Clamped below table
+        let between = model.ce_at_intensity(4e13);
+        assert!(between > 0.042 && between < 0.050);
+    }
+
+    #[test]
+    fn test_small_target_reduces_ce() {
+        let model = TabulatedConversionEfficiency::co2_tin();
+        let full = nominal_pulse();
+        let half = MainPulseParameters {
+            target_diameter: Distance::from_micrometers(75),
+            ..full
+        };
+        let ratio = model.conversion_efficiency(&half) / model.conversion_efficiency(&full);
+        assert!((ratio - 0.25).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_packet_count_from_emission() {
+        let config = PlasmaEmissionConfig {
+            conversion: Box::new(ConstantConversionEfficiency(0.02)),
+            pattern: Box::new(IsotropicEmission),
+            ..Default::default()
+        };
+        let emission = config.in_band_emission(&nominal_pulse());
+        assert!((emission.energy_2pi_sr - 0.008).abs() < 1e-6);
+        assert_eq!(config.packet_count(config.in_band_energy_4pi(&emission)), 800);
+        assert_eq!(config.packet_count(1.0), config.max_packets);
+    }
+
+    #[test]
+    fn test_spectrum_in_band_fraction() {
+        let spectrum = EmissionSpectrum::tin_lpp();
+        assert!((spectrum.in_band_fraction() - 0.06).abs() < 1e-6);
+        let narrow = EmissionSpectrum::new(vec![SpectralBin::new(13.0e-9, 14.0e-9, 1.0)], 0.0).unwrap();
+        assert!((narrow.in_band_fraction() - 0.27).abs() < 1e-3); // Partial bin overlap
+        assert_eq!(SpectralBand::classify(13.5e-9), SpectralBand::InBand);
+        assert_eq!(SpectralBand::classify(10.6e-6), SpectralBand::Infrared);
+    }
+
+    #[test]
+    fn test_spectrum_validation_and_sampling_fallback() {
+        use rand::SeedableRng;
+        assert!(EmissionSpectrum::new(Vec::new(), 0.5).is_err());
+        assert!(EmissionSpectrum::new(vec![SpectralBin::new(13.0e-9, 14.0e-9, 0.0)], 0.5).is_err());
+        assert!(EmissionSpectrum::new(vec![SpectralBin::new(13.0e-9, 14.0e-9, -1.0)], 0.5).is_err());
+
+        // A trailing empty bin is never drawn, even when rounding leaves u past the rest
+        let spectrum = EmissionSpectrum::new(
+            vec![SpectralBin::new(13.3e-9, 13.6e-9, 0.4), SpectralBin::new(20e-9, 30e-9, 0.6), SpectralBin::new(1e-6, 2e-6, 0.0)],
+            0.5,
+        )
+        .unwrap();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        for _ in 0..10_000 {
+            let sample = spectrum.sample(&mut rng);
+            assert!(sample.energy_weight.is_finite() && sample.energy_weight > 0.0);
+            assert!(sample.wavelength < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_spectrum_sampling_is_energy_unbiased() {
+        use rand::SeedableRng;
+        let spectrum = EmissionSpectrum::tin_lpp();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let samples = 200_000;
+
+        let mut in_band_packets = 0;
+        let mut in_band_energy = 0.0f64;
+        let mut total_energy = 0.0f64;
+        for _ in 0..samples {
+            let sample = spectrum.sample(&mut rng);
+            total_energy += sample.energy_weight as f64;
+            if SpectralBand::classify(sample.wavelength).is_in_band() {
+                in_band_packets += 1;
+                in_band_energy += sample.energy_weight as f64;
+            }
+        }
+
+        let packet_fraction = in_band_packets as f64 / samples as f64;
+        assert!((packet_fraction - 0.5).abs() < 0.01); // Biased toward in-band
+        assert!((total_energy / samples as f64 - 1.0).abs() < 0.02);
+        assert!((in_band_energy / total_energy - 0.06).abs() < 0.002);
+    }
+
+    /// Pearson χ² of observed counts against expected bin probabilities
+    fn chi_square(counts: &[u32], expected: &[f64]) -> f64 {
+        let total: u32 = counts.iter().sum();
+        counts
+            .iter()
+            .zip(expected)
+            .map(|(&o, &p)| {
+                let e = p * total as f64;
+                (o as f64 - e).powi(2) / e
+            })
+            .sum()
+    }
+
+    /// Histograms cos θ about `axis` and azimuth about +Z into `bins` bins each
+    fn angular_histograms(pattern: &dyn EmissionPattern, axis: Vec3, bins: usize, samples: usize) -> (Vec<u32>, Vec<u32>) {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let mut cos_counts = vec![0u32; bins];
+        let mut phi_counts = vec![0u32; bins];
+        for _ in 0..samples {
+            let d = pattern.sample_direction(axis, &mut rng);
+            assert!((d.length() - 1.0).abs() < 1e-4);
+            let mu = d.dot(axis).clamp(-1.0, 1.0);
+            let cos_bin = (((mu + 1.0) / 2.0) * bins as f32).min(bins as f32 - 1.0) as usize;
+            cos_counts[cos_bin] += 1;
+            let phi = d.y.atan2(d.x) + std::f32::consts::PI;
+            let phi_bin = ((phi / std::f32::consts::TAU) * bins as f32).min(bins as f32 - 1.0) as usize;
+            phi_counts[phi_bin] += 1;
+        }
+        (cos_counts, phi_counts)
+    }
+
+    // χ² critical value for 19 degrees of freedom at p = 0.001
+    const CHI2_CRITICAL_19: f64 = 43.82;
+
+    #[test]
+    fn test_isotropic_emission_is_uniform_over_sphere() {
+        let bins = 20;
+        let uniform = vec![1.0 / bins as f64; bins];
+        let (cos_counts, phi_counts) = angular_histograms(&IsotropicEmission, Vec3::Z, bins, 100_000);
+        assert!(chi_square(&cos_counts, &uniform) < CHI2_CRITICAL_19);
+        assert!(chi_square(&phi_counts, &uniform) < CHI2_CRITICAL_19);
+
+        // The old phi ∈ [0, π) parameterisation piles samples up at the poles
+        let polar_bias: Vec<u32> = {
+            use rand::SeedableRng;
+            let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+            let mut counts = vec![0u32; bins];
+            for _ in 0..100_000 {
+                let phi: f32 = rng.gen_range(0.0..std::f32::consts::PI);
+                let bin = (((phi.cos() + 1.0) / 2.0) * bins as f32).min(bins as f32 - 1.0) as usize;
+                counts[bin] += 1;
+            }
+            counts
+        };
+        assert!(chi_square(&polar_bias, &uniform) > 1000.0);
+    }
+
+    #[test]
+    fn test_anisotropic_emission_matches_distribution() {
+        let pattern = AnisotropicEmission::co2_tin();
+        let axis = Vec3::new(0.0, -1.0, 1.0).normalize();
+        let bins = 20;
+
+        // Expected probability per cos θ bin: ∫ I(μ) dμ over the bin, normalized
+        let integral = |mu: f32| mu + pattern.a1 * mu * mu / 2.0 + pattern.a2 * mu * mu * mu / 3.0;
+        let norm = integral(1.0) - integral(-1.0);
+        let expected: Vec<f64> = (0..bins)
+            .map(|i| {
+                let lo = -1.0 + 2.0 * i as f32 / bins as f32;
+                let hi = lo + 2.0 / bins as f32;
+                ((integral(hi) - integral(lo)) / norm) as f64
+            })
+            .collect();
+
+        let (cos_counts, _) = angular_histograms(&pattern, axis, bins, 100_000);
+        assert!(chi_square(&cos_counts, &expected) < CHI2_CRITICAL_19);
+
+        let forward: u32 = cos_counts[bins / 2..].iter().sum();
+        let measured = forward as f32 / 100_000.0;
+        assert!((measured - pattern.laser_hemisphere_fraction()).abs() < 0.01);
+        assert!(pattern.laser_hemisphere_fraction() > 0.5);
+    }
+
+    #[test]
+    fn test_anisotropic_emission_rejects_negative_intensity() {
+        assert!(AnisotropicEmission::new(2.0, 0.0).is_none());
+        assert!(AnisotropicEmission::new(0.5, 0.2).is_some());
+        let pattern = AnisotropicEmission::new(0.5, 0.2).unwrap();
+        assert!((pattern.relative_intensity(0.0) - 1.0 / (1.0 + 0.2 / 3.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_source_power_curve() {
+        let model = ConstantConversionEfficiency(0.05);
+        let curve = source_power_curve(&model, &nominal_pulse(), &[0.2, 0.4], 50_000.0);
+        assert_eq!(curve.len(), 2);
+        assert!((curve[1].1 - 1000.0).abs() < 1e-2); // 0.4 J * 5% * 50 kHz = 1 kW
+    }
+}
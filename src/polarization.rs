@@ -0,0 +1,206 @@
+//! Polarization state of traced light
+//!
+//! Each packet carries a fully polarized state as a complex field vector in
+//! world coordinates, transverse to its direction, so no per-packet frame has
+//! to be tracked through reflections. Unpolarized light is an ensemble of
+//! packets with random states; detectors sum Stokes parameters over packets.
+
+use glam::Vec3;
+use nalgebra::Complex;
+use rand::Rng;
+
+/// Unit complex field vector (Jones vector expressed in the world frame)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JonesVector {
+    pub re: Vec3,
+    pub im: Vec3,
+}
+
+impl JonesVector {
+    /// Linear polarization along `axis`, made transverse to `direction`
+    pub fn linear(direction: Vec3, axis: Vec3) -> Self {
+        let transverse = axis - axis.dot(direction) * direction;
+        let axis = transverse.try_normalize().unwrap_or_else(|| direction.any_orthonormal_vector());
+        Self { re: axis, im: Vec3::ZERO }
+    }
+
+    /// Random fully polarized state, uniform over the Poincaré sphere; an
+    /// ensemble of these is unpolarized
+    pub fn random(direction: Vec3, rng: &mut impl Rng) -> Self {
+        let (x, y) = transverse_frame(direction);
+        let azimuth = rng.gen_range(0.0..std::f32::consts::PI);
+        // sin 2χ uniform in [-1, 1] gives S3 uniform, i.e. uniform on the sphere
+        let ellipticity = rng.gen_range(-1.0f32..1.0).asin() / 2.0;
+        let (sin_a, cos_a) = azimuth.sin_cos();
+        let (sin_e, cos_e) = ellipticity.sin_cos();
+        Self::from_components(
+            Complex::new(cos_a * cos_e, -sin_a * sin_e),
+            x,
+            Complex::new(sin_a * cos_e, cos_a * sin_e),
+            y,
+        )
+    }
+
+    /// Field `a` along `axis_a` plus `b` along `axis_b`
+    pub fn from_components(a: Complex<f32>, axis_a: Vec3, b: Complex<f32>, axis_b: Vec3) -> Self {
+        Self {
+            re: a.re * axis_a + b.re * axis_b,
+            im: a.im * axis_a + b.im * axis_b,
+        }
+    }
+
+    /// Same state with any component along `direction` removed, renormalized
+    pub fn transverse_to(&self, direction: Vec3) -> Self {
+        let field = Self {
+            re: self.re - self.re.dot(direction) * direction,
+            im: self.im - self.im.dot(direction) * direction,
+        };
+        let scale = field.intensity().sqrt().recip();
+        if scale.is_finite() {
+            Self { re: field.re * scale, im: field.im * scale }
+        } else {
+            Self::linear(direction, self.re)
+        }
+    }
+
+    /// Complex amplitude along a real unit axis
+    pub fn component(&self, axis: Vec3) -> Complex<f32> {
+        Complex::new(self.re.dot(axis), self.im.dot(axis))
+    }
+
+    /// Squared field magnitude, relative to the packet's photon count
+    pub fn intensity(&self) -> f32 {
+        self.re.length_squared() + self.im.length_squared()
+    }
+
+    /// Reflects the field with amplitude coefficients `rs` and `rp`
+    ///
+    /// Returns the renormalized reflected state and the reflectance for this
+    /// state. `rp` follows the tangential-field convention of the coating
+    /// model (r_p = r_s at normal incidence).
+    pub fn reflect(&self, incoming: Vec3, reflected: Vec3, normal: Vec3, rs: Complex<f32>, rp: Complex<f32>) -> (Self, f32) {
+        let s = incoming.cross(normal).try_normalize().unwrap_or_else(|| incoming.any_orthonormal_vector());
+        let p_in = s.cross(incoming);
+        let p_out = reflected.cross(s);
+        let field = Self::from_components(rs * self.component(s), s, rp * self.component(p_in), p_out);
+        let reflectance = field.intensity() / self.intensity();
+        let scale = reflectance.sqrt().recip();
+        if scale.is_finite() {
+            (Self { re: field.re * scale, im: field.im * scale }, reflectance)
+        } else {
+            (*self, reflectance)
+        }
+    }
+}
+
+/// Transverse axes (x, y) for a ray, with x as close to the world Y axis as
+/// the direction allows; the lab frame in which detectors report Stokes parameters
+pub fn transverse_frame(direction: Vec3) -> (Vec3, Vec3) {
+    let reference = if direction.y.abs() < 0.99 { Vec3::Y } else { Vec3::Z };
+    let x = (reference - reference.dot(direction) * direction).normalize();
+    (x, direction.cross(x))
+}
+
+/// Energy-weighted Stokes parameters summed over packets
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StokesVector {
+    pub s0: f64,
+    pub s1: f64,
+    pub s2: f64,
+    pub s3: f64,
+}
+
+impl StokesVector {
+    /// Stokes parameters of one packet's state in the ray's [`transverse_frame`], scaled by `weight`
+    pub fn from_field(field: &JonesVector, direction: Vec3, weight: f64) -> Self {
+        let (x, y) = transverse_frame(direction);
+        let ex = field.component(x);
+        let ey = field.component(y);
+        let cross = ex * ey.conj();
+        let scale = weight / field.intensity() as f64;
+        Self {
+            s0: (ex.norm_sqr() + ey.norm_sqr()) as f64 * scale,
+            s1: (ex.norm_sqr() - ey.norm_sqr()) as f64 * scale,
+            s2: 2.0 * cross.re as f64 * scale,
+            s3: -2.0 * cross.im as f64 * scale,
+        }
+    }
+
+    pub fn add(&mut self, other: &StokesVector) {
+        self.s0 += other.s0;
+        self.s1 += other.s1;
+        self.s2 += other.s2;
+        self.s3 += other.s3;
+    }
+
+    /// Polarized fraction of the summed light, 0 when nothing was recorded
+    pub fn degree_of_polarization(&self) -> f64 {
+        if self.s0 > 0.0 {
+            (self.s1 * self.s1 + self.s2 * self.s2 + self.s3 * self.s3).sqrt() / self.s0
+        } else {
+            0.0
+        }
+    }
+
+    /// Linearly polarized fraction of the summed light
+    pub fn degree_of_linear_polarization(&self) -> f64 {
+        if self.s0 > 0.0 {
+            (self.s1 * self.s1 + self.s2 * self.s2).sqrt() / self.s0
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_random_states_average_to_unpolarized() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let direction = Vec3::new(1.0, 2.0, -0.5).normalize();
+        let mut sum = StokesVector::default();
+        for _ in 0..20_000 {
+            let field = JonesVector::random(direction, &mut rng);
+            assert!((field.intensity() - 1.0).abs() < 1e-5);
+            assert!(field.re.dot(direction).abs() < 1e-5 && field.im.dot(direction).abs() < 1e-5);
+            let stokes = StokesVector::from_field(&field, direction, 1.0);
+            // Every single packet is fully polarized
+            assert!((stokes.degree_of_polarization() - 1.0).abs() < 1e-4);
+            sum.add(&stokes);
+        }
+        assert!(sum.degree_of_polarization() < 0.02);
+    }
+
+    #[test]
+    fn test_normal_incidence_keeps_tangential_field() {
+        let field = JonesVector::linear(-Vec3::Z, Vec3::new(1.0, 1.0, 0.0));
+        let r = Complex::new(0.0, 0.8);
+        let (reflected, reflectance) = field.reflect(-Vec3::Z, Vec3::Z, Vec3::Z, r, r);
+        assert!((reflectance - 0.64).abs() < 1e-5);
+        // Same transverse field, up to the common phase of r
+        assert!(reflected.re.length() < 1e-5);
+        assert!((reflected.im - field.re).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_s_only_reflection_polarizes() {
+        // 45° incidence in the xz-plane; s is along y
+        let incoming = Vec3::new(1.0, 0.0, -1.0).normalize();
+        let reflected = Vec3::new(1.0, 0.0, 1.0).normalize();
+        let p_in = Vec3::new(1.0, 0.0, 1.0).normalize();
+        let field = JonesVector::linear(incoming, Vec3::Y + p_in);
+        let one = Complex::new(1.0, 0.0);
+        let zero = Complex::new(0.0, 0.0);
+        let (out, reflectance) = field.reflect(incoming, reflected, Vec3::Z, one, zero);
+        assert!((reflectance - 0.5).abs() < 1e-5);
+        assert!((out.re.y.abs() - 1.0).abs() < 1e-5);
+        let stokes = StokesVector::from_field(&out, reflected, 1.0);
+        assert!((stokes.degree_of_linear_polarization() - 1.0).abs() < 1e-5);
+        // Transverse x is world Y here, so s-polarized light has S1 = S0
+        assert!((stokes.s1 - stokes.s0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,642 @@
+//! Plain-text optical prescriptions: load a mirror system from a lens file
+//! and write the spawned system back out
+//!
+//! A prescription lists surfaces in the order light visits them. Each block
+//! runs from `surface <name>` to `end`, one `key value...` pair per line;
+//! `#` starts a comment. Lengths are meters, tilts degrees.
+//!
+//! ```text
+//! surface m1
+//!     type sag                 # sag, sphere, ellipsoid or plane
+//!     position 0 0 1.2         # mirror (and sag vertex) position
+//!     tilt 0 7.5 0             # about x, then y, then z (fixed axes)
+//!     radius -1.8              # vertex radius; inf for a flat base
+//!     conic -0.45
+//!     asphere 1.2e-3 -4e-5     # coefficients of r⁴, r⁶, ...
+//!     zernike 0 0 0 1e-9       # Noll-ordered, or: xy 2,0,1e-6 0,2,-1e-6
+//!     semi_diameter 0.15
+//!     aperture circle:0.15
+//!     coating mo_si            # mo_si, none, or a layer stack
+//!     roughness gaussian:0.1e-9,1e-6   # or abc:A,B,C; none (specular) if omitted
+//!     reticle                  # light reaching this surface is reticle-level
+//! end
+//! ```
+//!
+//! For image analysis, top-level `object x y z [tilt_x tilt_y tilt_z]` and
+//! `image ...` lines place the reticle and wafer planes, and `pupil x y z
+//! radius` the entrance pupil.
+//!
+//! `sphere` takes `radius` and an optional `center` (default: the centre of
+//! curvature on the local z-axis of the vertex at `position`); `ellipsoid`
+//! takes `focus1`, `focus2` and `semi_major`; `plane` takes `normal`. A layer
+//! stack is written `period=Si:4.14/Mo:2.76 periods=40 cap=Ru:2
+//! interdiffusion=0.5 substrate=Si`, with `cap=none` for an uncapped stack.
+
+use std::fmt::{self, Write as _};
+use bevy_ecs::prelude::*;
+use glam::{EulerRot, Quat, Vec3};
+use crate::units::{Distance, Position3D};
+use crate::components::*;
+use crate::debris::SurfaceDeposit;
+use crate::contamination::ContaminationState;
+use crate::raytracing::RayTracingConfig;
+use crate::reticle::{Absorber, AbsorberPattern, Reticle};
+use crate::coating::{Layer, MultilayerCoating, MultilayerStack};
+use crate::scatter::SurfaceRoughness;
+use crate::optics::{Aperture, Freeform, MirrorSurface, SagProfile, SurfaceGeometry, XyTerm};
+use crate::projection::{FieldPlane, ProjectionOptics};
+
+/// Heat capacity given to mirrors whose prescription does not set one (J/K)
+const DEFAULT_HEAT_CAPACITY: f32 = 2000.0;
+
+/// One mirror of a prescription
+#[derive(Debug, Clone)]
+pub struct SurfacePrescription {
+    pub name: String,
+    pub position: Position3D,
+    pub surface: MirrorSurface,
+    /// `None` leaves the mirror on its bulk [`OpticalMaterial`] reflectivity
+    pub coating: Option<MultilayerStack>,
+    pub roughness: Option<SurfaceRoughness>,
+    pub heat_capacity: f32,
+}
+
+/// Mirror system in the order light visits it
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Prescription {
+    pub surfaces: Vec<SurfacePrescription>,
+    /// Index of the surface whose incoming light is measured as reticle-level light
+    pub reticle_index: Option<usize>,
+    /// Object (reticle) and image (wafer) planes for image analysis
+    pub object: Option<FieldPlane>,
+    pub image: Option<FieldPlane>,
+    /// Entrance-pupil centre and radius (m)
+    pub pupil: Option<(Vec3, f32)>,
+}
+
+/// Tilt angles (degrees) about the fixed x, y and z axes, applied in that order
+fn tilt_rotation(degrees: Vec3) -> Quat {
+    let r = degrees * (std::f32::consts::PI / 180.0);
+    Quat::from_euler(EulerRot::ZYX, r.z, r.y, r.x)
+}
+
+fn rotation_tilt(rotation: Quat) -> Vec3 {
+    let (z, y, x) = rotation.to_euler(EulerRot::ZYX);
+    Vec3::new(x, y, z) * (180.0 / std::f32::consts::PI)
+}
+
+/// Surface fields collected from one block before its geometry is assembled
+#[derive(Default)]
+struct SurfaceBlock {
+    kind: Option<String>,
+    position: Option<Vec3>,
+    tilt: Option<Vec3>,
+    radius: Option<f64>,
+    conic: Option<f64>,
+    asphere: Vec<f64>,
+    freeform: Freeform,
+    semi_diameter: Option<f64>,
+    center: Option<Vec3>,
+    focus1: Option<Vec3>,
+    focus2: Option<Vec3>,
+    semi_major: Option<f32>,
+    normal: Option<Vec3>,
+    aperture: Option<Aperture>,
+    coating: Option<Option<MultilayerStack>>,
+    roughness: Option<Option<SurfaceRoughness>>,
+    heat_capacity: Option<f32>,
+}
+
+impl SurfaceBlock {
+    fn set(&mut self, key: &str, values: &[&str]) -> Result<(), String> {
+        let joined = values.join(" ");
+        match key {
+            "type" => self.kind = Some(single(values)?.to_string()),
+            "position" => self.position = Some(vector(values)?),
+            "tilt" => self.tilt = Some(vector(values)?),
+            "radius" => self.radius = Some(number(single(values)?)?),
+            "conic" => self.conic = Some(number(single(values)?)?),
+            "asphere" => self.asphere = values.iter().map(|v| number(v)).collect::<Result<_, _>>()?,
+            "zernike" => {
+                self.freeform = Freeform::Zernike(values.iter().map(|v| number(v)).collect::<Result<_, _>>()?)
+            }
+            "xy" => {
+                let terms = values
+                    .iter()
+                    .map(|term| match term.split(',').collect::<Vec<_>>().as_slice() {
+                        &[i, j, c] => Ok(XyTerm {
+                            x_power: i.parse().map_err(|e| format!("bad power '{i}': {e}"))?,
+                            y_power: j.parse().map_err(|e| format!("bad power '{j}': {e}"))?,
+                            coefficient: number(c)?,
+                        }),
+                        _ => Err(format!("xy term '{term}' needs x_power,y_power,coefficient")),
+                    })
+                    .collect::<Result<_, _>>()?;
+                self.freeform = Freeform::XyPolynomial(terms);
+            }
+            "semi_diameter" => self.semi_diameter = Some(number(single(values)?)?),
+            "center" => self.center = Some(vector(values)?),
+            "focus1" => self.focus1 = Some(vector(values)?),
+            "focus2" => self.focus2 = Some(vector(values)?),
+            "semi_major" => self.semi_major = Some(number(single(values)?)? as f32),
+            "normal" => self.normal = Some(vector(values)?),
+            "aperture" => self.aperture = Some(single(values)?.parse()?),
+            "coating" => self.coating = Some(parse_coating(&joined)?),
+            "roughness" => {
+                self.roughness = Some(match single(values)? {
+                    "none" => None,
+                    spec => Some(spec.parse()?),
+                })
+            }
+            "heat_capacity" => self.heat_capacity = Some(number(single(values)?)? as f32),
+            other => return Err(format!("unknown key '{other}'")),
+        }
+        Ok(())
+    }
+
+    fn build(self, name: String) -> Result<SurfacePrescription, String> {
+        let require = |value: Option<Vec3>, key: &str| value.ok_or_else(|| format!("surface '{name}' needs '{key}'"));
+        let position = self.position.unwrap_or(Vec3::ZERO);
+        let orientation = tilt_rotation(self.tilt.unwrap_or(Vec3::ZERO));
+        let geometry = match self.kind.as_deref() {
+            Some("sag") => SurfaceGeometry::Sag {
+                vertex: Position3D::from_vec3(position),
+                orientation,
+                profile: SagProfile {
+                    even_asphere: self.asphere,
+                    freeform: self.freeform,
+                    ..SagProfile::conic(
+                        self.radius.unwrap_or(f64::INFINITY),
+                        self.conic.unwrap_or(0.0),
+                        self.semi_diameter.ok_or_else(|| format!("surface '{name}' needs 'semi_diameter'"))?,
+                    )
+                },
+            },
+            Some("sphere") => {
+                let radius = self.radius.ok_or_else(|| format!("surface '{name}' needs 'radius'"))?;
+                let center = self
+                    .center
+                    .unwrap_or_else(|| position + orientation * Vec3::Z * radius as f32);
+                SurfaceGeometry::Spherical {
+                    radius: Distance::from_meters_f64(radius.abs()),
+                    center: Position3D::from_vec3(center),
+                }
+            }
+            Some("ellipsoid") => {
+                let focus1 = Position3D::from_vec3(require(self.focus1, "focus1")?);
+                let focus2 = Position3D::from_vec3(require(self.focus2, "focus2")?);
+                let semi_major = self.semi_major.ok_or_else(|| format!("surface '{name}' needs 'semi_major'"))?;
+                SurfaceGeometry::ellipsoid_from_foci(focus1, focus2, semi_major)
+                    .ok_or_else(|| format!("surface '{name}': semi_major must exceed half the focal separation"))?
+            }
+            Some("plane") => SurfaceGeometry::Planar { normal: require(self.normal, "normal")?.normalize() },
+            Some(other) => return Err(format!("surface '{name}' has unknown type '{other}'")),
+            None => return Err(format!("surface '{name}' needs a 'type'")),
+        };
+
+        let aperture = match (self.aperture, &geometry) {
+            (Some(aperture), _) => aperture,
+            (None, SurfaceGeometry::Sag { profile, .. }) => Aperture::Circular { radius: profile.semi_diameter as f32 },
+            (None, _) => return Err(format!("surface '{name}' needs an 'aperture'")),
+        };
+        Ok(SurfacePrescription {
+            name,
+            position: Position3D::from_vec3(position),
+            surface: MirrorSurface { geometry, orientation, aperture },
+            coating: self.coating.unwrap_or_else(|| Some(MultilayerStack::mo_si())),
+            roughness: self.roughness.flatten(),
+            heat_capacity: self.heat_capacity.unwrap_or(DEFAULT_HEAT_CAPACITY),
+        })
+    }
+}
+
+fn single<'a>(values: &[&'a str]) -> Result<&'a str, String> {
+    match values {
+        &[value] => Ok(value),
+        _ => Err(format!("expected one value, found {}", values.len())),
+    }
+}
+
+fn number(text: &str) -> Result<f64, String> {
+    text.parse::<f64>().map_err(|e| format!("bad number '{text}': {e}"))
+}
+
+fn vector(values: &[&str]) -> Result<Vec3, String> {
+    match values {
+        &[x, y, z] => Ok(Vec3::new(number(x)? as f32, number(y)? as f32, number(z)? as f32)),
+        _ => Err(format!("expected three values, found {}", values.len())),
+    }
+}
+
+/// `x y z` or `x y z tilt_x tilt_y tilt_z`
+fn field_plane(values: &[&str]) -> Result<FieldPlane, String> {
+    match values.len() {
+        3 => Ok(FieldPlane::new(vector(values)?, Quat::IDENTITY)),
+        6 => Ok(FieldPlane::new(vector(&values[..3])?, tilt_rotation(vector(&values[3..])?))),
+        n => Err(format!("expected a position and optional tilt, found {n} values")),
+    }
+}
+
+fn parse_layer(text: &str) -> Result<Layer, String> {
+    let (material, thickness) = text.split_once(':').ok_or_else(|| format!("layer '{text}' needs material:thickness"))?;
+    Ok(Layer { material: material.parse()?, thickness: number(thickness)? })
+}
+
+fn parse_coating(text: &str) -> Result<Option<MultilayerStack>, String> {
+    match text.trim() {
+        "none" => return Ok(None),
+        "mo_si" => return Ok(Some(MultilayerStack::mo_si())),
+        _ => {}
+    }
+    let mut stack = MultilayerStack { period: Vec::new(), periods: 1, capping: None, ..MultilayerStack::mo_si() };
+    for field in text.split_whitespace() {
+        let (key, value) = field.split_once('=').ok_or_else(|| format!("coating field '{field}' needs key=value"))?;
+        match key {
+            "period" => stack.period = value.split('/').map(parse_layer).collect::<Result<_, _>>()?,
+            "periods" => stack.periods = value.parse().map_err(|e| format!("bad period count '{value}': {e}"))?,
+            "cap" if value == "none" => stack.capping = None,
+            "cap" => stack.capping = Some(parse_layer(value)?),
+            "interdiffusion" => stack.interdiffusion = number(value)?,
+            "substrate" => stack.substrate = value.parse()?,
+            other => return Err(format!("unknown coating field '{other}'")),
+        }
+    }
+    if stack.period.is_empty() {
+        return Err("coating stack needs a 'period'".to_string());
+    }
+    Ok(Some(stack))
+}
+
+fn write_coating(out: &mut String, stack: &MultilayerStack) -> fmt::Result {
+    if *stack == MultilayerStack::mo_si() {
+        return out.write_str("mo_si");
+    }
+    let period: Vec<String> = stack.period.iter().map(|l| format!("{}:{}", l.material, l.thickness)).collect();
+    write!(out, "period={} periods={}", period.join("/"), stack.periods)?;
+    match stack.capping {
+        Some(cap) => write!(out, " cap={}:{}", cap.material, cap.thickness)?,
+        None => out.write_str(" cap=none")?,
+    }
+    write!(out, " interdiffusion={} substrate={}", stack.interdiffusion, stack.substrate)
+}
+
+impl std::str::FromStr for Prescription {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut prescription = Prescription::default();
+        let mut open: Option<(String, SurfaceBlock)> = None;
+        for (index, line) in s.lines().enumerate() {
+            let at = |e: String| format!("line {}: {e}", index + 1);
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut words = line.split_whitespace();
+            let Some(key) = words.next() else { continue };
+            let values: Vec<&str> = words.collect();
+            match (&mut open, key) {
+                (None, "surface") => open = Some((single(&values).map_err(at)?.to_string(), SurfaceBlock::default())),
+                (None, "object") => prescription.object = Some(field_plane(&values).map_err(at)?),
+                (None, "image") => prescription.image = Some(field_plane(&values).map_err(at)?),
+                (None, "pupil") => match values[..] {
+                    [x, y, z, radius] => {
+                        let radius = number(radius).map_err(at)? as f32;
+                        prescription.pupil = Some((vector(&[x, y, z]).map_err(at)?, radius));
+                    }
+                    _ => return Err(at("pupil needs x y z radius".to_string())),
+                },
+                (None, other) => return Err(at(format!("expected 'surface', found '{other}'"))),
+                (Some(_), "surface") => return Err(at("missing 'end' before the next surface".to_string())),
+                (Some(_), "end") => {
+                    let (name, block) = open.take().unwrap();
+                    prescription.surfaces.push(block.build(name).map_err(at)?);
+                }
+                (Some(_), "reticle") => prescription.reticle_index = Some(prescription.surfaces.len()),
+                (Some((_, block)), key) => block.set(key, &values).map_err(at)?,
+            }
+        }
+        if let Some((name, _)) = open {
+            return Err(format!("surface '{name}' is missing its 'end'"));
+        }
+        Ok(prescription)
+    }
+}
+
+/// Writes the form accepted by `FromStr`
+impl fmt::Display for Prescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# lithos optical prescription: meters, degrees")?;
+        // Adding zero turns -0 into 0
+        let v = |v: Vec3| format!("{} {} {}", v.x + 0.0, v.y + 0.0, v.z + 0.0);
+        for (key, plane) in [("object", &self.object), ("image", &self.image)] {
+            if let Some(plane) = plane {
+                writeln!(f, "{key} {} {}", v(plane.origin), v(rotation_tilt(plane.orientation)))?;
+            }
+        }
+        if let Some((center, radius)) = self.pupil {
+            writeln!(f, "pupil {} {radius}", v(center))?;
+        }
+        for (index, surface) in self.surfaces.iter().enumerate() {
+            let mut out = String::new();
+            writeln!(out, "\nsurface {}", surface.name)?;
+            let position = surface.position.to_vec3();
+            let mirror = &surface.surface;
+            match &mirror.geometry {
+                SurfaceGeometry::Sag { vertex, orientation, profile } => {
+                    writeln!(out, "    type sag")?;
+                    // The sag frame, not the mirror's, defines the written position and tilt
+                    writeln!(out, "    position {}", v(vertex.to_vec3()))?;
+                    writeln!(out, "    tilt {}", v(rotation_tilt(*orientation)))?;
+                    if profile.curvature == 0.0 {
+                        writeln!(out, "    radius inf")?;
+                    } else {
+                        writeln!(out, "    radius {}", 1.0 / profile.curvature)?;
+                    }
+                    writeln!(out, "    conic {}", profile.conic)?;
+                    if !profile.even_asphere.is_empty() {
+                        let terms: Vec<String> = profile.even_asphere.iter().map(f64::to_string).collect();
+                        writeln!(out, "    asphere {}", terms.join(" "))?;
+                    }
+                    match &profile.freeform {
+                        Freeform::None => {}
+                        Freeform::Zernike(terms) => {
+                            let terms: Vec<String> = terms.iter().map(f64::to_string).collect();
+                            writeln!(out, "    zernike {}", terms.join(" "))?;
+                        }
+                        Freeform::XyPolynomial(terms) => {
+                            let terms: Vec<String> = terms
+                                .iter()
+                                .map(|t| format!("{},{},{}", t.x_power, t.y_power, t.coefficient))
+                                .collect();
+                            writeln!(out, "    xy {}", terms.join(" "))?;
+                        }
+                    }
+                    writeln!(out, "    semi_diameter {}", profile.semi_diameter)?;
+                }
+                SurfaceGeometry::Spherical { radius, center } => {
+                    writeln!(out, "    type sphere")?;
+                    writeln!(out, "    position {}", v(position))?;
+                    writeln!(out, "    tilt {}", v(rotation_tilt(mirror.orientation)))?;
+                    writeln!(out, "    radius {}", radius.as_meters_f64())?;
+                    writeln!(out, "    center {}", v(center.to_vec3()))?;
+                }
+                SurfaceGeometry::Ellipsoid { semi_axes, focus1, focus2 } => {
+                    writeln!(out, "    type ellipsoid")?;
+                    writeln!(out, "    position {}", v(position))?;
+                    writeln!(out, "    tilt {}", v(rotation_tilt(mirror.orientation)))?;
+                    writeln!(out, "    focus1 {}", v(focus1.to_vec3()))?;
+                    writeln!(out, "    focus2 {}", v(focus2.to_vec3()))?;
+                    writeln!(out, "    semi_major {}", semi_axes.x)?;
+                }
+                SurfaceGeometry::Planar { normal } => {
+                    writeln!(out, "    type plane")?;
+                    writeln!(out, "    position {}", v(position))?;
+                    writeln!(out, "    tilt {}", v(rotation_tilt(mirror.orientation)))?;
+                    writeln!(out, "    normal {}", v(*normal))?;
+                }
+            }
+            writeln!(out, "    aperture {}", mirror.aperture)?;
+            out.write_str("    coating ")?;
+            match &surface.coating {
+                Some(stack) => write_coating(&mut out, stack)?,
+                None => out.write_str("none")?,
+            }
+            out.push('\n');
+            match &surface.roughness {
+                Some(roughness) => writeln!(out, "    roughness {roughness}")?,
+                None => writeln!(out, "    roughness none")?,
+            }
+            writeln!(out, "    heat_capacity {}", surface.heat_capacity)?;
+            if self.reticle_index == Some(index) {
+                writeln!(out, "    reticle")?;
+            }
+            writeln!(out, "end")?;
+            f.write_str(&out)?;
+        }
+        Ok(())
+    }
+}
+
+impl Prescription {
+    /// Mirrors in the world, in the declared tracing order followed by any
+    /// that are not part of it, named `s1`, `s2`, ...
+    pub fn from_world(world: &mut World) -> Self {
+        let config = world.resource::<RayTracingConfig>().clone();
+        let mut query = world.query::<(
+            Entity,
+            &Position,
+            &MirrorSurface,
+            Option<&MultilayerCoating>,
+            Option<&Reticle>,
+            Option<&SurfaceRoughness>,
+            Option<&ThermalState>,
+        )>();
+        let mut mirrors: Vec<_> = query.iter(world).collect();
+        mirrors.sort_by_key(|(entity, ..)| {
+            let order = config.sequence.iter().position(|e| e == entity).unwrap_or(usize::MAX);
+            (order, *entity)
+        });
+        let surfaces = mirrors
+            .into_iter()
+            .enumerate()
+            .map(|(index, (_, position, surface, coating, reticle, roughness, thermal))| SurfacePrescription {
+                name: format!("s{}", index + 1),
+                position: position.0,
+                surface: surface.clone(),
+                // A reticle exports its blank; the absorber pattern is not part of the format
+                coating: coating.or(reticle.map(|r| &r.blank)).map(|c| c.stack.clone()),
+                roughness: roughness.copied(),
+                heat_capacity: thermal.map_or(DEFAULT_HEAT_CAPACITY, |t| t.heat_capacity),
+            })
+            .collect();
+        Self { surfaces, reticle_index: config.reticle_index, ..Default::default() }
+    }
+
+    /// Mirror train between the object and image planes, for image analysis
+    pub fn projection_optics(&self) -> Result<ProjectionOptics, String> {
+        let missing = |key: &str| format!("prescription needs an '{key}' line for image analysis");
+        let (entrance_pupil, entrance_pupil_radius) = self.pupil.ok_or_else(|| missing("pupil"))?;
+        Ok(ProjectionOptics {
+            reticle: self.object.ok_or_else(|| missing("object"))?,
+            wafer: self.image.ok_or_else(|| missing("image"))?,
+            entrance_pupil,
+            entrance_pupil_radius,
+            mirrors: self.surfaces.iter().map(|s| (s.position, s.surface.clone())).collect(),
+        })
+    }
+}
+
+/// Spawns the prescription's mirrors and declares them, in file order, as
+/// the sequence for sequential ray tracing
+///
+/// A coated `reticle` surface becomes an unpatterned [`Reticle`] on its coating.
+pub fn spawn_prescription(
+    mut commands: Commands,
+    prescription: Res<Prescription>,
+    mut tracing: ResMut<RayTracingConfig>,
+) {
+    let mut sequence = Vec::with_capacity(prescription.surfaces.len());
+    for (index, surface) in prescription.surfaces.iter().enumerate() {
+        let mut mirror = commands.spawn((
+            Position(surface.position),
+            ContaminationState::for_mirror(&surface.surface, ContaminationState::DEFAULT_CLEANING_RATE),
+            surface.surface.clone(),
+            OpticalMaterial::BRAGG_MIRROR,
+            ThermalState::new(ThermalState::AMBIENT, surface.heat_capacity),
+            SurfaceDeposit::default(),
+            EntityType::Mirror,
+        ));
+        match &surface.coating {
+            // The reticle's coating is its blank, with no absorber pattern
+            Some(stack) if prescription.reticle_index == Some(index) => {
+                mirror.insert(Reticle::new(stack.clone(), Absorber::TA_BN, AbsorberPattern::default()));
+            }
+            Some(stack) => {
+                mirror.insert(MultilayerCoating::new(stack.clone()));
+            }
+            None => {}
+        }
+        if let Some(roughness) = surface.roughness {
+            mirror.insert(roughness);
+        }
+        sequence.push(mirror.id());
+    }
+    tracing.sequence = sequence;
+    tracing.reticle_index = prescription.reticle_index;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::system::RunSystemOnce;
+    use crate::optics::{spawn_optical_system, OpticalSystemConfig};
+
+    const TWO_MIRROR: &str = "
+        # Collector and one freeform
+        surface collector
+            type ellipsoid
+            focus1 0 0 0
+            focus2 0.5 0 0
+            semi_major 0.3
+            tilt 0 90 0
+            aperture annulus:0.02,0.2
+            roughness gaussian:0.25e-9,1e-6
+            heat_capacity 5000
+        end
+        surface m1
+            type sag
+            position 1 0 0
+            tilt 0 -90 0     # vertex faces back towards the collector
+            radius -2.0
+            conic -0.5
+            asphere 1e-3 -2e-5
+            xy 2,1,1e-6 0,3,-2e-6
+            semi_diameter 0.2
+            coating period=Si:4.1/Mo:2.8 periods=50 cap=none interdiffusion=0 substrate=Si
+            reticle
+        end
+    ";
+
+    #[test]
+    fn test_parse_prescription() {
+        let prescription: Prescription = TWO_MIRROR.parse().unwrap();
+        assert_eq!(prescription.surfaces.len(), 2);
+        assert_eq!(prescription.reticle_index, Some(1));
+
+        let collector = &prescription.surfaces[0];
+        assert!(matches!(collector.surface.geometry, SurfaceGeometry::Ellipsoid { .. }));
+        assert_eq!(collector.heat_capacity, 5000.0);
+        assert_eq!(collector.coating, Some(MultilayerStack::mo_si()));
+        // +90° about y turns the local z-axis onto +x
+        assert!((collector.surface.orientation * Vec3::Z - Vec3::X).length() < 1e-6);
+
+        let m1 = &prescription.surfaces[1];
+        let SurfaceGeometry::Sag { vertex, orientation, profile } = &m1.surface.geometry else { panic!() };
+        assert!((vertex.to_vec3() - Vec3::X).length() < 1e-6);
+        assert!((*orientation * Vec3::Z + Vec3::X).length() < 1e-6);
+        assert!((profile.curvature + 0.5).abs() < 1e-12);
+        assert_eq!(profile.even_asphere, vec![1e-3, -2e-5]);
+        assert!(matches!(&profile.freeform, Freeform::XyPolynomial(terms) if terms.len() == 2));
+        assert!(matches!(m1.surface.aperture, Aperture::Circular { radius } if (radius - 0.2).abs() < 1e-6));
+        let stack = m1.coating.as_ref().unwrap();
+        assert_eq!((stack.periods, stack.capping, stack.interdiffusion), (50, None, 0.0));
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        let missing_end = "surface a\n type plane\n normal 0 0 1\n aperture circle:1\n";
+        assert!("surface a\n type plane\n normal 0 0 1\n aperture circle:1\n end\n".parse::<Prescription>().is_ok());
+        assert!(missing_end.parse::<Prescription>().unwrap_err().contains("'end'"));
+        let error = "surface a\n type sag\n conic abc\nend".parse::<Prescription>().unwrap_err();
+        assert!(error.starts_with("line 3:"), "{error}");
+        assert!("surface a\n type sag\nend".parse::<Prescription>().unwrap_err().contains("semi_diameter"));
+    }
+
+    fn assert_same_system(a: &Prescription, b: &Prescription) {
+        assert_eq!(a.surfaces.len(), b.surfaces.len());
+        assert_eq!(a.reticle_index, b.reticle_index);
+        for (a, b) in a.surfaces.iter().zip(&b.surfaces) {
+            assert!((a.position.to_vec3() - b.position.to_vec3()).length() < 1e-6);
+            for axis in [Vec3::X, Vec3::Z] {
+                assert!((a.surface.orientation * axis - b.surface.orientation * axis).length() < 1e-5);
+            }
+            assert_eq!(a.surface.aperture.to_string(), b.surface.aperture.to_string());
+            assert_eq!(a.coating, b.coating);
+            assert_eq!(a.roughness, b.roughness);
+            assert_eq!(a.heat_capacity, b.heat_capacity);
+            match (&a.surface.geometry, &b.surface.geometry) {
+                (SurfaceGeometry::Sag { profile: pa, .. }, SurfaceGeometry::Sag { profile: pb, .. }) => {
+                    assert!((pa.curvature - pb.curvature).abs() < 1e-12);
+                    assert_eq!((pa.conic, &pa.even_asphere, &pa.freeform), (pb.conic, &pb.even_asphere, &pb.freeform));
+                }
+                (SurfaceGeometry::Spherical { radius: ra, center: ca }, SurfaceGeometry::Spherical { radius: rb, center: cb }) => {
+                    assert!((ra.as_meters_f64() - rb.as_meters_f64()).abs() < 1e-9);
+                    assert!((ca.to_vec3() - cb.to_vec3()).length() < 1e-6);
+                }
+                (SurfaceGeometry::Ellipsoid { semi_axes: sa, .. }, SurfaceGeometry::Ellipsoid { semi_axes: sb, .. }) => {
+                    assert!((*sa - *sb).length() < 1e-6);
+                }
+                (ga, gb) => panic!("geometry changed from {ga:?} to {gb:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let text = format!("object 0 0 1.5 0 180 0\nimage 0 0 0.75\npupil 0 0 1 0.05\n{TWO_MIRROR}");
+        let prescription: Prescription = text.parse().unwrap();
+        let reparsed: Prescription = prescription.to_string().parse().unwrap();
+        assert_same_system(&prescription, &reparsed);
+
+        let optics = reparsed.projection_optics().unwrap();
+        assert!((optics.reticle.normal() + Vec3::Z).length() < 1e-6);
+        assert_eq!(optics.wafer.origin, Vec3::new(0.0, 0.0, 0.75));
+        assert_eq!((optics.entrance_pupil, optics.entrance_pupil_radius), (Vec3::Z, 0.05));
+        assert_eq!(optics.mirrors.len(), 2);
+        assert!(TWO_MIRROR.parse::<Prescription>().unwrap().projection_optics().is_err());
+    }
+
+    #[test]
+    fn test_spawn_and_export_round_trip() {
+        let prescription: Prescription = TWO_MIRROR.parse().unwrap();
+        let mut world = World::new();
+        world.insert_resource(RayTracingConfig::default());
+        world.insert_resource(prescription.clone());
+        world.run_system_once(spawn_prescription).unwrap();
+        let sequence = world.resource::<RayTracingConfig>().sequence.clone();
+        assert_eq!(sequence.len(), 2);
+        // The reticle surface's coating becomes an unpatterned reticle blank
+        let reticle = world.get::<Reticle>(sequence[1]).unwrap();
+        assert_eq!(Some(&reticle.blank.stack), prescription.surfaces[1].coating.as_ref());
+        assert!(world.get::<MultilayerCoating>(sequence[1]).is_none());
+        assert_same_system(&prescription, &Prescription::from_world(&mut world));
+
+        // The built-in system exports and reloads to the same mirrors
+        let mut world = World::new();
+        world.insert_resource(RayTracingConfig::default());
+        world.insert_resource(OpticalSystemConfig::default());
+        let sequence = world.run_system_once(spawn_optical_system).unwrap();
+        world.resource_mut::<RayTracingConfig>().sequence = sequence;
+        let exported = Prescription::from_world(&mut world);
+        assert_eq!(exported.surfaces.len(), 3);
+        assert_same_system(&exported, &exported.to_string().parse().unwrap());
+    }
+}
@@ -0,0 +1,388 @@
+//! Projection box: reticle-to-wafer field mapping of the projection optics
+//!
+//! High-NA scanners demagnify anamorphically, 4× across the slit (x) and 8×
+//! along the scan (y). The box maps reticle field points to wafer image points
+//! with a signed magnification per axis plus polynomial distortion, and gives
+//! the chief-ray angle at the wafer (telecentricity). A box can be fitted to
+//! chief rays traced through a mirror train to check the optics against it.
+
+use bevy_ecs::prelude::*;
+use glam::{DVec2, DVec3, Quat, Vec2, Vec3};
+use nalgebra::{DMatrix, DVector};
+use crate::units::Position3D;
+use crate::optics::MirrorSurface;
+use crate::raytracing::trace_specular;
+
+/// Plane carrying field coordinates: local x/y in the plane, local z its normal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldPlane {
+    pub origin: Vec3,
+    /// Rotation from the plane's local frame to the world
+    pub orientation: Quat,
+}
+
+impl FieldPlane {
+    pub fn new(origin: Vec3, orientation: Quat) -> Self {
+        Self { origin, orientation }
+    }
+
+    pub fn point(&self, field: Vec2) -> Vec3 {
+        self.origin + self.orientation * field.extend(0.0)
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.orientation * Vec3::Z
+    }
+
+    /// Where a ray crosses the plane, in field coordinates, with the ray
+    /// direction in the plane's frame; `None` if it runs parallel or away
+    pub fn crossing(&self, origin: Vec3, direction: Vec3) -> Option<(Vec2, Vec3)> {
+        let normal = self.normal();
+        let denom = normal.dot(direction);
+        if denom.abs() < 1e-9 {
+            return None;
+        }
+        let t = normal.dot(self.origin - origin) / denom;
+        if t < 0.0 {
+            return None;
+        }
+        let inverse = self.orientation.inverse();
+        let local = inverse * (origin + direction * t - self.origin);
+        Some((local.truncate(), inverse * direction))
+    }
+}
+
+/// Wafer displacement proportional to xⁱyʲ of the reticle field point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistortionTerm {
+    pub x_power: u32,
+    pub y_power: u32,
+    /// Displacement (m) per m^(i+j) of reticle field
+    pub displacement: Vec2,
+}
+
+/// Reticle-to-wafer mapping of a projection lens
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ProjectionBox {
+    /// Image/object size ratio along reticle x and y; negative for an inverted image
+    pub magnification: Vec2,
+    /// Wafer point imaged from the reticle origin (m)
+    pub image_offset: Vec2,
+    /// Departures from the linear mapping, including any rotation or skew
+    pub distortion: Vec<DistortionTerm>,
+    /// Chief-ray slope (dx/dz, dy/dz) at the wafer for the on-axis image point
+    pub chief_ray_tilt: Vec2,
+    /// Change of chief-ray slope per meter of image height along x and y;
+    /// zero for a telecentric image
+    pub chief_ray_gradient: Vec2,
+}
+
+impl Default for ProjectionBox {
+    fn default() -> Self {
+        Self::HIGH_NA
+    }
+}
+
+/// Reticle x and y powers of the distortion terms fitted to traced chief rays
+const FIT_TERMS: [(u32, u32); 10] = [
+    (0, 0), (1, 0), (0, 1),
+    (2, 0), (1, 1), (0, 2),
+    (3, 0), (2, 1), (1, 2), (0, 3),
+];
+
+impl ProjectionBox {
+    /// Ideal High-NA box: 4× across the slit, 8× along the scan, telecentric
+    pub const HIGH_NA: Self = Self::anamorphic(4.0, 8.0);
+
+    /// Distortion-free, telecentric box with the given demagnifications
+    pub const fn anamorphic(demagnification_x: f32, demagnification_y: f32) -> Self {
+        Self {
+            magnification: Vec2::new(1.0 / demagnification_x, 1.0 / demagnification_y),
+            image_offset: Vec2::ZERO,
+            distortion: Vec::new(),
+            chief_ray_tilt: Vec2::ZERO,
+            chief_ray_gradient: Vec2::ZERO,
+        }
+    }
+
+    /// Demagnification along reticle x and y (object/image size)
+    pub fn demagnification(&self) -> Vec2 {
+        Vec2::ONE / self.magnification.abs()
+    }
+
+    /// Wafer image point of a reticle field point (m)
+    pub fn map(&self, reticle: Vec2) -> Vec2 {
+        let linear = self.image_offset + self.magnification * reticle;
+        self.distortion.iter().fold(linear, |image, term| {
+            image + term.displacement * reticle.x.powi(term.x_power as i32) * reticle.y.powi(term.y_power as i32)
+        })
+    }
+
+    /// Distortion at a field point: displacement from the linear image (m)
+    pub fn distortion_at(&self, reticle: Vec2) -> Vec2 {
+        self.map(reticle) - (self.image_offset + self.magnification * reticle)
+    }
+
+    /// Chief-ray slope (dx/dz, dy/dz) at the wafer image of a reticle field point
+    pub fn chief_ray_slope(&self, reticle: Vec2) -> Vec2 {
+        let image = self.map(reticle) - self.image_offset;
+        self.chief_ray_tilt + self.chief_ray_gradient * image
+    }
+
+    /// Angle between the chief ray and the wafer normal (rad)
+    pub fn telecentricity_error(&self, reticle: Vec2) -> f32 {
+        self.chief_ray_slope(reticle).length().atan()
+    }
+
+    /// Least-squares box through traced chief rays
+    ///
+    /// Fits a cubic in the reticle coordinates for each image axis; the
+    /// diagonal linear terms become the magnification and every other
+    /// non-constant term a distortion term. Needs at least ten rays spread
+    /// over both field axes.
+    pub fn fit(rays: &[ChiefRay]) -> Option<Self> {
+        if rays.len() < FIT_TERMS.len() {
+            return None;
+        }
+        // Normalise the field so the cubic terms stay well conditioned
+        let scale = rays.iter().map(|r| r.reticle.abs().max_element()).fold(0.0, f32::max) as f64;
+        if scale == 0.0 {
+            return None;
+        }
+        let basis = DMatrix::from_fn(rays.len(), FIT_TERMS.len(), |row, column| {
+            let (i, j) = FIT_TERMS[column];
+            let p = rays[row].reticle.as_dvec2() / scale;
+            p.x.powi(i as i32) * p.y.powi(j as i32)
+        });
+        let svd = basis.svd(true, true);
+        let solve = |values: DVector<f64>| svd.solve(&values, 1e-12).ok();
+        let x = solve(DVector::from_iterator(rays.len(), rays.iter().map(|r| r.wafer.x as f64)))?;
+        let y = solve(DVector::from_iterator(rays.len(), rays.iter().map(|r| r.wafer.y as f64)))?;
+
+        let coefficient = |column: usize| {
+            let (i, j) = FIT_TERMS[column];
+            let unscale = scale.powi((i + j) as i32);
+            Vec2::new((x[column] / unscale) as f32, (y[column] / unscale) as f32)
+        };
+        let mut distortion = Vec::new();
+        for (column, &(x_power, y_power)) in FIT_TERMS.iter().enumerate().skip(1) {
+            let mut displacement = coefficient(column);
+            // The diagonal linear terms are the magnification itself
+            match (x_power, y_power) {
+                (1, 0) => displacement.x = 0.0,
+                (0, 1) => displacement.y = 0.0,
+                _ => {}
+            }
+            if displacement != Vec2::ZERO {
+                distortion.push(DistortionTerm { x_power, y_power, displacement });
+            }
+        }
+        let magnification = Vec2::new(coefficient(1).x, coefficient(2).y);
+        let image_offset = coefficient(0);
+
+        // Chief-ray slope against image height, one line per axis
+        let slope_fit = |height: fn(&ChiefRay) -> f32, slope: fn(&ChiefRay) -> f32| {
+            let n = rays.len() as f64;
+            let (mut sh, mut ss, mut shh, mut shs) = (0.0, 0.0, 0.0, 0.0);
+            for ray in rays {
+                let h = height(ray) as f64;
+                let s = slope(ray) as f64;
+                sh += h;
+                ss += s;
+                shh += h * h;
+                shs += h * s;
+            }
+            let variance = n * shh - sh * sh;
+            let gradient = if variance.abs() > 1e-30 { (n * shs - sh * ss) / variance } else { 0.0 };
+            ((ss - gradient * sh) / n, gradient)
+        };
+        let (tilt_x, gradient_x) = slope_fit(|r| r.wafer.x, |r| r.slope().x);
+        let (tilt_y, gradient_y) = slope_fit(|r| r.wafer.y, |r| r.slope().y);
+        let offset_slope = Vec2::new(gradient_x as f32, gradient_y as f32) * image_offset;
+
+        Some(Self {
+            magnification,
+            image_offset,
+            distortion,
+            chief_ray_tilt: Vec2::new(tilt_x as f32, tilt_y as f32) + offset_slope,
+            chief_ray_gradient: Vec2::new(gradient_x as f32, gradient_y as f32),
+        })
+    }
+
+    /// Largest distance between traced image points and the box's prediction (m)
+    pub fn max_residual(&self, rays: &[ChiefRay]) -> f32 {
+        rays.iter().map(|r| (self.map(r.reticle) - r.wafer).length()).fold(0.0, f32::max)
+    }
+}
+
+/// Chief ray from a reticle field point to its wafer image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChiefRay {
+    /// Field point in reticle-plane coordinates (m)
+    pub reticle: Vec2,
+    /// Image point in wafer-plane coordinates (m)
+    pub wafer: Vec2,
+    /// Unit direction arriving at the wafer, in the wafer plane's frame
+    pub direction: Vec3,
+}
+
+impl ChiefRay {
+    /// Slope (dx/dz, dy/dz) of the ray at the wafer
+    pub fn slope(&self) -> Vec2 {
+        self.direction.truncate() / self.direction.z
+    }
+}
+
+/// Mirror train from reticle to wafer with its entrance pupil
+#[derive(Debug, Clone)]
+pub struct ProjectionOptics {
+    pub reticle: FieldPlane,
+    pub wafer: FieldPlane,
+    /// Centre of the entrance pupil; every chief ray leaves the reticle towards it
+    pub entrance_pupil: Vec3,
+    /// Radius of the entrance pupil, a disk facing the reticle origin (m)
+    pub entrance_pupil_radius: f32,
+    /// Mirror positions and surfaces in the order light visits them
+    pub mirrors: Vec<(Position3D, MirrorSurface)>,
+}
+
+impl ProjectionOptics {
+    /// Traces the chief ray of a reticle field point with [`trace_specular`]
+    ///
+    /// Returns `None` if the ray misses a mirror, leaves through the outside
+    /// of a clear aperture, or never reaches the wafer plane.
+    pub fn trace_chief_ray(&self, field: Vec2) -> Option<ChiefRay> {
+        let origin = self.reticle.point(field);
+        let path = trace_specular(&self.mirrors, origin.as_dvec3(), (self.entrance_pupil - origin).as_dvec3())?;
+        let (wafer, direction) = self.wafer.crossing(path.point.as_vec3(), path.direction.as_vec3())?;
+        Some(ChiefRay { reticle: field, wafer, direction })
+    }
+
+    /// Unit vectors spanning the entrance pupil disk, perpendicular to the
+    /// line from the reticle origin to the pupil centre
+    pub fn pupil_axes(&self) -> (Vec3, Vec3) {
+        let axis = (self.entrance_pupil - self.reticle.origin).normalize();
+        // Keep the pupil's x aligned with the reticle's x where possible
+        let x = (self.reticle.orientation * Vec3::X).reject_from(axis).try_normalize()
+            .unwrap_or_else(|| axis.any_orthonormal_vector());
+        (x, axis.cross(x))
+    }
+
+    /// World point of a normalised entrance-pupil coordinate (unit disk)
+    pub fn pupil_point(&self, pupil: DVec2) -> DVec3 {
+        let (x, y) = self.pupil_axes();
+        self.entrance_pupil.as_dvec3()
+            + (x.as_dvec3() * pupil.x + y.as_dvec3() * pupil.y) * self.entrance_pupil_radius as f64
+    }
+
+    /// Cell centres of a `grid` × `grid` square over the pupil that fall inside the unit disk
+    pub fn pupil_grid(grid: usize) -> impl Iterator<Item = DVec2> {
+        let grid = grid.max(2);
+        let coordinate = move |k: usize| 2.0 * (k as f64 + 0.5) / grid as f64 - 1.0;
+        (0..grid)
+            .flat_map(move |i| (0..grid).map(move |j| DVec2::new(coordinate(i), coordinate(j))))
+            .filter(|pupil| pupil.length_squared() <= 1.0)
+    }
+
+    /// Chief rays over a `steps` × `steps` grid spanning ± `half_field` on the reticle
+    pub fn trace_field_grid(&self, half_field: Vec2, steps: usize) -> Vec<ChiefRay> {
+        let steps = steps.max(2);
+        let coordinate = |i: usize| 2.0 * i as f32 / (steps - 1) as f32 - 1.0;
+        (0..steps)
+            .flat_map(|i| (0..steps).map(move |j| Vec2::new(coordinate(i), coordinate(j))))
+            .filter_map(|unit| self.trace_chief_ray(unit * half_field))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+    use crate::optics::{Aperture, SagProfile, SurfaceGeometry};
+
+    /// Concave sphere of radius 1 m, vertex at the origin facing +z, with the
+    /// reticle, entrance pupil (radius 5 cm) and wafer on its axis at the given heights
+    ///
+    /// With the pupil at the centre of curvature every chief ray meets the
+    /// mirror normally and retraces its path, so the image is distortion-free.
+    pub(crate) fn concave_sphere(object_z: f32, pupil_z: f32, image_z: f32) -> ProjectionOptics {
+        ProjectionOptics {
+            reticle: FieldPlane::new(Vec3::new(0.0, 0.0, object_z), Quat::IDENTITY),
+            wafer: FieldPlane::new(Vec3::new(0.0, 0.0, image_z), Quat::IDENTITY),
+            entrance_pupil: Vec3::new(0.0, 0.0, pupil_z),
+            entrance_pupil_radius: 0.05,
+            mirrors: vec![(Position3D::zero(), MirrorSurface {
+                geometry: SurfaceGeometry::Sag {
+                    vertex: Position3D::zero(),
+                    orientation: Quat::IDENTITY,
+                    profile: SagProfile::conic(1.0, 0.0, 0.2),
+                },
+                orientation: Quat::IDENTITY,
+                aperture: Aperture::Circular { radius: 0.2 },
+            })],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::fixtures::concave_sphere;
+
+    #[test]
+    fn test_high_na_mapping() {
+        let projection = ProjectionBox::HIGH_NA;
+        assert_eq!(projection.demagnification(), Vec2::new(4.0, 8.0));
+        // Full reticle field 104 mm × 132 mm images onto a 26 mm × 16.5 mm half field
+        let corner = projection.map(Vec2::new(0.052, 0.066));
+        assert!((corner - Vec2::new(0.013, 0.00825)).length() < 1e-9);
+        assert_eq!(projection.telecentricity_error(Vec2::new(0.052, 0.066)), 0.0);
+    }
+
+    #[test]
+    fn test_fit_to_traced_chief_rays() {
+        // Object 1.5 m in front of the vertex images 0.75 m in front of it
+        let optics = concave_sphere(1.5, 1.0, 0.75);
+        let rays = optics.trace_field_grid(Vec2::new(0.02, 0.01), 7);
+        assert_eq!(rays.len(), 49);
+        let projection = ProjectionBox::fit(&rays).unwrap();
+
+        assert!((projection.magnification - Vec2::splat(-0.5)).length() < 1e-4);
+        assert!(projection.image_offset.length() < 1e-7);
+        assert!(projection.max_residual(&rays) < 1e-7);
+        for ray in &rays {
+            assert!(projection.distortion_at(ray.reticle).length() < 1e-6);
+        }
+        // Chief rays converge on the pupil 0.25 m behind the image: slope -4 per meter of height
+        assert!((projection.chief_ray_gradient - Vec2::splat(-4.0)).length() < 1e-3);
+        assert!(projection.chief_ray_tilt.length() < 1e-5);
+        let edge = Vec2::new(0.02, 0.0);
+        assert!((projection.telecentricity_error(edge) - (4.0f32 * 0.01).atan()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_fit_recovers_anamorphic_distortion() {
+        let mut truth = ProjectionBox::anamorphic(-4.0, -8.0);
+        truth.image_offset = Vec2::new(1e-4, -2e-4);
+        truth.distortion = vec![
+            DistortionTerm { x_power: 0, y_power: 1, displacement: Vec2::new(1e-4, 0.0) },
+            DistortionTerm { x_power: 3, y_power: 0, displacement: Vec2::new(2e-3, 0.0) },
+            DistortionTerm { x_power: 1, y_power: 2, displacement: Vec2::new(0.0, -5e-3) },
+        ];
+        truth.chief_ray_gradient = Vec2::new(0.5, -0.25);
+        let rays: Vec<ChiefRay> = (0..64)
+            .map(|k| {
+                let reticle = Vec2::new((k % 8) as f32 / 7.0 - 0.5, (k / 8) as f32 / 7.0 - 0.5) * Vec2::new(0.104, 0.132);
+                let slope = truth.chief_ray_slope(reticle);
+                ChiefRay { reticle, wafer: truth.map(reticle), direction: slope.extend(1.0).normalize() }
+            })
+            .collect();
+
+        let fitted = ProjectionBox::fit(&rays).unwrap();
+        assert!((fitted.demagnification() - Vec2::new(4.0, 8.0)).length() < 1e-3);
+        assert!(fitted.max_residual(&rays) < 1e-8);
+        assert!((fitted.chief_ray_gradient - truth.chief_ray_gradient).length() < 1e-4);
+        let corner = Vec2::new(0.052, -0.066);
+        assert!((fitted.distortion_at(corner) - truth.distortion_at(corner)).length() < 1e-8);
+    }
+}
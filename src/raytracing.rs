@@ -1,113 +1,744 @@
+use std::collections::HashMap;
 use bevy_ecs::prelude::*;
-use glam::Vec3;
-use rand::Rng;
+use glam::{DVec3, Vec3};
+use nalgebra::Complex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
 use crate::units::Position3D;
 use crate::components::*;
-use crate::optics::MirrorSurface;
+use crate::optics::{MirrorSurface, SurfaceGeometry};
+use crate::plasma::SpectralBand;
+use crate::contamination::ContaminationState;
+use crate::debris::ChamberGeometry;
+use crate::dose::{EuvEnergySensor, RunningStatistics};
+use crate::bvh::Bvh;
+use crate::coating::MultilayerCoating;
+use crate::polarization::{JonesVector, StokesVector};
+use crate::scatter::SurfaceRoughness;
+use monitor::{FacetArray, IlluminationMonitor, IlluminationStatistics};
+use crate::reticle::{Reticle, ReticleStatistics};
 
-#[derive(Component, Debug)]
+pub mod monitor;
+
+/// Bundle of photons traced together as one ray
+///
+/// Packets are not entities: each is traced surface-to-surface to its fate
+/// within the tick it is emitted, since light crosses the chamber in nanoseconds.
+#[derive(Debug, Clone)]
 pub struct PhotonPacket {
     pub photon_count: u64,
     pub wavelength: f32,
     pub energy_per_photon: f32,
     pub bounces: u32,
+    /// Mirrors of the declared sequence this packet has reflected from in order
+    pub sequence_index: usize,
+    /// Current ray origin
+    pub position: Position3D,
+    /// Unit propagation direction
+    pub direction: Vec3,
+    /// Path length travelled since emission (m)
+    pub path_length: f32,
+    /// Field state, transverse to `direction`
+    pub polarization: JonesVector,
+    /// Reflections that scattered the packet out of the specular direction
+    pub scatter_events: u32,
 }
 
 impl PhotonPacket {
     pub const EUV_WAVELENGTH: f32 = 13.5e-9;
     pub const MAX_BOUNCES: u32 = 15;
+    pub const SPEED_OF_LIGHT: f32 = 3e8;
 
-    pub fn new(photon_count: u64) -> Self {
-        const PLANCK: f64 = 6.626e-34;
-        const LIGHT_SPEED: f64 = 3e8;
-        let energy = (PLANCK * LIGHT_SPEED / Self::EUV_WAVELENGTH as f64) as f32;
+    pub fn new(position: Position3D, direction: Vec3, photon_count: u64) -> Self {
+        Self::with_wavelength(position, direction, photon_count, Self::EUV_WAVELENGTH)
+    }
 
+    /// Packet linearly polarized as close to the world Y axis as its direction allows
+    pub fn with_wavelength(position: Position3D, direction: Vec3, photon_count: u64, wavelength: f32) -> Self {
+        let direction = direction.normalize_or_zero();
         Self {
             photon_count,
-            wavelength: Self::EUV_WAVELENGTH,
-            energy_per_photon: energy,
+            wavelength,
+            energy_per_photon: Self::photon_energy(wavelength),
             bounces: 0,
+            sequence_index: 0,
+            position,
+            direction,
+            path_length: 0.0,
+            polarization: JonesVector::linear(direction, Vec3::Y),
+            scatter_events: 0,
         }
     }
 
+    pub fn with_polarization(mut self, polarization: JonesVector) -> Self {
+        self.polarization = polarization;
+        self
+    }
+
+    /// Energy of a single photon at the given wavelength (J)
+    pub fn photon_energy(wavelength: f32) -> f32 {
+        const PLANCK: f64 = 6.626e-34;
+        const LIGHT_SPEED: f64 = 3e8;
+        (PLANCK * LIGHT_SPEED / wavelength as f64) as f32
+    }
+
     pub fn total_energy(&self) -> f32 {
         self.photon_count as f32 * self.energy_per_photon
     }
+
+    pub fn band(&self) -> SpectralBand {
+        SpectralBand::classify(self.wavelength)
+    }
+
+    /// Time since emission along the path travelled so far (s)
+    pub fn time_of_flight(&self) -> f32 {
+        self.path_length / Self::SPEED_OF_LIGHT
+    }
 }
 
-pub fn photon_mirror_interaction_system(
-    mut commands: Commands,
-    mut photons: Query<(Entity, &mut Position, &mut Velocity, &mut PhotonPacket)>,
-    mut mirrors: Query<(&Position, &MirrorSurface, &OpticalMaterial, &mut ThermalState), Without<PhotonPacket>>,
-    mut stats: ResMut<RayTracingStatistics>,
+/// Packets emitted this tick, waiting to be traced, stored as structure-of-arrays
+///
+/// Only the emission state is stored; each packet's path is traced from a
+/// working copy. Every packet gets a serial number that seeds its own random
+/// stream, so its fate does not depend on which thread traces it.
+#[derive(Resource, Debug, Default)]
+pub struct PhotonBatch {
+    pub photon_count: Vec<u64>,
+    pub wavelength: Vec<f32>,
+    pub position: Vec<Position3D>,
+    pub direction: Vec<Vec3>,
+    pub polarization: Vec<JonesVector>,
+    /// Serial number of each packet over the whole run
+    pub id: Vec<u64>,
+    next_id: u64,
+}
+
+impl PhotonBatch {
+    pub fn push(&mut self, packet: &PhotonPacket) {
+        self.photon_count.push(packet.photon_count);
+        self.wavelength.push(packet.wavelength);
+        self.position.push(packet.position);
+        self.direction.push(packet.direction);
+        self.polarization.push(packet.polarization);
+        self.id.push(self.next_id);
+        self.next_id += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.id.is_empty()
+    }
+
+    /// Serial number the next pushed packet will get
+    pub fn next_id(&self) -> u64 {
+        self.next_id
+    }
+
+    /// Fresh working copy of packet `index`
+    pub fn packet(&self, index: usize) -> PhotonPacket {
+        PhotonPacket::with_wavelength(
+            self.position[index],
+            self.direction[index],
+            self.photon_count[index],
+            self.wavelength[index],
+        )
+        .with_polarization(self.polarization[index])
+    }
+
+    /// Drops all packets; serial numbers keep counting
+    pub fn clear(&mut self) {
+        self.photon_count.clear();
+        self.wavelength.clear();
+        self.position.clear();
+        self.direction.clear();
+        self.polarization.clear();
+        self.id.clear();
+    }
+}
+
+/// How a traced packet's path ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketFate {
+    /// Absorbed by a mirror coating or its tin film
+    Absorbed,
+    /// Left the optics with no further surface ahead
+    Escaped,
+    /// Stopped after `PhotonPacket::MAX_BOUNCES` reflections
+    BounceLimit,
+}
+
+type MirrorQueryData<'a> = (
+    Entity,
+    &'a Position,
+    &'a MirrorSurface,
+    &'a OpticalMaterial,
+    &'a mut ThermalState,
+    Option<&'a ContaminationState>,
+    Option<&'a MultilayerCoating>,
+    Option<&'a SurfaceRoughness>,
+    Option<&'a FacetArray>,
+    Option<&'a Reticle>,
+);
+
+/// Read-only item of a [`MirrorQueryData`] query
+type MirrorItem<'a> = (
+    Entity,
+    &'a Position,
+    &'a MirrorSurface,
+    &'a OpticalMaterial,
+    &'a ThermalState,
+    Option<&'a ContaminationState>,
+    Option<&'a MultilayerCoating>,
+    Option<&'a SurfaceRoughness>,
+    Option<&'a FacetArray>,
+    Option<&'a Reticle>,
+);
+
+/// How a photon packet chooses the mirror it interacts with next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TracingMode {
+    /// Packets only interact with the next mirror of the declared sequence
+    /// and pass every other surface; a packet that completed the sequence travels on freely
+    #[default]
+    Sequential,
+    /// Packets interact with whichever mirror they reach first, in any order
+    /// (stray-light and ghost-path studies)
+    NonSequential,
+}
+
+impl std::str::FromStr for TracingMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "sequential" => Ok(TracingMode::Sequential),
+            "non-sequential" | "nonsequential" => Ok(TracingMode::NonSequential),
+            other => Err(format!("unknown tracing mode '{other}'")),
+        }
+    }
+}
+
+/// Ray-tracing mode and the declared mirror order from source to image
+#[derive(Resource, Debug, Clone)]
+pub struct RayTracingConfig {
+    pub mode: TracingMode,
+    /// Mirror entities in the order light is meant to visit them; with no
+    /// declared order, sequential mode falls back to nearest-hit tracing
+    pub sequence: Vec<Entity>,
+    /// Cull mirrors through the [`MirrorBvh`]; off tests every mirror for every ray
+    pub use_bvh: bool,
+    /// Seed combined with each packet's serial number for its random stream
+    pub seed: u64,
+    /// Position of the reticle in `sequence`; light arriving there is measured
+    /// by the reticle polarization detector
+    pub reticle_index: Option<usize>,
+    /// Pupil and slit monitoring of an illuminator ending at the reticle
+    pub illumination: Option<IlluminationMonitor>,
+}
+
+impl Default for RayTracingConfig {
+    fn default() -> Self {
+        Self {
+            mode: TracingMode::default(),
+            sequence: Vec::new(),
+            use_bvh: true,
+            seed: 0,
+            reticle_index: None,
+            illumination: None,
+        }
+    }
+}
+
+impl RayTracingConfig {
+    /// Mirror a packet that has completed `sequence_index` sequential reflections reaches next
+    pub fn next_in_sequence(&self, sequence_index: usize) -> Option<Entity> {
+        self.sequence.get(sequence_index).copied()
+    }
+
+    /// Random stream for the packet with the given serial number
+    fn packet_rng(&self, id: u64) -> StdRng {
+        StdRng::seed_from_u64(self.seed ^ id.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    /// Random stream for emitting the packets that start at serial number
+    /// `first_id`, separate from the packets' own tracing streams
+    pub fn emission_rng(&self, first_id: u64) -> StdRng {
+        StdRng::seed_from_u64(!self.seed ^ first_id.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    /// Whether a packet may interact with `mirror` at this point of its path
+    fn accepts(&self, sequence_index: usize, mirror: Entity) -> bool {
+        match self.mode {
+            TracingMode::Sequential if !self.sequence.is_empty() => {
+                self.next_in_sequence(sequence_index) == Some(mirror)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Distance a reflected ray is started off the surface so it does not re-hit the same point (m)
+const SURFACE_OFFSET: f32 = 1e-5;
+
+/// Borrowed view of one mirror for the duration of a tracing pass
+struct MirrorView<'a> {
+    entity: Entity,
+    position: Position3D,
+    surface: &'a MirrorSurface,
+    material: &'a OpticalMaterial,
+    contamination: Option<&'a ContaminationState>,
+    coating: Option<&'a MultilayerCoating>,
+    roughness: Option<&'a SurfaceRoughness>,
+    facets: Option<&'a FacetArray>,
+    reticle: Option<&'a Reticle>,
+}
+
+impl<'a> MirrorView<'a> {
+    fn new((entity, pos, surface, material, _, contamination, coating, roughness, facets, reticle): MirrorItem<'a>) -> Self {
+        Self {
+            entity,
+            position: pos.0,
+            surface,
+            material,
+            contamination,
+            coating,
+            roughness,
+            facets,
+            reticle,
+        }
+    }
+
+    /// Whether a surface point can reflect: inside the clear aperture and, on
+    /// a facet mirror, on a facet
+    fn accepts_point(&self, point: Position3D) -> bool {
+        self.surface.in_aperture(self.position, point)
+            && self.facets.is_none_or(|facets| {
+                facets.facet_at(self.surface.aperture_coordinates(self.position, point)).is_some()
+            })
+    }
+
+    /// Surface normal at a point, tilted by the facet hit on a facet mirror
+    fn normal_at(&self, point: Position3D) -> Vec3 {
+        self.facets
+            .and_then(|facets| facets.normal_at(self.surface, self.position, point))
+            .unwrap_or_else(|| self.surface.geometry.normal_at(point))
+    }
+
+    /// Whether a point on a reticle lies under its absorber, or `None` off a reticle
+    fn on_absorber(&self, point: Position3D) -> Option<bool> {
+        self.reticle
+            .map(|reticle| reticle.absorbs(self.surface.aperture_coordinates(self.position, point)))
+    }
+
+    /// Clean-surface response at the given angle of incidence
+    ///
+    /// A multilayer coating (or a reticle's blank, under its absorber where
+    /// `on_absorber`) applies inside its tabulated band, where everything it
+    /// does not reflect is absorbed; elsewhere the bulk material model
+    /// reflects both polarizations alike.
+    fn response(&self, wavelength: f32, incidence: f32, on_absorber: bool) -> SurfaceResponse {
+        let coefficients = match self.reticle {
+            Some(reticle) => reticle.coefficients(on_absorber, wavelength, incidence),
+            None => self.coating.and_then(|c| c.coefficients(wavelength, incidence)),
+        };
+        match coefficients {
+            Some((rs, rp)) => SurfaceResponse { rs, rp, absorption: None },
+            None => {
+                let r = Complex::new(self.material.reflectivity_at(wavelength).sqrt(), 0.0);
+                SurfaceResponse { rs: r, rp: r, absorption: Some(self.material.absorption_at(wavelength)) }
+            }
+        }
+    }
+}
+
+/// Amplitude reflection coefficients of a clean surface for one hit
+struct SurfaceResponse {
+    rs: Complex<f32>,
+    rp: Complex<f32>,
+    /// Absorbed fraction, or `None` when everything not reflected is absorbed
+    absorption: Option<f32>,
+}
+
+/// What a non-reflecting surface in the scene is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObstacleKind {
+    /// Chamber wall; packets reaching it have left the optics
+    ChamberWall,
+    /// EUV energy sensor head
+    Sensor,
+}
+
+/// Non-reflecting surface that ends a packet's path
+#[derive(Debug, Clone)]
+pub struct Obstacle {
+    pub kind: ObstacleKind,
+    pub geometry: SurfaceGeometry,
+}
+
+/// Hierarchy over the bounds of every surface a packet can reach: the
+/// mirrors, then the chamber wall and sensors. Rebuilt when mirrors are added,
+/// removed, moved or reshaped (e.g. stage-mounted optics), or when the
+/// chamber or sensor changes
+#[derive(Resource, Debug, Default)]
+pub struct MirrorBvh {
+    pub bvh: Bvh,
+    /// Mirror entity for each of the first BVH primitive indices
+    pub entities: Vec<Entity>,
+    /// Obstacles for the BVH primitive indices after the mirrors
+    pub obstacles: Vec<Obstacle>,
+    /// Times the hierarchy has been rebuilt
+    pub rebuilds: u64,
+}
+
+/// Mirrors moved or reshaped since the last run
+type ChangedMirror = (With<MirrorSurface>, Or<(Changed<Position>, Changed<MirrorSurface>)>);
+
+/// System that rebuilds the BVH when any mirror, the chamber or the sensor changed
+pub fn mirror_bvh_system(
+    mut mirror_bvh: ResMut<MirrorBvh>,
+    mirrors: Query<(Entity, &MirrorSurface)>,
+    changed: Query<(), ChangedMirror>,
+    mut removed: RemovedComponents<MirrorSurface>,
+    chamber: Option<Res<ChamberGeometry>>,
+    sensor: Option<Res<EuvEnergySensor>>,
 ) {
-    if mirrors.is_empty() {
+    let removed = removed.read().count() > 0;
+    let obstacles_changed = chamber.as_ref().is_some_and(|c| c.is_changed()) || sensor.as_ref().is_some_and(|s| s.is_changed());
+    if changed.is_empty() && !removed && !obstacles_changed {
         return;
     }
+    let (entities, mut bounds): (Vec<Entity>, Vec<_>) = mirrors
+        .iter()
+        .map(|(entity, surface)| (entity, surface.geometry.bounds()))
+        .unzip();
+    let wall = chamber.map(|chamber| Obstacle {
+        kind: ObstacleKind::ChamberWall,
+        geometry: SurfaceGeometry::Spherical { radius: chamber.wall_radius, center: chamber.center },
+    });
+    let sensor = sensor.map(|sensor| Obstacle {
+        kind: ObstacleKind::Sensor,
+        geometry: SurfaceGeometry::Spherical { radius: sensor.radius, center: sensor.position },
+    });
+    let obstacles: Vec<Obstacle> = wall.into_iter().chain(sensor).collect();
+    bounds.extend(obstacles.iter().map(|obstacle| obstacle.geometry.bounds()));
+    mirror_bvh.bvh = Bvh::build(&bounds);
+    mirror_bvh.entities = entities;
+    mirror_bvh.obstacles = obstacles;
+    mirror_bvh.rebuilds += 1;
+}
+
+/// Surfaces a packet can reach
+struct Scene<'a> {
+    views: &'a [MirrorView<'a>],
+    obstacles: &'a [Obstacle],
+    /// Hierarchy with primitive indices matching `views`, then `obstacles`
+    bvh: Option<&'a Bvh>,
+}
 
-    let (_, mirror_surface, mirror_material, _) = mirrors.single();
-    
-    let (mirror_center, mirror_radius) = match &mirror_surface.geometry {
-        crate::optics::SurfaceGeometry::Spherical { radius, center } => {
-            (*center, radius.as_meters_f64() as f32)
+/// Nearest crossing of the ray within `max_distance` among the accepted mirrors
+/// (inside their clear apertures) and the obstacles
+///
+/// Indices past the views are obstacles. Crossings outside a clear aperture
+/// (or between facets) that lie before the accepted hit are returned in
+/// `vignetted`; the ray passes those mirrors by. With a BVH only surfaces
+/// whose bounds the ray enters are tested; the result is the same.
+fn nearest_hit(
+    scene: &Scene,
+    accepts: impl Fn(Entity) -> bool,
+    origin: Position3D,
+    direction: Vec3,
+    max_distance: f32,
+    vignetted: &mut Vec<(usize, f32)>,
+) -> Option<(usize, Position3D, f32)> {
+    vignetted.clear();
+    let mut best: Option<(usize, Position3D, f32)> = None;
+    let mut test = |index: usize| -> f32 {
+        let (geometry, view) = match scene.views.get(index) {
+            Some(view) => (&view.surface.geometry, Some(view)),
+            None => (&scene.obstacles[index - scene.views.len()].geometry, None),
+        };
+        if view.is_none_or(|view| accepts(view.entity)) {
+            if let (true, Some(point), t) = geometry.ray_intersection(origin, direction) {
+                if t <= max_distance {
+                    if view.is_some_and(|view| !view.accepts_point(point)) {
+                        vignetted.push((index, t));
+                    } else if best.as_ref().is_none_or(|&(_, _, best_t)| t < best_t) {
+                        best = Some((index, point, t));
+                    }
+                }
+            }
         }
-        _ => return,
+        best.as_ref().map_or(max_distance, |&(_, _, t)| t)
     };
+    match scene.bvh {
+        Some(bvh) => bvh.traverse(origin.to_vec3(), direction, max_distance, test),
+        None => {
+            for index in 0..scene.views.len() + scene.obstacles.len() {
+                test(index);
+            }
+        }
+    }
+    if let Some((_, _, best_t)) = best {
+        vignetted.retain(|&(_, t)| t < best_t);
+    }
+    best
+}
+
+/// Packets traced per parallel work item; tallies are merged in chunk order
+const TRACE_CHUNK: usize = 256;
+
+/// Results of tracing one chunk of a batch; mirror entries are indexed like the views
+#[derive(Debug, Default)]
+struct TraceTally {
+    /// Totals only; `per_mirror` stays empty
+    stats: RayTracingStatistics,
+    mirrors: Vec<MirrorStatistics>,
+    heat: Vec<f32>,
+}
+
+impl TraceTally {
+    fn new(mirror_count: usize) -> Self {
+        Self {
+            stats: RayTracingStatistics::default(),
+            mirrors: vec![MirrorStatistics::default(); mirror_count],
+            heat: vec![0.0; mirror_count],
+        }
+    }
+}
 
-    let mut absorbed_heat = 0.0f32;
-    let mut to_despawn = Vec::new();
-    let mirror_center_vec = mirror_center.to_vec3();
-
-    for (photon_entity, mut photon_pos, mut photon_vel, mut packet) in photons.iter_mut() {
-        if packet.bounces >= PhotonPacket::MAX_BOUNCES {
-            to_despawn.push(photon_entity);
-            continue;
-        }
-
-        let photon_vec = photon_pos.0.to_vec3();
-        let distance = photon_vec.distance(mirror_center_vec);
-        
-        if distance <= mirror_radius {
-            let mut rng = rand::thread_rng();
-            let reflects = rng.gen::<f32>() < mirror_material.reflectivity;
-
-            if reflects {
-                let ray_direction = photon_vel.0.normalize();
-                let normal = mirror_surface.geometry.normal_at(photon_pos.0);
-                let reflected = ray_direction - 2.0 * ray_direction.dot(normal) * normal;
-                
-                photon_vel.0 = reflected.normalize() * photon_vel.0.length();
-                packet.bounces += 1;
-                stats.total_reflections += 1;
+/// Traces one packet surface-to-surface until it is absorbed, escapes or hits the bounce limit
+fn trace_packet(
+    packet: &mut PhotonPacket,
+    scene: &Scene,
+    config: &RayTracingConfig,
+    tally: &mut TraceTally,
+    rng: &mut impl Rng,
+) -> PacketFate {
+    let stats = &mut tally.stats;
+    // Packets launched behind the collector start at the intermediate focus
+    if packet.bounces == 0
+        && config.illumination.as_ref().is_some_and(|m| m.intermediate_focus_index == packet.sequence_index)
+    {
+        stats.illumination.intermediate_focus_energy += packet.total_energy();
+    }
+    let mut vignetted = Vec::new();
+    while packet.bounces < PhotonPacket::MAX_BOUNCES {
+        let sequence_index = packet.sequence_index;
+        let accepts = |mirror| config.accepts(sequence_index, mirror);
+        let hit = nearest_hit(scene, accepts, packet.position, packet.direction, f32::INFINITY, &mut vignetted);
+        for &(index, _) in vignetted.iter() {
+            let mirror_stats = &mut tally.mirrors[index];
+            mirror_stats.vignetted += 1;
+            mirror_stats.vignetted_energy += packet.total_energy();
+        }
+        let Some((index, point, travelled)) = hit else {
+            return PacketFate::Escaped;
+        };
+        packet.path_length += travelled;
+        let Some(view) = scene.views.get(index) else {
+            let energy = packet.total_energy();
+            return match scene.obstacles[index - scene.views.len()].kind {
+                ObstacleKind::ChamberWall => {
+                    stats.wall_energy += energy;
+                    PacketFate::Escaped
+                }
+                ObstacleKind::Sensor => {
+                    stats.sensor_packets += 1;
+                    stats.sensor_energy += energy;
+                    PacketFate::Absorbed
+                }
+            };
+        };
+        let mirror_stats = &mut tally.mirrors[index];
+
+        let in_sequence = config.next_in_sequence(sequence_index) == Some(view.entity);
+        if !in_sequence && !config.sequence.is_empty() {
+            mirror_stats.out_of_sequence += 1;
+        }
+        if in_sequence && config.reticle_index == Some(sequence_index) {
+            let weight = packet.total_energy() as f64;
+            stats.reticle_polarization.add(&StokesVector::from_field(&packet.polarization, packet.direction, weight));
+            if let Some(monitor) = &config.illumination {
+                stats.illumination.record_reticle(monitor, point.to_vec3(), packet.direction, packet.total_energy());
+            }
+        }
+
+        let normal = view.normal_at(point);
+        let incidence = packet.direction.dot(normal).abs().min(1.0).acos();
+        let direction = packet.direction;
+        let reflected = (direction - 2.0 * direction.dot(normal) * normal).normalize();
+        let on_absorber = view.on_absorber(point);
+        let in_band = packet.band().is_in_band();
+        if let Some(on_absorber) = on_absorber.filter(|_| in_band) {
+            stats.reticle.record_incident(on_absorber, packet.total_energy());
+        }
+        let response = view.response(packet.wavelength, incidence, on_absorber == Some(true));
+        let (polarization, clean_reflectivity) =
+            packet.polarization.reflect(direction, reflected, normal, response.rs, response.rp);
+        let clean_absorption = response.absorption.unwrap_or(1.0 - clean_reflectivity);
+        let film_factor = view.contamination.map_or(1.0, |c| c.reflectivity_factor_at(packet.wavelength));
+        if rng.gen::<f32>() >= clean_reflectivity * film_factor {
+            // Light lost in the tin film is absorbed along with the coating's own
+            // absorption. The packet stands for all the light not reflected, so it
+            // deposits its whole energy unless the bulk material transmits some.
+            let absorption = clean_absorption + clean_reflectivity * (1.0 - film_factor);
+            let lost = 1.0 - clean_reflectivity * film_factor;
+            let absorbed = packet.total_energy() * (absorption / lost).min(1.0);
+            tally.heat[index] += absorbed;
+            if let Some(on_absorber) = on_absorber {
+                stats.reticle.record_heat(on_absorber, absorbed);
+            }
+            if in_band {
+                stats.in_band_heat += absorbed;
             } else {
-                absorbed_heat += packet.total_energy() * mirror_material.absorption;
-                stats.total_absorptions += 1;
-                to_despawn.push(photon_entity);
+                stats.out_of_band_heat += absorbed;
             }
+            stats.total_absorptions += 1;
+            mirror_stats.absorptions += 1;
+            return PacketFate::Absorbed;
         }
-    }
 
-    if absorbed_heat > 0.0 {
-        let (_, _, _, mut thermal) = mirrors.single_mut();
-        thermal.add_heat(absorbed_heat);
+        // Roughness scatters part of the reflected light out of the specular direction
+        let scattered = view
+            .roughness
+            .filter(|roughness| rng.gen::<f32>() < roughness.total_integrated_scatter(packet.wavelength, incidence))
+            .and_then(|roughness| roughness.scatter_direction(reflected, normal, packet.wavelength, rng));
+        match scattered {
+            Some(direction) => {
+                packet.direction = direction;
+                packet.polarization = polarization.transverse_to(direction);
+                packet.scatter_events += 1;
+                stats.total_scattered += 1;
+                mirror_stats.scattered += 1;
+            }
+            None => {
+                packet.direction = reflected;
+                packet.polarization = polarization;
+            }
+        }
+        if let Some(on_absorber) = on_absorber.filter(|_| in_band) {
+            stats.reticle.record_reflected(on_absorber, packet.total_energy());
+        }
+        packet.position = Position3D::from_vec3(point.to_vec3() + packet.direction * SURFACE_OFFSET);
+        packet.path_length += SURFACE_OFFSET;
+        packet.bounces += 1;
+        if in_sequence {
+            packet.sequence_index += 1;
+            if config.illumination.as_ref().is_some_and(|m| m.intermediate_focus_index == packet.sequence_index) {
+                stats.illumination.intermediate_focus_energy += packet.total_energy();
+            }
+        }
+        stats.total_reflections += 1;
+        mirror_stats.reflections += 1;
     }
+    PacketFate::BounceLimit
+}
 
-    for entity in to_despawn {
-        commands.entity(entity).despawn();
+/// End of a ray traced by [`trace_specular`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpecularPath {
+    /// Last mirror hit, or the origin if there were no mirrors
+    pub point: DVec3,
+    /// Unit direction leaving the last mirror
+    pub direction: DVec3,
+    /// Geometric path length from the origin (m)
+    pub path_length: f64,
+}
+
+/// Follows one ray by ideal specular reflection off each mirror in turn, in
+/// double precision, for image analysis
+///
+/// Unlike packet tracing there is no absorption, scatter or random choice.
+/// Returns `None` if the ray misses a mirror or lands outside its clear aperture.
+pub fn trace_specular(mirrors: &[(Position3D, MirrorSurface)], origin: DVec3, direction: DVec3) -> Option<SpecularPath> {
+    let mut point = origin;
+    let mut direction = direction.normalize();
+    let mut path_length = 0.0;
+    for (position, surface) in mirrors {
+        let t = surface.geometry.ray_intersection_f64(point, direction)?;
+        point += direction * t;
+        path_length += t;
+        if !surface.in_aperture(*position, Position3D::from_dvec3(point)) {
+            return None;
+        }
+        let normal = surface.geometry.normal_at_f64(point);
+        direction = (direction - 2.0 * direction.dot(normal) * normal).normalize();
     }
+    Some(SpecularPath { point, direction, path_length })
 }
 
-pub fn photon_cleanup_system(
-    mut commands: Commands,
-    query: Query<(Entity, &Position, &PhotonPacket)>,
+/// Traces every packet emitted this tick to its fate against all mirrors
+///
+/// Chunks of the batch are traced in parallel; their tallies are merged in
+/// chunk order, so heat and statistics do not depend on the thread count.
+pub fn ray_transport_system(
+    mut batch: ResMut<PhotonBatch>,
+    mut mirrors: Query<MirrorQueryData>,
+    mut stats: ResMut<RayTracingStatistics>,
+    config: Res<RayTracingConfig>,
+    mirror_bvh: Res<MirrorBvh>,
 ) {
-    const MAX_DISTANCE: f32 = 20.0;
+    if batch.is_empty() {
+        return;
+    }
+
+    let stats = &mut *stats;
+    let absorbed_heat: Vec<(Entity, f32)> = {
+        // Views follow the BVH's primitive order while it covers every mirror
+        let bvh_views: Option<Vec<MirrorView>> = if config.use_bvh {
+            mirror_bvh.entities.iter().map(|&entity| mirrors.get(entity).ok().map(MirrorView::new)).collect()
+        } else {
+            None
+        };
+        let bvh = bvh_views
+            .as_ref()
+            .filter(|views| views.len() == mirrors.iter().len())
+            .map(|_| &mirror_bvh.bvh);
+        let views = match (bvh, bvh_views) {
+            (Some(_), Some(views)) => views,
+            _ => mirrors.iter().map(MirrorView::new).collect(),
+        };
+
+        let scene = Scene { views: &views, obstacles: &mirror_bvh.obstacles, bvh };
+        let batch = &*batch;
+        let tallies: Vec<TraceTally> = (0..batch.len().div_ceil(TRACE_CHUNK))
+            .into_par_iter()
+            .map(|chunk| {
+                let mut tally = TraceTally::new(views.len());
+                let end = ((chunk + 1) * TRACE_CHUNK).min(batch.len());
+                for index in chunk * TRACE_CHUNK..end {
+                    let mut packet = batch.packet(index);
+                    let mut rng = config.packet_rng(batch.id[index]);
+                    let fate = trace_packet(&mut packet, &scene, &config, &mut tally, &mut rng);
+                    tally.stats.record_fate(&packet, fate, config.sequence.len());
+                }
+                tally
+            })
+            .collect();
+
+        let mut heat = vec![0.0f32; views.len()];
+        for tally in tallies {
+            stats.merge_totals(&tally.stats);
+            for (index, mirror) in tally.mirrors.iter().enumerate() {
+                if mirror.arrivals() > 0 {
+                    stats.per_mirror.entry(views[index].entity).or_default().merge(mirror);
+                }
+                heat[index] += tally.heat[index];
+            }
+        }
+        views.iter().zip(heat).map(|(view, heat)| (view.entity, heat)).collect()
+    };
+    batch.clear();
 
-    for (entity, pos, packet) in query.iter() {
-        let distance = pos.0.to_vec3().length();
-        
-        if distance > MAX_DISTANCE || packet.bounces >= PhotonPacket::MAX_BOUNCES {
-            commands.entity(entity).despawn();
+    for (entity, heat) in absorbed_heat {
+        if heat > 0.0 {
+            if let Ok((_, _, _, _, mut thermal, ..)) = mirrors.get_mut(entity) {
+                thermal.add_heat(heat);
+            }
         }
     }
 }
@@ -116,18 +747,475 @@ pub fn photon_cleanup_system(
 pub struct RayTracingStatistics {
     pub total_reflections: u64,
     pub total_absorptions: u64,
-    pub active_photon_packets: u32,
-    pub average_bounces: f32,
+    /// Reflections scattered out of the specular direction by surface roughness
+    pub total_scattered: u64,
+    /// Packets traced to their fate
+    pub traced_packets: u64,
+    /// Packets that left the optics without being absorbed
+    pub escaped_packets: u64,
+    /// Packets stopped by the bounce limit
+    pub bounce_limited_packets: u64,
+    /// Reflections summed over all traced packets
+    pub total_bounces: u64,
+    /// Escaped packets that completed the declared mirror sequence
+    pub delivered_packets: u64,
+    /// Energy carried by delivered packets (J)
+    pub delivered_energy: f32,
+    /// Delivered energy carried by packets scattered at least once (J), i.e. flare after the last mirror
+    pub flare_energy: f32,
+    /// Time of flight from the plasma to the last mirror of the sequence for delivered packets (ns)
+    pub delivered_time_of_flight: RunningStatistics,
+    /// Energy-weighted Stokes parameters of light arriving at the reticle
+    /// (the mirror at `reticle_index` in the sequence)
+    pub reticle_polarization: StokesVector,
+    /// Energy-weighted Stokes parameters of delivered light, leaving the last
+    /// mirror of the sequence
+    pub delivered_polarization: StokesVector,
+    /// Heat deposited in mirrors by in-band light (J)
+    pub in_band_heat: f32,
+    /// Heat deposited in mirrors by out-of-band light (J)
+    pub out_of_band_heat: f32,
+    /// Energy of escaped packets stopped by the chamber wall (J)
+    pub wall_energy: f32,
+    /// Packets absorbed by the EUV energy sensor
+    pub sensor_packets: u64,
+    /// Energy absorbed by the EUV energy sensor (J)
+    pub sensor_energy: f32,
+    /// Illuminator throughput, pupil map and slit profile, when monitored
+    pub illumination: IlluminationStatistics,
+    /// Light reaching reticles, split by absorber and bare blank
+    pub reticle: ReticleStatistics,
+    /// Interaction counts for each mirror entity
+    pub per_mirror: HashMap<Entity, MirrorStatistics>,
 }
 
-pub fn raytracing_statistics_system(
-    photons: Query<&PhotonPacket>,
-    mut stats: ResMut<RayTracingStatistics>,
-) {
-    stats.active_photon_packets = photons.iter().count() as u32;
-    
-    if stats.active_photon_packets > 0 {
-        let total_bounces: u32 = photons.iter().map(|p| p.bounces).sum();
-        stats.average_bounces = total_bounces as f32 / stats.active_photon_packets as f32;
+impl RayTracingStatistics {
+    fn record_fate(&mut self, packet: &PhotonPacket, fate: PacketFate, sequence_length: usize) {
+        self.traced_packets += 1;
+        self.total_bounces += packet.bounces as u64;
+        match fate {
+            PacketFate::Absorbed => {}
+            PacketFate::BounceLimit => self.bounce_limited_packets += 1,
+            PacketFate::Escaped => {
+                self.escaped_packets += 1;
+                if sequence_length > 0 && packet.sequence_index == sequence_length {
+                    self.delivered_packets += 1;
+                    self.delivered_energy += packet.total_energy();
+                    if packet.scatter_events > 0 {
+                        self.flare_energy += packet.total_energy();
+                    }
+                    self.delivered_time_of_flight.push(packet.time_of_flight() as f64 * 1e9);
+                    let weight = packet.total_energy() as f64;
+                    self.delivered_polarization.add(&StokesVector::from_field(&packet.polarization, packet.direction, weight));
+                }
+            }
+        }
+    }
+
+    /// Adds another tally's totals (everything but `per_mirror`)
+    fn merge_totals(&mut self, other: &RayTracingStatistics) {
+        self.total_reflections += other.total_reflections;
+        self.total_absorptions += other.total_absorptions;
+        self.total_scattered += other.total_scattered;
+        self.traced_packets += other.traced_packets;
+        self.escaped_packets += other.escaped_packets;
+        self.bounce_limited_packets += other.bounce_limited_packets;
+        self.total_bounces += other.total_bounces;
+        self.delivered_packets += other.delivered_packets;
+        self.delivered_energy += other.delivered_energy;
+        self.flare_energy += other.flare_energy;
+        self.delivered_time_of_flight.merge(&other.delivered_time_of_flight);
+        self.reticle_polarization.add(&other.reticle_polarization);
+        self.delivered_polarization.add(&other.delivered_polarization);
+        self.in_band_heat += other.in_band_heat;
+        self.out_of_band_heat += other.out_of_band_heat;
+        self.wall_energy += other.wall_energy;
+        self.sensor_packets += other.sensor_packets;
+        self.sensor_energy += other.sensor_energy;
+        self.illumination.merge(&other.illumination);
+        self.reticle.merge(&other.reticle);
+    }
+
+    /// Fraction of delivered energy that was scattered on the way (flare)
+    pub fn flare_fraction(&self) -> f32 {
+        if self.delivered_energy > 0.0 {
+            self.flare_energy / self.delivered_energy
+        } else {
+            0.0
+        }
+    }
+
+    /// Mean reflections per traced packet
+    pub fn average_bounces(&self) -> f32 {
+        if self.traced_packets > 0 {
+            self.total_bounces as f32 / self.traced_packets as f32
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Photon interactions with a single mirror
+#[derive(Debug, Default, Clone)]
+pub struct MirrorStatistics {
+    pub reflections: u64,
+    pub absorptions: u64,
+    /// Reflections scattered out of the specular direction (counted in `reflections` too)
+    pub scattered: u64,
+    /// Packets that reached the surface outside its clear aperture
+    pub vignetted: u64,
+    /// Energy carried by vignetted packets (J)
+    pub vignetted_energy: f32,
+    /// Interactions that did not follow the declared mirror order (stray light)
+    pub out_of_sequence: u64,
+}
+
+impl MirrorStatistics {
+    /// Packets that reached the surface, inside or outside the aperture
+    pub fn arrivals(&self) -> u64 {
+        self.reflections + self.absorptions + self.vignetted
+    }
+
+    fn merge(&mut self, other: &MirrorStatistics) {
+        self.reflections += other.reflections;
+        self.absorptions += other.absorptions;
+        self.scattered += other.scattered;
+        self.vignetted += other.vignetted;
+        self.vignetted_energy += other.vignetted_energy;
+        self.out_of_sequence += other.out_of_sequence;
+    }
+
+    /// Fraction of packets reaching the surface that were lost outside the aperture
+    pub fn vignetting_fraction(&self) -> f64 {
+        let arrivals = self.arrivals();
+        if arrivals > 0 {
+            self.vignetted as f64 / arrivals as f64
+        } else {
+            0.0
+        }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::system::RunSystemOnce;
+    use glam::Quat;
+    use crate::coating::MultilayerStack;
+    use crate::optics::{spawn_optical_system, Aperture, OpticalSystemConfig, SagProfile, SurfaceGeometry};
+    use crate::units::Distance;
+
+    const PERFECT_MIRROR: OpticalMaterial = OpticalMaterial {
+        reflectivity: 1.0,
+        absorption: 0.0,
+        ..OpticalMaterial::BRAGG_MIRROR
+    };
+
+    fn on_axis(z: i128) -> Position3D {
+        Position3D::new(Distance::ZERO, Distance::ZERO, Distance::from_meters(z))
+    }
+
+    /// Concave unit-radius mirror with its vertex at `z` on the axis, facing -z
+    fn spawn_mirror(world: &mut World, z: i128, aperture: Aperture) -> Entity {
+        world.spawn((
+            Position(on_axis(z)),
+            MirrorSurface {
+                geometry: SurfaceGeometry::Spherical {
+                    radius: Distance::from_meters(1),
+                    center: on_axis(z + 1),
+                },
+                orientation: Quat::IDENTITY,
+                aperture,
+            },
+            PERFECT_MIRROR,
+            ThermalState::new(293.15, 1000.0),
+        )).id()
+    }
+
+    /// Mirrors at z = 1 m and z = 2 m and one packet leaving the origin along +z;
+    /// only the far mirror is declared in the sequence
+    fn trace_one(mode: TracingMode, near_aperture: Aperture) -> (World, Entity, Entity) {
+        let mut world = World::new();
+        let near = spawn_mirror(&mut world, 1, near_aperture);
+        let far = spawn_mirror(&mut world, 2, Aperture::Circular { radius: 0.5 });
+        world.insert_resource(RayTracingStatistics::default());
+        world.insert_resource(RayTracingConfig { mode, sequence: vec![far], ..Default::default() });
+        world.insert_resource(MirrorBvh::default());
+        world.run_system_once(mirror_bvh_system).unwrap();
+        let mut batch = PhotonBatch::default();
+        batch.push(&PhotonPacket::new(Position3D::zero(), Vec3::Z, 1000));
+        world.insert_resource(batch);
+        world.run_system_once(ray_transport_system).unwrap();
+        assert!(world.resource::<PhotonBatch>().is_empty());
+        (world, near, far)
+    }
+
+    #[test]
+    fn test_non_sequential_takes_nearest_hit() {
+        let (world, near, far) = trace_one(TracingMode::NonSequential, Aperture::Circular { radius: 0.5 });
+        let stats = world.resource::<RayTracingStatistics>();
+        assert_eq!(stats.per_mirror[&near].reflections, 1);
+        // Out of the declared order, so it counts as stray light
+        assert_eq!(stats.per_mirror[&near].out_of_sequence, 1);
+        assert!(!stats.per_mirror.contains_key(&far));
+        assert_eq!(stats.escaped_packets, 1);
+        assert_eq!(stats.delivered_packets, 0);
+    }
+
+    #[test]
+    fn test_sequential_skips_undeclared_mirrors() {
+        let (world, near, far) = trace_one(TracingMode::Sequential, Aperture::Circular { radius: 0.5 });
+        let stats = world.resource::<RayTracingStatistics>();
+        assert!(!stats.per_mirror.contains_key(&near));
+        assert_eq!(stats.per_mirror[&far].reflections, 1);
+        assert_eq!(stats.delivered_packets, 1);
+        // 2 m from the origin to the far mirror
+        let time_of_flight = stats.delivered_time_of_flight.mean();
+        assert!((time_of_flight - 2.0 / 0.3).abs() < 1e-3);
+        assert!((stats.average_bounces() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_vignetted_crossing_passes_to_next_mirror() {
+        let hole = Aperture::Annular { inner_radius: 0.05, outer_radius: 0.5 };
+        let (world, near, far) = trace_one(TracingMode::NonSequential, hole);
+        let stats = world.resource::<RayTracingStatistics>();
+        // Passes the hole on the way out and again after reflecting from the far mirror
+        assert_eq!(stats.per_mirror[&near].vignetted, 2);
+        assert_eq!(stats.per_mirror[&near].reflections, 0);
+        assert_eq!(stats.per_mirror[&far].reflections, 1);
+        assert_eq!(stats.delivered_packets, 1);
+    }
+
+    #[test]
+    fn test_rough_mirror_scatters_tis_fraction() {
+        let mut world = World::new();
+        let mirror = spawn_mirror(&mut world, 2, Aperture::Circular { radius: 0.5 });
+        let roughness = SurfaceRoughness::gaussian(0.5e-9, 1e-6);
+        world.entity_mut(mirror).insert(roughness);
+        world.insert_resource(RayTracingStatistics::default());
+        world.insert_resource(RayTracingConfig { sequence: vec![mirror], ..Default::default() });
+        world.insert_resource(MirrorBvh::default());
+        world.run_system_once(mirror_bvh_system).unwrap();
+        let mut batch = PhotonBatch::default();
+        for _ in 0..20_000 {
+            batch.push(&PhotonPacket::new(Position3D::zero(), Vec3::Z, 1000));
+        }
+        world.insert_resource(batch);
+        world.run_system_once(ray_transport_system).unwrap();
+
+        let stats = world.resource::<RayTracingStatistics>();
+        assert_eq!(stats.delivered_packets, 20_000);
+        assert_eq!(stats.per_mirror[&mirror].scattered, stats.total_scattered);
+        let tis = roughness.total_integrated_scatter(PhotonPacket::EUV_WAVELENGTH, 0.0);
+        assert!((stats.flare_fraction() - tis).abs() < 0.015, "{} vs {tis}", stats.flare_fraction());
+    }
+
+    #[test]
+    fn test_wall_and_sensor_stop_packets() {
+        for use_bvh in [true, false] {
+            let mut world = World::new();
+            let mirror = spawn_mirror(&mut world, 2, Aperture::Circular { radius: 0.5 });
+            world.insert_resource(ChamberGeometry::default());
+            world.insert_resource(EuvEnergySensor {
+                position: on_axis(-1),
+                radius: Distance::from_millimeters(100),
+                ..Default::default()
+            });
+            world.insert_resource(RayTracingStatistics::default());
+            world.insert_resource(RayTracingConfig { sequence: vec![mirror], use_bvh, ..Default::default() });
+            world.insert_resource(MirrorBvh::default());
+            world.run_system_once(mirror_bvh_system).unwrap();
+            assert_eq!(world.resource::<MirrorBvh>().bvh.len(), 3);
+
+            // One packet retro-reflects onto the sensor behind the origin, the other runs into the wall
+            let mut batch = PhotonBatch::default();
+            let packet = PhotonPacket::new(Position3D::zero(), Vec3::Z, 1000);
+            batch.push(&packet);
+            batch.push(&PhotonPacket::new(Position3D::zero(), -Vec3::X, 1000));
+            world.insert_resource(batch);
+            world.run_system_once(ray_transport_system).unwrap();
+
+            let stats = world.resource::<RayTracingStatistics>();
+            assert_eq!(stats.per_mirror[&mirror].reflections, 1);
+            assert_eq!(stats.sensor_packets, 1);
+            assert_eq!(stats.escaped_packets, 1);
+            assert_eq!(stats.delivered_packets, 0);
+            assert_eq!(stats.wall_energy, packet.total_energy());
+            assert_eq!(stats.sensor_energy, packet.total_energy());
+        }
+    }
+
+    /// Fraction of 4000 packets a Mo/Si-coated flat at z = 1 m reflects, and
+    /// the coating's tabulated reflectance, at one wavelength and angle of incidence
+    fn coated_reflection(coating: &MultilayerCoating, wavelength: f32, incidence_deg: f32) -> (f32, f32) {
+        let mut world = World::new();
+        let expected = coating.reflectance(wavelength, incidence_deg.to_radians()).unwrap();
+        let mirror = world.spawn((
+            Position(on_axis(1)),
+            MirrorSurface {
+                geometry: SurfaceGeometry::Sag {
+                    vertex: on_axis(1),
+                    orientation: Quat::IDENTITY,
+                    profile: SagProfile::conic(f64::INFINITY, 0.0, 1.0),
+                },
+                orientation: Quat::IDENTITY,
+                aperture: Aperture::Circular { radius: 1.0 },
+            },
+            PERFECT_MIRROR,
+            coating.clone(),
+            ThermalState::new(293.15, 1000.0),
+        )).id();
+        world.insert_resource(RayTracingStatistics::default());
+        world.insert_resource(RayTracingConfig { sequence: vec![mirror], ..Default::default() });
+        world.insert_resource(MirrorBvh::default());
+        world.run_system_once(mirror_bvh_system).unwrap();
+
+        // Launched 0.5 m from the vertex along the incoming ray
+        let direction = Vec3::new(incidence_deg.to_radians().sin(), 0.0, incidence_deg.to_radians().cos());
+        let origin = Position3D::from_vec3(Vec3::Z - direction * 0.5);
+        let mut batch = PhotonBatch::default();
+        for _ in 0..4000 {
+            batch.push(&PhotonPacket::with_wavelength(origin, direction, 1000, wavelength));
+        }
+        world.insert_resource(batch);
+        world.run_system_once(ray_transport_system).unwrap();
+
+        let stats = world.resource::<RayTracingStatistics>();
+        assert_eq!(stats.per_mirror[&mirror].arrivals(), 4000);
+        (stats.total_reflections as f32 / 4000.0, expected)
+    }
+
+    #[test]
+    fn test_coated_mirror_reflectivity_follows_angle_and_wavelength() {
+        let coating = MultilayerCoating::new(MultilayerStack::mo_si());
+        let (normal, normal_expected) = coated_reflection(&coating, 13.5e-9, 0.0);
+        let (oblique, oblique_expected) = coated_reflection(&coating, 13.5e-9, 20.0);
+        let (detuned, detuned_expected) = coated_reflection(&coating, 12.8e-9, 0.0);
+        for (measured, expected) in [(normal, normal_expected), (oblique, oblique_expected), (detuned, detuned_expected)] {
+            assert!((measured - expected).abs() < 0.03, "{measured} vs {expected}");
+        }
+        // Off the Bragg peak in angle or wavelength the mirror reflects far less
+        assert!(normal > 0.6);
+        assert!(oblique < 0.5 * normal);
+        assert!(detuned < 0.8 * normal);
+    }
+
+    #[test]
+    fn test_system_delivers_through_projection_mirrors() {
+        let mut world = World::new();
+        world.insert_resource(OpticalSystemConfig::default());
+        let sequence = world.run_system_once(spawn_optical_system).unwrap();
+        world.insert_resource(RayTracingStatistics::default());
+        world.insert_resource(RayTracingConfig { sequence: sequence.clone(), seed: 3, ..Default::default() });
+        world.insert_resource(MirrorBvh::default());
+        world.run_system_once(mirror_bvh_system).unwrap();
+
+        // Light the back of the collector, within 60° of -x, which reflects towards the intermediate focus
+        let mut batch = PhotonBatch::default();
+        for i in 0..20_000 {
+            let angle = i as f32 * 2.399_963;
+            let x = -0.5 - 0.5 * (i as f32 + 0.5) / 20_000.0;
+            let r = (1.0 - x * x).sqrt();
+            batch.push(&PhotonPacket::new(Position3D::zero(), Vec3::new(x, r * angle.cos(), r * angle.sin()), 1000));
+        }
+        world.insert_resource(batch);
+        world.run_system_once(ray_transport_system).unwrap();
+
+        let stats = world.resource::<RayTracingStatistics>();
+        let mirror = |index: usize| &stats.per_mirror[&sequence[index]];
+        let collected = mirror(0).reflections;
+        assert!(collected > 200, "{collected}");
+        // The first projection mirror catches the collected light and the
+        // train passes a good share of it on
+        assert!(mirror(1).arrivals() as f64 > 0.95 * collected as f64, "{:?}", mirror(1));
+        assert!(mirror(2).reflections > 0);
+        assert!(stats.delivered_packets as f64 > 0.3 * collected as f64, "{} of {collected}", stats.delivered_packets);
+    }
+
+    #[test]
+    fn test_absorbed_and_reflected_energy_add_up() {
+        let mut world = World::new();
+        let mirror = spawn_mirror(&mut world, 2, Aperture::Circular { radius: 0.5 });
+        let mut film = ContaminationState::new(1.0, 0.0);
+        film.tin_thickness = 2e-9;
+        world.entity_mut(mirror).insert((MultilayerCoating::new(MultilayerStack::mo_si()), film));
+        world.insert_resource(RayTracingStatistics::default());
+        world.insert_resource(RayTracingConfig { sequence: vec![mirror], ..Default::default() });
+        world.insert_resource(MirrorBvh::default());
+        world.run_system_once(mirror_bvh_system).unwrap();
+        let packet = PhotonPacket::new(Position3D::zero(), Vec3::Z, 1000);
+        let mut batch = PhotonBatch::default();
+        for _ in 0..4000 {
+            batch.push(&packet);
+        }
+        world.insert_resource(batch);
+        world.run_system_once(ray_transport_system).unwrap();
+
+        // Coating and tin film both take their share, and every joule that
+        // arrives is either reflected or heats the mirror
+        let stats = world.resource::<RayTracingStatistics>();
+        let incident = 4000.0 * packet.total_energy();
+        assert!(stats.delivered_packets > 1000 && stats.total_absorptions > 1000);
+        let heat = world.get::<ThermalState>(mirror).unwrap().heat_energy;
+        assert!((stats.in_band_heat - heat).abs() < 1e-4 * incident);
+        assert!((stats.delivered_energy + heat - incident).abs() < 1e-4 * incident, "{} + {heat} vs {incident}", stats.delivered_energy);
+    }
+
+    /// Traces 5000 packets into a lossy sphere on a pool of `threads` threads
+    fn trace_sphere(threads: usize) -> (RayTracingStatistics, f32) {
+        let mut world = World::new();
+        let mirror = world.spawn((
+            Position(on_axis(0)),
+            MirrorSurface {
+                geometry: SurfaceGeometry::Spherical { radius: Distance::from_meters(1), center: on_axis(0) },
+                orientation: Quat::IDENTITY,
+                aperture: Aperture::Rectangular { half_width: 0.6, half_height: 0.6 },
+            },
+            OpticalMaterial::BRAGG_MIRROR,
+            ThermalState::new(293.15, 1000.0),
+        )).id();
+        world.insert_resource(RayTracingStatistics::default());
+        world.insert_resource(RayTracingConfig {
+            mode: TracingMode::NonSequential,
+            seed: 11,
+            ..Default::default()
+        });
+        world.insert_resource(MirrorBvh::default());
+
+        let mut batch = PhotonBatch::default();
+        for i in 0..5000 {
+            let angle = i as f32 * 2.399_963; // golden-angle spiral over the sphere
+            let z = 1.0 - 2.0 * (i as f32 + 0.5) / 5000.0;
+            let r = (1.0 - z * z).sqrt();
+            let direction = Vec3::new(r * angle.cos(), r * angle.sin(), z);
+            batch.push(&PhotonPacket::with_wavelength(Position3D::zero(), direction, 1000 + i, 13.5e-9 + i as f32 * 1e-13));
+        }
+        world.insert_resource(batch);
+
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        pool.install(|| {
+            world.run_system_once(mirror_bvh_system).unwrap();
+            world.run_system_once(ray_transport_system).unwrap();
+        });
+        let heat = world.get::<ThermalState>(mirror).unwrap().heat_energy;
+        (world.remove_resource::<RayTracingStatistics>().unwrap(), heat)
+    }
+
+    #[test]
+    fn test_parallel_tracing_is_deterministic() {
+        let (serial, serial_heat) = trace_sphere(1);
+        let (parallel, parallel_heat) = trace_sphere(4);
+
+        assert_eq!(serial.traced_packets, 5000);
+        assert!(serial.total_reflections > 0 && serial.total_absorptions > 0);
+        assert_eq!(serial.total_reflections, parallel.total_reflections);
+        assert_eq!(serial.total_absorptions, parallel.total_absorptions);
+        assert_eq!(serial.escaped_packets, parallel.escaped_packets);
+        // Same reduction order, so floating-point sums match bit for bit
+        assert_eq!(serial.in_band_heat.to_bits(), parallel.in_band_heat.to_bits());
+        assert_eq!(serial_heat.to_bits(), parallel_heat.to_bits());
+        let vignetted = |stats: &RayTracingStatistics| stats.per_mirror.values().map(|m| m.vignetted_energy).sum::<f32>();
+        assert_eq!(vignetted(&serial).to_bits(), vignetted(&parallel).to_bits());
+    }
+}
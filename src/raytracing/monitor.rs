@@ -0,0 +1,313 @@
+//! What the tracer measures of the illuminator: the facet mirrors it reflects
+//! off, and where light reaching the reticle sits in the pupil and the slit
+//!
+//! The illuminator (see `crate::illumination`) builds these; the tracer only reads them.
+
+use bevy_ecs::prelude::*;
+use glam::{Quat, Vec2, Vec3};
+use crate::units::Position3D;
+use crate::optics::MirrorSurface;
+
+/// Figure of one facet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FacetShape {
+    /// Concave sphere of `radius` (infinite for flat) whose axis is the
+    /// array's local z-axis rotated about local x, then local y, by `tilt` (rad)
+    Spherical { tilt: Vec2, radius: f32 },
+    /// Ellipsoid with foci `source` and `target` in the array's local frame,
+    /// imaging one onto the other without aberration
+    Ellipsoidal { source: Vec3, target: Vec3 },
+}
+
+/// One rectangular facet of a facet mirror, in the array's local frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Facet {
+    /// Centre in the array's local xy-plane (m)
+    pub center: Vec2,
+    pub half_size: Vec2,
+    pub shape: FacetShape,
+}
+
+impl Facet {
+    pub fn contains(&self, p: Vec2) -> bool {
+        let offset = (p - self.center).abs();
+        offset.x <= self.half_size.x && offset.y <= self.half_size.y
+    }
+
+    /// Unit normal at local point `p`, towards the array's local +z
+    ///
+    /// Facets are thin: their figure sets the normal but the hit point stays
+    /// on the array plane.
+    pub fn normal(&self, p: Vec2) -> Vec3 {
+        match self.shape {
+            FacetShape::Spherical { tilt, radius } => {
+                let slope = if radius.is_finite() { (p - self.center) / radius } else { Vec2::ZERO };
+                let rotation = Quat::from_rotation_y(tilt.y) * Quat::from_rotation_x(tilt.x);
+                rotation * Vec3::new(-slope.x, -slope.y, 1.0).normalize()
+            }
+            FacetShape::Ellipsoidal { source, target } => {
+                let point = p.extend(0.0);
+                ((target - point).normalize() - (point - source).normalize()).normalize()
+            }
+        }
+    }
+
+    /// Tilt that turns the local z-axis onto the unit `normal`
+    pub fn tilt_towards(normal: Vec3) -> Vec2 {
+        Vec2::new((-normal.y).clamp(-1.0, 1.0).asin(), normal.x.atan2(normal.z))
+    }
+}
+
+/// Regular grid of identical facets centred on the array origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FacetGrid {
+    pub columns: usize,
+    pub rows: usize,
+    pub half_size: Vec2,
+    /// Centre-to-centre spacing (m)
+    pub pitch: Vec2,
+}
+
+impl FacetGrid {
+    /// Half extent of the whole array (m)
+    pub fn half_extent(&self) -> Vec2 {
+        let cells = Vec2::new(self.columns.saturating_sub(1) as f32, self.rows.saturating_sub(1) as f32);
+        self.pitch * cells / 2.0 + self.half_size
+    }
+
+    /// Facet centres, row by row
+    pub fn centers(&self) -> impl Iterator<Item = Vec2> + '_ {
+        let origin = self.pitch * Vec2::new(self.columns as f32 - 1.0, self.rows as f32 - 1.0) / 2.0;
+        (0..self.rows).flat_map(move |row| {
+            (0..self.columns).map(move |column| Vec2::new(column as f32, row as f32) * self.pitch - origin)
+        })
+    }
+}
+
+/// Facets of a facet mirror; the mirror's normal comes from the facet hit,
+/// and light landing between facets passes as if outside the aperture
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct FacetArray {
+    pub facets: Vec<Facet>,
+}
+
+impl FacetArray {
+    /// Untilted flat facets on a grid
+    pub fn grid(grid: &FacetGrid) -> Self {
+        let facets = grid
+            .centers()
+            .map(|center| Facet {
+                center,
+                half_size: grid.half_size,
+                shape: FacetShape::Spherical { tilt: Vec2::ZERO, radius: f32::INFINITY },
+            })
+            .collect();
+        Self { facets }
+    }
+
+    pub fn facet_at(&self, p: Vec2) -> Option<usize> {
+        self.facets.iter().position(|facet| facet.contains(p))
+    }
+
+    /// World normal at a surface point, or `None` between facets
+    pub fn normal_at(&self, surface: &MirrorSurface, mirror_position: Position3D, point: Position3D) -> Option<Vec3> {
+        let p = surface.aperture_coordinates(mirror_position, point);
+        let facet = &self.facets[self.facet_at(p)?];
+        Some(surface.orientation * facet.normal(p))
+    }
+}
+
+/// Maps rays arriving at the reticle to pupil coordinates σ
+///
+/// Each field point sees the pupil about its own chief ray, the ray from the
+/// pupil centre, so σ is the ray's angle from that chief ray over the NA.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PupilFrame {
+    pub center: Vec3,
+    /// Pupil x (across the slit), perpendicular to the central chief ray
+    pub x_axis: Vec3,
+    pub y_axis: Vec3,
+    /// Reticle-side numerical aperture at σ = 1
+    pub numerical_aperture: f32,
+}
+
+impl PupilFrame {
+    pub fn new(center: Vec3, chief_ray: Vec3, slit_axis: Vec3, numerical_aperture: f32) -> Self {
+        let chief_ray = chief_ray.normalize();
+        let x_axis = (slit_axis - chief_ray * slit_axis.dot(chief_ray)).normalize();
+        Self { center, x_axis, y_axis: chief_ray.cross(x_axis), numerical_aperture }
+    }
+
+    /// Pupil position σ of light reaching reticle `point` along `direction`
+    pub fn sigma(&self, point: Vec3, direction: Vec3) -> Vec2 {
+        let offset = (point - self.center).normalize() - direction.normalize();
+        Vec2::new(offset.dot(self.x_axis), offset.dot(self.y_axis)) / self.numerical_aperture
+    }
+}
+
+/// Where the ray tracer measures the illuminator's output
+#[derive(Debug, Clone, PartialEq)]
+pub struct IlluminationMonitor {
+    /// Sequence position of the first illuminator mirror; light reflected
+    /// towards it has passed the intermediate focus
+    pub intermediate_focus_index: usize,
+    pub pupil: PupilFrame,
+    /// Bins across σ ∈ [-1, 1] on each pupil axis
+    pub pupil_bins: usize,
+    pub slit_center: Vec3,
+    pub slit_axis: Vec3,
+    pub slit_half_width: f32,
+    /// Bins across the slit
+    pub slit_bins: usize,
+}
+
+/// Energy through the illuminator and its distribution at the reticle
+#[derive(Debug, Clone, Default)]
+pub struct IlluminationStatistics {
+    /// Energy reflected towards the intermediate focus (J)
+    pub intermediate_focus_energy: f32,
+    /// Energy arriving at the reticle (J)
+    pub reticle_energy: f32,
+    /// Reticle energy binned over σ ∈ [-1, 1]², rows from σy = -1
+    pub pupil_map: Vec<f32>,
+    pub pupil_bins: usize,
+    /// Reticle energy binned across the slit, integrated along the scan
+    pub slit_profile: Vec<f32>,
+}
+
+impl IlluminationStatistics {
+    /// Records light of `energy` arriving at the reticle at `point` along `direction`
+    pub fn record_reticle(&mut self, monitor: &IlluminationMonitor, point: Vec3, direction: Vec3, energy: f32) {
+        if self.pupil_map.is_empty() {
+            self.pupil_bins = monitor.pupil_bins;
+            self.pupil_map = vec![0.0; monitor.pupil_bins * monitor.pupil_bins];
+            self.slit_profile = vec![0.0; monitor.slit_bins];
+        }
+        self.reticle_energy += energy;
+
+        let bin = |u: f32, bins: usize| {
+            let index = ((u + 1.0) / 2.0 * bins as f32).floor();
+            (0.0..bins as f32).contains(&index).then_some(index as usize)
+        };
+        let sigma = monitor.pupil.sigma(point, direction);
+        if let (Some(column), Some(row)) = (bin(sigma.x, self.pupil_bins), bin(sigma.y, self.pupil_bins)) {
+            self.pupil_map[row * self.pupil_bins + column] += energy;
+        }
+        let across = (point - monitor.slit_center).dot(monitor.slit_axis) / monitor.slit_half_width;
+        if let Some(index) = bin(across, self.slit_profile.len()) {
+            self.slit_profile[index] += energy;
+        }
+    }
+
+    pub fn merge(&mut self, other: &IlluminationStatistics) {
+        self.intermediate_focus_energy += other.intermediate_focus_energy;
+        self.reticle_energy += other.reticle_energy;
+        if self.pupil_map.is_empty() {
+            self.pupil_map = vec![0.0; other.pupil_map.len()];
+            self.pupil_bins = other.pupil_bins;
+            self.slit_profile = vec![0.0; other.slit_profile.len()];
+        }
+        for (total, value) in self.pupil_map.iter_mut().zip(&other.pupil_map) {
+            *total += value;
+        }
+        for (total, value) in self.slit_profile.iter_mut().zip(&other.slit_profile) {
+            *total += value;
+        }
+    }
+
+    /// Fraction of the energy passing the intermediate focus that reaches the reticle
+    pub fn transmission(&self) -> f32 {
+        if self.intermediate_focus_energy > 0.0 {
+            self.reticle_energy / self.intermediate_focus_energy
+        } else {
+            0.0
+        }
+    }
+
+    /// Slit non-uniformity (max − min) / (max + min) of the scan-integrated profile
+    pub fn slit_uniformity(&self) -> f32 {
+        let (min, max) = self
+            .slit_profile
+            .iter()
+            .fold((f32::INFINITY, 0.0f32), |(min, max), &e| (min.min(e), max.max(e)));
+        if max > 0.0 { (max - min) / (max + min) } else { 0.0 }
+    }
+
+    /// Pupil map as text, σy = +1 at the top, shaded relative to the brightest bin
+    pub fn pupil_rows(&self) -> Vec<String> {
+        const SHADES: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+        let peak = self.pupil_map.iter().copied().fold(0.0, f32::max);
+        self.pupil_map
+            .chunks(self.pupil_bins.max(1))
+            .rev()
+            .map(|row| {
+                row.iter()
+                    .map(|&e| {
+                        let level = if peak > 0.0 { (e / peak * (SHADES.len() - 1) as f32).ceil() as usize } else { 0 };
+                        SHADES[level.min(SHADES.len() - 1)]
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_facet_tilt_round_trip() {
+        let normal = Vec3::new(0.1, -0.2, 1.0).normalize();
+        let mut facet = Facet {
+            center: Vec2::new(0.3, 0.1),
+            half_size: Vec2::splat(0.01),
+            shape: FacetShape::Spherical { tilt: Facet::tilt_towards(normal), radius: 2.0 },
+        };
+        assert!((facet.normal(facet.center) - normal).length() < 1e-6);
+        // Concave: the normal leans back towards the centre
+        assert!(facet.normal(facet.center + Vec2::new(0.01, 0.0)).x < normal.x);
+
+        // Every point of an ellipsoidal facet reflects light from one focus to the other
+        let (source, target) = (Vec3::new(-0.2, 0.0, 1.0), Vec3::new(0.5, 0.3, 2.0));
+        facet.shape = FacetShape::Ellipsoidal { source, target };
+        for p in [facet.center, facet.center + Vec2::new(0.01, -0.01)] {
+            let point = p.extend(0.0);
+            let normal = facet.normal(p);
+            let incoming = (point - source).normalize();
+            let reflected = incoming - 2.0 * incoming.dot(normal) * normal;
+            assert!(reflected.dot((target - point).normalize()) > 1.0 - 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_statistics() {
+        let monitor = IlluminationMonitor {
+            intermediate_focus_index: 1,
+            pupil: PupilFrame::new(Vec3::new(0.0, 0.0, -10.0), Vec3::Z, Vec3::X, 0.1),
+            pupil_bins: 4,
+            slit_center: Vec3::ZERO,
+            slit_axis: Vec3::X,
+            slit_half_width: 0.01,
+            slit_bins: 2,
+        };
+        let mut stats = IlluminationStatistics { intermediate_focus_energy: 4.0, ..Default::default() };
+        // σ ≈ (0.7, 0) at both points: direction tilted towards -x
+        let direction = Vec3::new(-0.07, 0.0, 1.0).normalize();
+        stats.record_reticle(&monitor, Vec3::new(-0.005, 0.0, 0.0), direction, 1.0);
+        stats.record_reticle(&monitor, Vec3::new(0.005, 0.0, 0.0), direction, 3.0);
+        assert_eq!(stats.transmission(), 1.0);
+        assert_eq!(stats.slit_profile, vec![1.0, 3.0]);
+        assert!((stats.slit_uniformity() - 0.5).abs() < 1e-6);
+        assert_eq!(stats.pupil_map[2 * 4 + 3], 4.0);
+        let rows = stats.pupil_rows();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[1], "   @");
+
+        let mut total = IlluminationStatistics::default();
+        total.merge(&stats);
+        total.merge(&stats);
+        assert_eq!(total.reticle_energy, 8.0);
+        assert_eq!(total.slit_profile, vec![2.0, 6.0]);
+    }
+}
@@ -0,0 +1,377 @@
+//! Reflective EUV reticle: a multilayer blank patterned with an absorber film
+//!
+//! Light landing on bare blank is reflected by the multilayer; light landing
+//! on the absorber crosses the film twice and mostly heats the reticle. The
+//! pattern lives in the reticle's local xy-plane, as a pixel raster or a list
+//! of polygons.
+
+use std::sync::Arc;
+use bevy_ecs::prelude::*;
+use glam::Vec2;
+use nalgebra::Complex;
+use crate::coating::{MultilayerCoating, MultilayerStack, ReflectivityTable};
+use crate::optics::Aperture;
+
+/// Absorber film on top of the blank's capping layer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Absorber {
+    /// Film thickness (nm)
+    pub thickness: f64,
+    /// Refractive index n = 1 - δ - iβ at 13.5 nm
+    pub delta: f64,
+    pub beta: f64,
+}
+
+impl Absorber {
+    /// 60 nm TaBN, the usual absorber of production EUV masks
+    pub const TA_BN: Absorber = Absorber { thickness: 60.0, delta: 0.050, beta: 0.031 };
+
+    /// Complex refractive index n - ik
+    pub fn refractive_index(&self) -> Complex<f64> {
+        Complex::new(1.0 - self.delta, -self.beta)
+    }
+}
+
+/// Parses `thickness_nm,delta,beta`
+impl std::str::FromStr for Absorber {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<f64>().map_err(|e| format!("bad number '{v}': {e}")))
+            .collect::<Result<Vec<_>, _>>()?;
+        match values.as_slice() {
+            &[thickness, delta, beta] if thickness >= 0.0 && beta >= 0.0 => Ok(Absorber { thickness, delta, beta }),
+            _ => Err(format!("absorber '{s}' needs a thickness (nm), δ and β, none negative")),
+        }
+    }
+}
+
+impl std::fmt::Display for Absorber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.thickness, self.delta, self.beta)
+    }
+}
+
+/// Pixel mask centred on the reticle origin; row 0 is the top (local +y)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RasterMask {
+    pub columns: usize,
+    pub rows: usize,
+    /// Pixel size (m)
+    pub pixel: f32,
+    /// `true` where the absorber is, row by row
+    pub absorber: Vec<bool>,
+}
+
+impl RasterMask {
+    /// Reads a plain (P1) PBM image, black pixels being absorber
+    pub fn from_pbm(text: &str, pixel: f32) -> Result<Self, String> {
+        let mut tokens = text.lines().map(|line| line.split('#').next().unwrap_or("")).flat_map(str::split_whitespace);
+        if tokens.next() != Some("P1") {
+            return Err("only plain PBM (P1) masks are supported".to_string());
+        }
+        let mut dimension = |name: &str| -> Result<usize, String> {
+            let token = tokens.next().ok_or_else(|| format!("PBM is missing its {name}"))?;
+            token.parse().map_err(|e| format!("bad PBM {name} '{token}': {e}"))
+        };
+        let (columns, rows) = (dimension("width")?, dimension("height")?);
+        // Plain PBM allows pixels with or without separating whitespace
+        let absorber = tokens
+            .flat_map(str::chars)
+            .map(|c| match c {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                other => Err(format!("bad PBM pixel '{other}'")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if absorber.len() != columns * rows {
+            return Err(format!("PBM has {} pixels, expected {columns}×{rows}", absorber.len()));
+        }
+        if pixel.is_nan() || pixel <= 0.0 {
+            return Err(format!("pixel size must be positive, got {pixel}"));
+        }
+        Ok(Self { columns, rows, pixel, absorber })
+    }
+
+    /// Whether local point `p` falls on an absorber pixel; outside the mask is bare blank
+    pub fn absorbs(&self, p: Vec2) -> bool {
+        let column = (p.x / self.pixel + self.columns as f32 / 2.0).floor();
+        let row = (self.rows as f32 / 2.0 - p.y / self.pixel).floor();
+        if column < 0.0 || row < 0.0 || column >= self.columns as f32 || row >= self.rows as f32 {
+            return false;
+        }
+        self.absorber[row as usize * self.columns + column as usize]
+    }
+}
+
+/// Where the absorber covers the blank, in the reticle's local xy-plane
+#[derive(Debug, Clone)]
+pub enum AbsorberPattern {
+    Raster(RasterMask),
+    /// Absorber polygons (each an `Aperture::Polygon`, meters); none leaves a bare blank
+    Polygons(Vec<Aperture>),
+}
+
+impl Default for AbsorberPattern {
+    fn default() -> Self {
+        AbsorberPattern::Polygons(Vec::new())
+    }
+}
+
+impl AbsorberPattern {
+    pub fn absorbs(&self, p: Vec2) -> bool {
+        match self {
+            AbsorberPattern::Raster(mask) => mask.absorbs(p),
+            AbsorberPattern::Polygons(polygons) => polygons.iter().any(|polygon| polygon.contains(p)),
+        }
+    }
+
+    /// Parses a pattern, reading the image for `raster:path.pbm,pixel_m`
+    pub fn load(spec: &str) -> Result<Self, String> {
+        match spec.trim().strip_prefix("raster:") {
+            Some(raster) => {
+                let (path, pixel) = raster.rsplit_once(',').ok_or("raster pattern needs 'path,pixel'")?;
+                let pixel = pixel.trim().parse::<f32>().map_err(|e| format!("bad pixel size '{pixel}': {e}"))?;
+                let text = std::fs::read_to_string(path.trim()).map_err(|e| format!("cannot read '{path}': {e}"))?;
+                RasterMask::from_pbm(&text, pixel).map(AbsorberPattern::Raster)
+            }
+            None => spec.parse(),
+        }
+    }
+}
+
+/// Parses `none` or `polygons:x1,y1;x2,y2;...|x1,y1;...` (meters)
+impl std::str::FromStr for AbsorberPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "none" => Ok(AbsorberPattern::default()),
+            other => {
+                let polygons = other
+                    .strip_prefix("polygons:")
+                    .ok_or_else(|| format!("unrecognised absorber pattern '{s}'"))?;
+                polygons
+                    .split('|')
+                    .map(|polygon| format!("polygon:{polygon}").parse::<Aperture>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(AbsorberPattern::Polygons)
+            }
+        }
+    }
+}
+
+/// Patterned reflective reticle; replaces `MultilayerCoating` on its mirror
+#[derive(Component, Debug, Clone)]
+pub struct Reticle {
+    /// Reflective multilayer of the bare blank
+    pub blank: MultilayerCoating,
+    pub absorber: Absorber,
+    pub pattern: AbsorberPattern,
+    /// Blank reflectivity beneath the absorber film
+    absorber_table: Arc<ReflectivityTable>,
+}
+
+impl Default for Reticle {
+    /// Mo/Si blank with a TaBN absorber and no pattern
+    fn default() -> Self {
+        Self::new(MultilayerStack::mo_si(), Absorber::TA_BN, AbsorberPattern::default())
+    }
+}
+
+impl Reticle {
+    pub fn new(blank: MultilayerStack, absorber: Absorber, pattern: AbsorberPattern) -> Self {
+        let absorber_table = ReflectivityTable::compute_beneath(&blank, &[(absorber.refractive_index(), absorber.thickness)]);
+        Self { blank: MultilayerCoating::new(blank), absorber, pattern, absorber_table: Arc::new(absorber_table) }
+    }
+
+    /// Whether local point `p` lies under the absorber
+    pub fn absorbs(&self, p: Vec2) -> bool {
+        self.pattern.absorbs(p)
+    }
+
+    /// Amplitude coefficients (r_s, r_p) on the absorber or the bare blank at
+    /// the given wavelength (m) and angle of incidence (rad), or `None`
+    /// outside the tabulated band
+    pub fn coefficients(&self, on_absorber: bool, wavelength: f32, incidence: f32) -> Option<(Complex<f32>, Complex<f32>)> {
+        if on_absorber {
+            self.absorber_table.coefficients(wavelength, incidence)
+        } else {
+            self.blank.coefficients(wavelength, incidence)
+        }
+    }
+
+    /// Unpolarized reflectance on the absorber or the bare blank
+    pub fn reflectance(&self, on_absorber: bool, wavelength: f32, incidence: f32) -> Option<f32> {
+        self.coefficients(on_absorber, wavelength, incidence)
+            .map(|(rs, rp)| 0.5 * (rs.norm_sqr() + rp.norm_sqr()))
+    }
+}
+
+/// Energy budget of light reaching a reticle, split by absorber and bare blank (J)
+///
+/// Incident and reflected energy count in-band light only, so the measured
+/// reflectances are those of the pattern at 13.5 nm; heat counts every band.
+#[derive(Debug, Clone, Default)]
+pub struct ReticleStatistics {
+    pub absorber_incident: f32,
+    pub absorber_reflected: f32,
+    pub absorber_heat: f32,
+    pub blank_incident: f32,
+    pub blank_reflected: f32,
+    pub blank_heat: f32,
+}
+
+impl ReticleStatistics {
+    pub fn record_incident(&mut self, on_absorber: bool, energy: f32) {
+        *if on_absorber { &mut self.absorber_incident } else { &mut self.blank_incident } += energy;
+    }
+
+    pub fn record_reflected(&mut self, on_absorber: bool, energy: f32) {
+        *if on_absorber { &mut self.absorber_reflected } else { &mut self.blank_reflected } += energy;
+    }
+
+    pub fn record_heat(&mut self, on_absorber: bool, energy: f32) {
+        *if on_absorber { &mut self.absorber_heat } else { &mut self.blank_heat } += energy;
+    }
+
+    pub fn merge(&mut self, other: &ReticleStatistics) {
+        self.absorber_incident += other.absorber_incident;
+        self.absorber_reflected += other.absorber_reflected;
+        self.absorber_heat += other.absorber_heat;
+        self.blank_incident += other.blank_incident;
+        self.blank_reflected += other.blank_reflected;
+        self.blank_heat += other.blank_heat;
+    }
+
+    pub fn incident(&self) -> f32 {
+        self.absorber_incident + self.blank_incident
+    }
+
+    /// Heat deposited in the reticle (J)
+    pub fn heat(&self) -> f32 {
+        self.absorber_heat + self.blank_heat
+    }
+
+    /// Fraction of the incident light that landed on absorber
+    pub fn absorber_coverage(&self) -> f32 {
+        if self.incident() > 0.0 { self.absorber_incident / self.incident() } else { 0.0 }
+    }
+
+    /// Measured reflectance of the absorber and of the bare blank
+    pub fn reflectances(&self) -> (f32, f32) {
+        let ratio = |reflected: f32, incident: f32| if incident > 0.0 { reflected / incident } else { 0.0 };
+        (ratio(self.absorber_reflected, self.absorber_incident), ratio(self.blank_reflected, self.blank_incident))
+    }
+
+    /// Mask contrast (R_blank - R_absorber) / (R_blank + R_absorber)
+    pub fn contrast(&self) -> f32 {
+        let (absorber, blank) = self.reflectances();
+        if absorber + blank > 0.0 { (blank - absorber) / (blank + absorber) } else { 0.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::system::RunSystemOnce;
+    use glam::{Quat, Vec3};
+    use crate::components::*;
+    use crate::optics::{MirrorSurface, SagProfile, SurfaceGeometry};
+    use crate::raytracing::*;
+    use crate::units::Position3D;
+
+    #[test]
+    fn test_absorber_darkens_the_blank() {
+        let reticle = Reticle::default();
+        let incidence = 6f32.to_radians();
+        let blank = reticle.reflectance(false, 13.5e-9, incidence).unwrap();
+        let absorber = reticle.reflectance(true, 13.5e-9, incidence).unwrap();
+        assert!(blank > 0.6, "blank {blank}");
+        assert!(absorber < 0.03, "absorber {absorber}");
+
+        // A vanishing film leaves the blank unchanged
+        let bare = Reticle::new(MultilayerStack::mo_si(), Absorber { thickness: 0.0, ..Absorber::TA_BN }, AbsorberPattern::default());
+        assert!((bare.reflectance(true, 13.5e-9, incidence).unwrap() - blank).abs() < 1e-5);
+        assert!("60,0.05".parse::<Absorber>().is_err());
+        assert_eq!("55,0.06,0.04".parse::<Absorber>().unwrap().to_string(), "55,0.06,0.04");
+    }
+
+    #[test]
+    fn test_patterns() {
+        // 4×2 mask of 1 mm pixels: the left column and the bottom-right pixel absorb
+        let mask = RasterMask::from_pbm("P1\n# test\n4 2\n1000\n1 0 0 1\n", 1e-3).unwrap();
+        assert!(mask.absorbs(Vec2::new(-1.5e-3, 0.5e-3)));
+        assert!(!mask.absorbs(Vec2::new(-0.5e-3, 0.5e-3)));
+        assert!(mask.absorbs(Vec2::new(1.5e-3, -0.5e-3)));
+        assert!(!mask.absorbs(Vec2::new(1.5e-3, 0.5e-3)));
+        assert!(!mask.absorbs(Vec2::new(-2.5e-3, 0.0)));
+        assert!(RasterMask::from_pbm("P1 4 2 1000", 1e-3).is_err());
+        assert!(RasterMask::from_pbm("P4 1 1 1", 1e-3).is_err());
+
+        let pattern: AbsorberPattern = "polygons:0,0;1,0;1,1;0,1|-1,-1;-0.5,-1;-0.5,-0.5".parse().unwrap();
+        assert!(pattern.absorbs(Vec2::new(0.5, 0.5)));
+        assert!(pattern.absorbs(Vec2::new(-0.6, -0.9)));
+        assert!(!pattern.absorbs(Vec2::new(-0.5, 0.5)));
+        assert!(!"none".parse::<AbsorberPattern>().unwrap().absorbs(Vec2::ZERO));
+        assert!("polygons:0,0;1,0".parse::<AbsorberPattern>().is_err());
+        assert!("lines:1,2".parse::<AbsorberPattern>().is_err());
+    }
+
+    #[test]
+    fn test_traced_reticle_reflects_by_pattern_and_heats() {
+        let mut world = World::new();
+        let position = Position3D::from_vec3(Vec3::new(0.0, 0.0, 1.0));
+        // Facing the source, absorber over the half with local x < 0
+        let orientation = Quat::from_rotation_x(std::f32::consts::PI);
+        let pattern: AbsorberPattern = "polygons:-0.1,-0.1;0,-0.1;0,0.1;-0.1,0.1".parse().unwrap();
+        let reticle = world.spawn((
+            Position(position),
+            MirrorSurface {
+                geometry: SurfaceGeometry::Sag { vertex: position, orientation, profile: SagProfile::conic(f64::INFINITY, 0.0, 0.2) },
+                orientation,
+                aperture: crate::optics::Aperture::Rectangular { half_width: 0.1, half_height: 0.1 },
+            },
+            OpticalMaterial::BRAGG_MIRROR,
+            ThermalState::new(ThermalState::AMBIENT, 500.0),
+            Reticle::new(MultilayerStack::mo_si(), Absorber::TA_BN, pattern),
+        )).id();
+        world.insert_resource(RayTracingConfig { sequence: vec![reticle], reticle_index: Some(0), ..Default::default() });
+        world.insert_resource(RayTracingStatistics::default());
+        world.insert_resource(MirrorBvh::default());
+        world.run_system_once(mirror_bvh_system).unwrap();
+
+        let mut batch = PhotonBatch::default();
+        for i in 0..100 {
+            for j in 0..100 {
+                let aim = Vec3::new((i as f32 - 49.5) * 0.0015, (j as f32 - 49.5) * 0.0015, 1.0);
+                batch.push(&PhotonPacket::new(Position3D::zero(), aim, 1000));
+            }
+        }
+        world.insert_resource(batch);
+        world.run_system_once(ray_transport_system).unwrap();
+
+        let stats = &world.resource::<RayTracingStatistics>().reticle;
+        assert!((stats.absorber_coverage() - 0.5).abs() < 0.01, "{}", stats.absorber_coverage());
+        let (absorber, blank) = stats.reflectances();
+        assert!(blank > 0.6 && absorber < 0.05, "{absorber} {blank}");
+        assert!(stats.contrast() > 0.9);
+        // The absorber soaks up nearly all it receives, the blank only what it does not reflect
+        assert!(stats.absorber_heat > 0.95 * stats.absorber_incident);
+        assert!(stats.blank_heat < 0.4 * stats.blank_incident);
+        // Whatever reaches the reticle is either reflected or absorbed
+        for (incident, reflected, heat) in [
+            (stats.absorber_incident, stats.absorber_reflected, stats.absorber_heat),
+            (stats.blank_incident, stats.blank_reflected, stats.blank_heat),
+        ] {
+            assert!((reflected + heat - incident).abs() < 1e-4 * incident, "{reflected} + {heat} vs {incident}");
+        }
+
+        // Absorbed energy heats the reticle
+        let thermal = world.get::<ThermalState>(reticle).unwrap();
+        assert!(stats.heat() > 0.0);
+        assert!((thermal.heat_energy - stats.heat()).abs() < 1e-3 * stats.heat());
+    }
+}
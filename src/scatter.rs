@@ -0,0 +1,198 @@
+//! Surface roughness scatter on mirror reflections
+//!
+//! Smooth-surface (Rayleigh-Rice) model: the total integrated scatter follows
+//! from the rms roughness over the spatial frequencies that scatter into the
+//! hemisphere, and each scattered ray leaves along the grating equation for a
+//! surface spatial frequency drawn from the roughness PSD.
+
+use bevy_ecs::prelude::*;
+use glam::Vec3;
+use rand::Rng;
+
+/// Isotropic 2D power spectral density of the surface height
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoughnessPsd {
+    /// Gaussian autocorrelation with the given rms height and correlation length (m)
+    Gaussian { rms: f32, correlation_length: f32 },
+    /// K-correlation (ABC) model PSD(f) = A / (1 + (B f)²)^(C/2), A in m⁴ and B in m
+    KCorrelation { a: f32, b: f32, c: f32 },
+}
+
+/// Roughness of a mirror surface; mirrors without it reflect specularly
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceRoughness {
+    pub psd: RoughnessPsd,
+}
+
+/// Parses `gaussian:rms,correlation_length` (meters) or `abc:A,B,C` (m⁴, m, -)
+impl std::str::FromStr for SurfaceRoughness {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, values) = s.split_once(':').ok_or_else(|| format!("missing ':' in roughness '{s}'"))?;
+        let numbers = values
+            .split(',')
+            .map(|v| v.trim().parse::<f32>().map_err(|e| format!("bad number '{v}': {e}")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let psd = match (kind.trim(), numbers.as_slice()) {
+            ("gaussian", &[rms, correlation_length]) if rms >= 0.0 && correlation_length > 0.0 => {
+                RoughnessPsd::Gaussian { rms, correlation_length }
+            }
+            ("abc", &[a, b, c]) if a >= 0.0 && b > 0.0 && c > 1.0 => RoughnessPsd::KCorrelation { a, b, c },
+            _ => return Err(format!("unrecognised roughness '{s}'")),
+        };
+        Ok(Self { psd })
+    }
+}
+
+/// Writes the form accepted by `FromStr`
+impl std::fmt::Display for SurfaceRoughness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.psd {
+            RoughnessPsd::Gaussian { rms, correlation_length } => write!(f, "gaussian:{rms:e},{correlation_length:e}"),
+            RoughnessPsd::KCorrelation { a, b, c } => write!(f, "abc:{a:e},{b:e},{c}"),
+        }
+    }
+}
+
+impl SurfaceRoughness {
+    /// Typical polished collector substrate: 0.25 nm rms, 1 μm correlation length
+    pub const COLLECTOR: Self = Self::gaussian(0.25e-9, 1e-6);
+    /// Typical projection-optics figure finish: 0.1 nm rms, 1 μm correlation length
+    pub const PROJECTION: Self = Self::gaussian(0.1e-9, 1e-6);
+
+    pub const fn gaussian(rms: f32, correlation_length: f32) -> Self {
+        Self { psd: RoughnessPsd::Gaussian { rms, correlation_length } }
+    }
+
+    /// Rms height (m) over spatial frequencies up to `max_frequency` (1/m)
+    pub fn band_rms(&self, max_frequency: f32) -> f32 {
+        use std::f32::consts::PI;
+        let variance = match self.psd {
+            RoughnessPsd::Gaussian { rms, correlation_length } => {
+                rms * rms * (1.0 - (-(PI * correlation_length * max_frequency).powi(2)).exp())
+            }
+            RoughnessPsd::KCorrelation { a, b, c } => {
+                let u_max = 1.0 + (b * max_frequency).powi(2);
+                PI * a / (b * b) * k_correlation_integral(u_max, c)
+            }
+        };
+        variance.max(0.0).sqrt()
+    }
+
+    /// Fraction of the reflected light scattered out of the specular direction
+    ///
+    /// Uses the roughness over frequencies below 1/λ, which scatter into the hemisphere.
+    pub fn total_integrated_scatter(&self, wavelength: f32, incidence: f32) -> f32 {
+        let sigma = self.band_rms(1.0 / wavelength);
+        let phase = 4.0 * std::f32::consts::PI * sigma * incidence.cos() / wavelength;
+        1.0 - (-phase * phase).exp()
+    }
+
+    /// Spatial frequency magnitude (1/m) drawn from the radial PSD weight 2πf·PSD(f) below `max_frequency`
+    fn sample_frequency(&self, max_frequency: f32, rng: &mut impl Rng) -> f32 {
+        use std::f32::consts::PI;
+        match self.psd {
+            RoughnessPsd::Gaussian { correlation_length, .. } => {
+                let scale = PI * correlation_length;
+                let cdf_max = 1.0 - (-(scale * max_frequency).powi(2)).exp();
+                let x = rng.gen::<f32>() * cdf_max;
+                (-(1.0 - x).ln()).sqrt() / scale
+            }
+            RoughnessPsd::KCorrelation { b, c, .. } => {
+                let u_max = 1.0 + (b * max_frequency).powi(2);
+                let x = rng.gen::<f32>() * k_correlation_integral(u_max, c);
+                let exponent = 1.0 - c / 2.0;
+                let u = if exponent.abs() < 1e-6 {
+                    x.exp()
+                } else {
+                    (1.0 + exponent * x).powf(1.0 / exponent)
+                };
+                (u - 1.0).max(0.0).sqrt() / b
+            }
+        }
+    }
+
+    /// Scattered direction about the specular `reflected` ray off a surface with unit `normal`
+    ///
+    /// Adds λ·f to the tangential wavevector for a random surface frequency f;
+    /// returns `None` if no propagating direction was drawn.
+    pub fn scatter_direction(&self, reflected: Vec3, normal: Vec3, wavelength: f32, rng: &mut impl Rng) -> Option<Vec3> {
+        const ATTEMPTS: usize = 8;
+        let normal = if reflected.dot(normal) >= 0.0 { normal } else { -normal };
+        let tangential = reflected - reflected.dot(normal) * normal;
+        let (u, v) = normal.any_orthonormal_pair();
+        for _ in 0..ATTEMPTS {
+            let frequency = self.sample_frequency(1.0 / wavelength, rng);
+            let azimuth = rng.gen_range(0.0..std::f32::consts::TAU);
+            let (sin, cos) = azimuth.sin_cos();
+            let scattered = tangential + wavelength * frequency * (cos * u + sin * v);
+            let sin2 = scattered.length_squared();
+            if sin2 < 1.0 {
+                return Some(scattered + (1.0 - sin2).sqrt() * normal);
+            }
+        }
+        None
+    }
+}
+
+/// ∫₁^u t^(-C/2) dt, the radial K-correlation PSD integral in u = 1 + (B f)²
+fn k_correlation_integral(u: f32, c: f32) -> f32 {
+    let exponent = 1.0 - c / 2.0;
+    if exponent.abs() < 1e-6 {
+        u.ln()
+    } else {
+        (u.powf(exponent) - 1.0) / exponent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_total_integrated_scatter() {
+        // Debye-Waller factor for 0.25 nm rms at 13.5 nm, normal incidence
+        let tis = SurfaceRoughness::COLLECTOR.total_integrated_scatter(13.5e-9, 0.0);
+        let expected = 1.0 - (-(4.0 * std::f32::consts::PI * 0.25 / 13.5f32).powi(2)).exp();
+        assert!((tis - expected).abs() < 1e-4, "{tis} vs {expected}");
+        // Grazing light scatters less
+        assert!(SurfaceRoughness::COLLECTOR.total_integrated_scatter(13.5e-9, 1.2) < tis);
+    }
+
+    #[test]
+    fn test_k_correlation_rms_matches_integral() {
+        let roughness: SurfaceRoughness = "abc:1e-32,2e-6,3".parse().unwrap();
+        let RoughnessPsd::KCorrelation { a, b, c } = roughness.psd else { unreachable!() };
+        let max_frequency = 5e6f32;
+        let steps = 200_000;
+        let df = max_frequency as f64 / steps as f64;
+        let variance: f64 = (0..steps)
+            .map(|i| {
+                let f = (i as f64 + 0.5) * df;
+                2.0 * std::f64::consts::PI * f * a as f64 / (1.0 + (b as f64 * f).powi(2)).powf(c as f64 / 2.0) * df
+            })
+            .sum();
+        let rms = roughness.band_rms(max_frequency) as f64;
+        assert!((rms - variance.sqrt()).abs() / rms < 1e-3);
+    }
+
+    #[test]
+    fn test_scatter_directions_follow_psd() {
+        let roughness = SurfaceRoughness::gaussian(0.3e-9, 1e-6);
+        let wavelength = 13.5e-9;
+        let mut rng = StdRng::seed_from_u64(5);
+        let samples = 20_000;
+        let mut mean_angle = 0.0;
+        for _ in 0..samples {
+            let direction = roughness.scatter_direction(Vec3::Z, Vec3::Z, wavelength, &mut rng).unwrap();
+            assert!((direction.length() - 1.0).abs() < 1e-5 && direction.z > 0.0);
+            mean_angle += direction.truncate().length().asin() / samples as f32;
+        }
+        // |f| is Rayleigh distributed with mean √π / (2πℓ)
+        let expected = wavelength * std::f32::consts::PI.sqrt() / (2.0 * std::f32::consts::PI * 1e-6);
+        assert!((mean_angle - expected).abs() / expected < 0.03, "{mean_angle} vs {expected}");
+    }
+}
@@ -8,6 +8,8 @@ use rand::Rng;
 use rand_distr::{Distribution, Normal};
 use crate::units::{Position3D, Distance};
 use crate::components::*;
+use crate::burst::BurstScheduler;
+use crate::dose::{DoseController, PulseSkipped};
 
 /// State machine for tin droplet lifecycle
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
@@ -22,8 +24,8 @@ pub enum DropletState {
     Debris,
 }
 
-/// Configuration for the droplet generator
-#[derive(Resource, Debug, Clone)]
+/// Configuration for a droplet generator (one nozzle entity per stream)
+#[derive(Component, Debug, Clone)]
 pub struct DropletGeneratorConfig {
     /// Droplet generation frequency (Hz)
     pub frequency: f32,
@@ -63,12 +65,14 @@ impl Default for DropletGeneratorConfig {
 }
 
 /// Tracks timing for droplet generation
-#[derive(Resource, Debug)]
+#[derive(Component, Debug)]
 pub struct DropletGeneratorState {
     /// Accumulated time since last droplet (seconds)
     pub time_accumulator: f32,
     /// Total droplets spawned
     pub droplet_count: u64,
+    /// Disabled generators (clogged or standby nozzles) spawn nothing
+    pub enabled: bool,
 }
 
 impl Default for DropletGeneratorState {
@@ -76,19 +80,38 @@ impl Default for DropletGeneratorState {
         Self {
             time_accumulator: 0.0,
             droplet_count: 0,
+            enabled: true,
         }
     }
 }
 
-/// System that spawns tin droplets at regular intervals
+/// Generator entity that produced a droplet
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DropletStream(pub Entity);
+
+/// System that spawns tin droplets at regular intervals from every enabled generator
 pub fn droplet_generator_system(
     mut commands: Commands,
     time: Res<SimulationTime>,
-    config: Res<DropletGeneratorConfig>,
-    mut state: ResMut<DropletGeneratorState>,
+    mut generators: Query<(Entity, &DropletGeneratorConfig, &mut DropletGeneratorState)>,
 ) {
-    state.time_accumulator += time.delta_seconds;
+    for (generator, config, mut state) in generators.iter_mut() {
+        if !state.enabled {
+            state.time_accumulator = 0.0;
+            continue;
+        }
+        state.time_accumulator += time.delta_seconds;
+        spawn_due_droplets(&mut commands, generator, config, &mut state);
+    }
+}
 
+/// Spawns one droplet for each generator period that has elapsed
+fn spawn_due_droplets(
+    commands: &mut Commands,
+    generator: Entity,
+    config: &DropletGeneratorConfig,
+    state: &mut DropletGeneratorState,
+) {
     // Spawn droplets for each period that has elapsed
     while state.time_accumulator >= config.period {
         state.time_accumulator -= config.period;
@@ -110,6 +133,7 @@ pub fn droplet_generator_system(
             },
             EntityType::TinDroplet,
             ThermalState::new(293.15, 0.001), // Tin at room temp, low heat capacity
+            DropletStream(generator),
         ));
     }
 }
@@ -117,10 +141,22 @@ pub fn droplet_generator_system(
 /// Laser beam component
 #[derive(Component, Debug, Clone)]
 pub struct LaserBeam {
-    /// Laser power in Watts
+    /// Laser power in Watts (pulse energy spread over one repetition period)
     pub power: f32,
+    /// Pulse energy in Joules
+    pub energy: f32,
+    /// Pulse duration (seconds)
+    pub duration: f32,
+    /// Focal spot diameter
+    pub spot_diameter: Distance,
+    /// Propagation direction (normalized)
+    pub direction: Vec3,
     /// Is this the pre-pulse (true) or main pulse (false)?
     pub is_prepulse: bool,
+    /// Index of this pulse within its burst
+    pub burst_pulse: u32,
+    /// Laser channel that fired this pulse
+    pub channel: Entity,
     /// Has this laser fired?
     pub has_fired: bool,
 }
@@ -131,7 +167,9 @@ impl LaserBeam {
 }
 
 /// Laser targeting system - tracks droplets and fires when aligned
-#[derive(Resource, Debug)]
+///
+/// One per laser channel entity; `TargetsStream` on the same entity selects its droplet stream
+#[derive(Component, Debug, Clone)]
 pub struct LaserTargetingSystem {
     /// Target position for laser focus
     pub focal_point: Position3D,
@@ -139,6 +177,14 @@ pub struct LaserTargetingSystem {
     pub sensor_delay: f32,
     /// Laser cooldown timer
     pub cooldown: f32,
+    /// Focal spot diameter of the laser at the focal point
+    pub spot_diameter: Distance,
+    /// Main-pulse duration (seconds)
+    pub pulse_duration: f32,
+    /// Laser propagation direction at the focal point (normalized)
+    pub beam_direction: Vec3,
+    /// Laser firing-time jitter relative to the droplet arrival (seconds, 1σ)
+    pub timing_jitter: f32,
 }
 
 impl Default for LaserTargetingSystem {
@@ -147,41 +193,102 @@ impl Default for LaserTargetingSystem {
             focal_point: Position3D::zero(), // Center of vacuum chamber
             sensor_delay: 0.000_001, // 1 microsecond
             cooldown: 0.0,
+            spot_diameter: Distance::from_micrometers(150),
+            pulse_duration: 50e-9, // 50 ns CO2 pulse
+            beam_direction: Vec3::Z, // Perpendicular to the droplet stream
+            timing_jitter: 10e-9, // 10 ns, ~1 μm along the stream
         }
     }
 }
 
-/// System that fires lasers at droplets when they reach the focal point
+/// Droplet generator a laser channel fires at
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetsStream(pub Entity);
+
+/// System that fires each laser channel at droplets of its stream when they reach its focal point
+///
+/// Each channel runs its own dose controller and burst scheduler
 pub fn laser_targeting_system(
     mut commands: Commands,
     time: Res<SimulationTime>,
-    mut targeting: ResMut<LaserTargetingSystem>,
-    droplets: Query<(Entity, &Position, &DropletState)>,
+    mut lasers: Query<(Entity, &mut LaserTargetingSystem, &TargetsStream, &mut DoseController, &mut BurstScheduler)>,
+    generators: Query<&DropletGeneratorConfig>,
+    droplets: Query<(Entity, &Position, &Velocity, &DropletState, &DropletStream), Without<PulseSkipped>>,
 ) {
-    // Update cooldown
-    if targeting.cooldown > 0.0 {
-        targeting.cooldown -= time.delta_seconds;
-        return;
+    let mut rng = rand::thread_rng();
+
+    for (channel, mut targeting, stream, mut dose, mut burst) in lasers.iter_mut() {
+        // Update cooldown
+        if targeting.cooldown > 0.0 {
+            targeting.cooldown -= time.delta_seconds;
+            continue;
+        }
+
+        // The droplet stream keeps running between bursts; only the laser idles
+        if !burst.is_firing() {
+            continue;
+        }
+
+        let Ok(generator) = generators.get(stream.0) else {
+            continue;
+        };
+        let in_stream = droplets.iter().filter(|(.., droplet_stream)| droplet_stream.0 == stream.0);
+        fire_at_stream(&mut commands, channel, &mut targeting, generator, &mut dose, &mut burst, in_stream, &mut rng);
     }
+}
 
+/// Fires pre-pulses and dose-controlled main pulses at droplets inside the targeting window
+#[allow(clippy::too_many_arguments)]
+fn fire_at_stream<'a>(
+    commands: &mut Commands,
+    channel: Entity,
+    targeting: &mut LaserTargetingSystem,
+    generator: &DropletGeneratorConfig,
+    dose: &mut DoseController,
+    burst: &mut BurstScheduler,
+    droplets: impl Iterator<Item = (Entity, &'a Position, &'a Velocity, &'a DropletState, &'a DropletStream)>,
+    rng: &mut impl Rng,
+) {
     // Find droplets near the focal point
-    for (entity, pos, state) in droplets.iter() {
+    for (entity, pos, velocity, state, _) in droplets {
         let distance_to_focal = pos.0.distance_to(&targeting.focal_point);
         let threshold = Distance::from_millimeters(1); // 1mm targeting window
 
         if distance_to_focal < threshold {
+            // A pulse fired δt late lands where the droplet was δt ago
+            let spot = if targeting.timing_jitter > 0.0 {
+                let delay = Normal::new(0.0, targeting.timing_jitter).unwrap().sample(rng);
+                Position3D::from_vec3(pos.0.to_vec3() - velocity.0 * delay)
+            } else {
+                pos.0
+            };
+
             // Fire appropriate laser based on droplet state
             match state {
                 DropletState::Spherical => {
                     // Fire pre-pulse
-                    spawn_laser_pulse(&mut commands, pos.0, true);
+                    let energy = LaserBeam::PRE_PULSE_POWER * generator.period;
+                    let pulse = burst.pulse_in_burst();
+                    spawn_laser_pulse(commands, channel, targeting, spot, true, energy, generator.period, pulse);
                     targeting.cooldown = 0.000_005; // 5 microseconds between pulses
                 }
-                DropletState::Pancaked => {
-                    // Fire main pulse
-                    spawn_laser_pulse(&mut commands, pos.0, false);
-                    targeting.cooldown = targeting.sensor_delay;
-                }
+                DropletState::Pancaked => match dose.next_pulse_energy() {
+                    // Fire main pulse at the energy requested by the dose controller
+                    Some(energy) => {
+                        let energy = energy * burst.energy_factor();
+                        let pulse = burst.pulse_in_burst();
+                        spawn_laser_pulse(commands, channel, targeting, spot, false, energy, generator.period, pulse);
+                        burst.record_slot(true);
+                        targeting.cooldown = targeting.sensor_delay;
+                    }
+                    // Window already over-dosed: let this droplet pass unfired
+                    None => {
+                        dose.record_skip();
+                        burst.record_slot(false);
+                        commands.entity(entity).insert(PulseSkipped);
+                        targeting.cooldown = targeting.sensor_delay;
+                    }
+                },
                 _ => {}
             }
         }
@@ -189,18 +296,28 @@ pub fn laser_targeting_system(
 }
 
 /// Helper function to spawn a laser pulse entity
-fn spawn_laser_pulse(commands: &mut Commands, target_pos: Position3D, is_prepulse: bool) {
-    let power = if is_prepulse {
-        LaserBeam::PRE_PULSE_POWER
-    } else {
-        LaserBeam::MAIN_PULSE_POWER
-    };
-
+#[allow(clippy::too_many_arguments)]
+fn spawn_laser_pulse(
+    commands: &mut Commands,
+    channel: Entity,
+    targeting: &LaserTargetingSystem,
+    target_pos: Position3D,
+    is_prepulse: bool,
+    energy: f32,
+    repetition_period: f32,
+    burst_pulse: u32,
+) {
     commands.spawn((
         Position(target_pos),
         LaserBeam {
-            power,
+            power: energy / repetition_period,
+            energy,
+            duration: targeting.pulse_duration,
+            spot_diameter: targeting.spot_diameter,
+            direction: targeting.beam_direction,
             is_prepulse,
+            burst_pulse,
+            channel,
             has_fired: false,
         },
         EntityType::LaserBeam,
@@ -233,6 +350,23 @@ impl SimulationTime {
     }
 }
 
+/// Hot-swaps a stream: disables generator `from`, enables `to` and moves every
+/// laser channel targeting `from` over to `to`
+pub fn hot_swap_stream(world: &mut World, from: Entity, to: Entity) {
+    if let Some(mut state) = world.get_mut::<DropletGeneratorState>(from) {
+        state.enabled = false;
+    }
+    if let Some(mut state) = world.get_mut::<DropletGeneratorState>(to) {
+        state.enabled = true;
+    }
+    let mut channels = world.query::<&mut TargetsStream>();
+    for mut stream in channels.iter_mut(world) {
+        if stream.0 == from {
+            stream.0 = to;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -244,6 +378,80 @@ mod tests {
         assert_eq!(config.frequency, 50_000.0);
     }
 
+    #[test]
+    fn test_generators_tag_their_stream() {
+        let mut world = World::new();
+        world.insert_resource(SimulationTime::default());
+        let a = world.spawn((DropletGeneratorConfig::default(), DropletGeneratorState::default())).id();
+        let b = world.spawn((
+            DropletGeneratorConfig::default(),
+            DropletGeneratorState { enabled: false, ..Default::default() },
+        )).id();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(droplet_generator_system);
+        for _ in 0..100 {
+            world.resource_mut::<SimulationTime>().tick(1e-6);
+            schedule.run(&mut world);
+        }
+
+        let streams: Vec<DropletStream> = world.query::<&DropletStream>().iter(&world).copied().collect();
+        assert_eq!(streams.len(), 5); // 100 μs at 50 kHz
+        assert!(streams.iter().all(|s| *s == DropletStream(a)));
+        assert_eq!(world.get::<DropletGeneratorState>(b).unwrap().droplet_count, 0);
+    }
+
+    #[test]
+    fn test_hot_swap_retargets_lasers() {
+        let mut world = World::new();
+        let primary = world.spawn(DropletGeneratorState::default()).id();
+        let standby = world.spawn(DropletGeneratorState { enabled: false, ..Default::default() }).id();
+        let laser = world.spawn((LaserTargetingSystem::default(), TargetsStream(primary))).id();
+
+        hot_swap_stream(&mut world, primary, standby);
+        assert!(!world.get::<DropletGeneratorState>(primary).unwrap().enabled);
+        assert!(world.get::<DropletGeneratorState>(standby).unwrap().enabled);
+        assert_eq!(*world.get::<TargetsStream>(laser).unwrap(), TargetsStream(standby));
+    }
+
+    #[test]
+    fn test_channels_keep_their_own_bursts() {
+        use bevy_ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.insert_resource(SimulationTime::default());
+        let channel = |world: &mut World, firing: bool| {
+            let stream = world.spawn(DropletGeneratorConfig::default()).id();
+            let mut burst = BurstScheduler::new(3, 50e-6);
+            if firing {
+                burst.advance(0.0);
+            }
+            let laser = world.spawn((
+                LaserTargetingSystem::default(),
+                TargetsStream(stream),
+                DoseController::new(4, 1.0e-3, 0.4),
+                burst,
+            )).id();
+            world.spawn((
+                Position(Position3D::zero()),
+                Velocity(Vec3::X * 100.0),
+                DropletState::Pancaked,
+                DropletStream(stream),
+            ));
+            laser
+        };
+        let firing = channel(&mut world, true);
+        let idle = channel(&mut world, false);
+
+        world.run_system_once(laser_targeting_system).unwrap();
+
+        // Only the channel inside a burst fires, and only its scheduler consumes a slot
+        let beams: Vec<Entity> = world.query::<&LaserBeam>().iter(&world).map(|b| b.channel).collect();
+        assert_eq!(beams, vec![firing]);
+        assert_eq!(world.get::<BurstScheduler>(firing).unwrap().pulse_in_burst(), 1);
+        assert_eq!(world.get::<BurstScheduler>(idle).unwrap().pulse_in_burst(), 0);
+    }
+
     #[test]
     fn test_laser_power_levels() {
         assert_eq!(LaserBeam::PRE_PULSE_POWER, 1_000.0);
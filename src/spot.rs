@@ -0,0 +1,248 @@
+//! Geometric image quality: spot diagrams, encircled energy and MTF
+//!
+//! Rays from a reticle field point fill the entrance pupil on a square grid,
+//! each carrying equal energy, and are traced specularly to the wafer plane.
+//! All quantities are geometric, so they ignore diffraction: the MTF is the
+//! magnitude of the Fourier transform of the spot's ray density.
+
+use std::fmt::Write as _;
+use glam::{DVec2, Vec2};
+use crate::projection::ProjectionOptics;
+use crate::raytracing::trace_specular;
+
+/// Ray landing points at the wafer for one field point
+#[derive(Debug, Clone)]
+pub struct SpotDiagram {
+    pub field: Vec2,
+    /// Wafer-plane coordinates of every traced ray (m)
+    pub points: Vec<DVec2>,
+}
+
+impl SpotDiagram {
+    /// Traces a `grid` × `grid` pupil sample of one reticle field point
+    ///
+    /// Returns `None` if no ray reaches the wafer plane.
+    pub fn trace(optics: &ProjectionOptics, field: Vec2, grid: usize) -> Option<Self> {
+        let origin = optics.reticle.point(field).as_dvec3();
+        let wafer_origin = optics.wafer.origin.as_dvec3();
+        let wafer_rotation = optics.wafer.orientation.as_dquat();
+        let normal = optics.wafer.normal().as_dvec3();
+        let points: Vec<DVec2> = ProjectionOptics::pupil_grid(grid)
+            .filter_map(|pupil| {
+                let ray = trace_specular(&optics.mirrors, origin, optics.pupil_point(pupil) - origin)?;
+                let denom = normal.dot(ray.direction);
+                let t = normal.dot(wafer_origin - ray.point) / denom;
+                (denom.abs() > 1e-12 && t >= 0.0).then(|| {
+                    (wafer_rotation.inverse() * (ray.point + ray.direction * t - wafer_origin)).truncate()
+                })
+            })
+            .collect();
+        (!points.is_empty()).then_some(Self { field, points })
+    }
+
+    pub fn centroid(&self) -> DVec2 {
+        self.points.iter().copied().sum::<DVec2>() / self.points.len() as f64
+    }
+
+    /// RMS distance of the rays from the centroid (m)
+    pub fn rms_radius(&self) -> f64 {
+        let centroid = self.centroid();
+        let sum: f64 = self.points.iter().map(|p| p.distance_squared(centroid)).sum();
+        (sum / self.points.len() as f64).sqrt()
+    }
+
+    /// Largest distance of any ray from the centroid (m)
+    pub fn geo_radius(&self) -> f64 {
+        let centroid = self.centroid();
+        self.points.iter().map(|p| p.distance(centroid)).fold(0.0, f64::max)
+    }
+
+    /// Fraction of rays within each radius of the centroid
+    pub fn encircled_energy(&self, radii: &[f64]) -> Vec<f64> {
+        let centroid = self.centroid();
+        let mut distances: Vec<f64> = self.points.iter().map(|p| p.distance(centroid)).collect();
+        distances.sort_by(f64::total_cmp);
+        let n = distances.len() as f64;
+        radii
+            .iter()
+            .map(|r| distances.partition_point(|d| d <= r) as f64 / n)
+            .collect()
+    }
+
+    /// Geometric MTF at spatial frequency `frequency` (cycles/m) along the unit `axis`
+    pub fn mtf(&self, frequency: f64, axis: DVec2) -> f64 {
+        let centroid = self.centroid();
+        let (re, im) = self.points.iter().fold((0.0, 0.0), |(re, im), p| {
+            let phase = std::f64::consts::TAU * frequency * (*p - centroid).dot(axis);
+            (re + phase.cos(), im + phase.sin())
+        });
+        re.hypot(im) / self.points.len() as f64
+    }
+}
+
+/// Per-field summary rows: field, centroid, RMS and GEO radius
+pub fn summary_csv(spots: &[SpotDiagram]) -> String {
+    let mut csv = String::from("field_x_m,field_y_m,centroid_x_m,centroid_y_m,rms_radius_m,geo_radius_m,rays\n");
+    for spot in spots {
+        let c = spot.centroid();
+        let _ = writeln!(
+            csv, "{},{},{:e},{:e},{:e},{:e},{}",
+            spot.field.x, spot.field.y, c.x, c.y, spot.rms_radius(), spot.geo_radius(), spot.points.len()
+        );
+    }
+    csv
+}
+
+/// Every ray's landing point, relative to its spot's centroid
+pub fn points_csv(spots: &[SpotDiagram]) -> String {
+    let mut csv = String::from("field_x_m,field_y_m,dx_m,dy_m\n");
+    for spot in spots {
+        let c = spot.centroid();
+        for p in &spot.points {
+            let _ = writeln!(csv, "{},{},{:e},{:e}", spot.field.x, spot.field.y, p.x - c.x, p.y - c.y);
+        }
+    }
+    csv
+}
+
+/// Encircled energy out to each spot's GEO radius in `steps` radii
+pub fn encircled_energy_csv(spots: &[SpotDiagram], steps: usize) -> String {
+    let mut csv = String::from("field_x_m,field_y_m,radius_m,encircled_fraction\n");
+    for spot in spots {
+        let geo = spot.geo_radius();
+        let radii: Vec<f64> = (0..=steps).map(|i| geo * i as f64 / steps as f64).collect();
+        for (radius, fraction) in radii.iter().zip(spot.encircled_energy(&radii)) {
+            let _ = writeln!(csv, "{},{},{:e},{}", spot.field.x, spot.field.y, radius, fraction);
+        }
+    }
+    csv
+}
+
+/// MTF along wafer x and y from zero to `max_frequency` (cycles/m) in `steps` frequencies
+pub fn mtf_csv(spots: &[SpotDiagram], max_frequency: f64, steps: usize) -> String {
+    let mut csv = String::from("field_x_m,field_y_m,frequency_cycles_per_mm,mtf_x,mtf_y\n");
+    for spot in spots {
+        for i in 0..=steps {
+            let frequency = max_frequency * i as f64 / steps as f64;
+            let _ = writeln!(
+                csv, "{},{},{},{},{}",
+                spot.field.x, spot.field.y, frequency * 1e-3, spot.mtf(frequency, DVec2::X), spot.mtf(frequency, DVec2::Y)
+            );
+        }
+    }
+    csv
+}
+
+/// Spot diagrams side by side on one scale, labelled with RMS and GEO radii
+pub fn render_svg(spots: &[SpotDiagram]) -> String {
+    const CELL: f64 = 220.0;
+    const PLOT_RADIUS: f64 = 90.0;
+    let columns = (spots.len() as f64).sqrt().ceil().max(1.0) as usize;
+    let rows = spots.len().div_ceil(columns).max(1);
+    let largest = spots.iter().map(SpotDiagram::geo_radius).fold(0.0, f64::max);
+    // Meters per pixel, shared by every cell; a point-like spot gets a 1 nm scale
+    let scale = largest.max(1e-9) / PLOT_RADIUS;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="10">"#,
+        columns as f64 * CELL, rows as f64 * CELL + 20.0
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    for (index, spot) in spots.iter().enumerate() {
+        let cx = (index % columns) as f64 * CELL + CELL / 2.0;
+        let cy = (index / columns) as f64 * CELL + CELL / 2.0;
+        let _ = writeln!(
+            svg, r#"<circle cx="{cx}" cy="{cy}" r="{PLOT_RADIUS}" fill="none" stroke="lightgray"/>"#
+        );
+        let centroid = spot.centroid();
+        for p in &spot.points {
+            // Wafer y points up
+            let x = cx + (p.x - centroid.x) / scale;
+            let y = cy - (p.y - centroid.y) / scale;
+            let _ = writeln!(svg, r#"<circle cx="{x:.2}" cy="{y:.2}" r="1" fill="navy"/>"#);
+        }
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}">field ({:.2}, {:.2}) mm</text>"#,
+            cx - CELL / 2.0 + 5.0, cy - CELL / 2.0 + 12.0, spot.field.x * 1e3, spot.field.y * 1e3
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}">RMS {:.3} nm  GEO {:.3} nm</text>"#,
+            cx - CELL / 2.0 + 5.0, cy + CELL / 2.0 - 6.0, spot.rms_radius() * 1e9, spot.geo_radius() * 1e9
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r#"<text x="5" y="{:.1}">circle radius = {:.3} nm</text>"#,
+        rows as f64 * CELL + 14.0, largest.max(1e-9) * 1e9
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projection::fixtures::concave_sphere;
+
+    #[test]
+    fn test_perfect_focus_is_a_point() {
+        let spot = SpotDiagram::trace(&concave_sphere(1.0, 0.5, 1.0), Vec2::ZERO, 32).unwrap();
+        assert!(spot.geo_radius() < 1e-12);
+        assert!((spot.mtf(1e9, DVec2::X) - 1.0).abs() < 1e-9);
+    }
+
+    /// Bessel function of the first kind, order one, by its power series
+    fn bessel_j1(x: f64) -> f64 {
+        let mut term = x / 2.0;
+        let mut sum = term;
+        for k in 1..30 {
+            term *= -(x / 2.0).powi(2) / (k as f64 * (k + 1) as f64);
+            sum += term;
+        }
+        sum
+    }
+
+    #[test]
+    fn test_defocused_spot_is_a_uniform_disk() {
+        // Rays leave focus at up to tan θ = 0.1, so 1 mm of defocus blurs to a ~100 μm disk
+        let spot = SpotDiagram::trace(&concave_sphere(1.0, 0.5, 1.0 + 1e-3), Vec2::ZERO, 64).unwrap();
+        let radius = 1e-3 * (0.1f64).atan().sin() / (0.1f64).atan().cos();
+        assert!(spot.centroid().length() < 1e-9);
+        assert!((spot.geo_radius() - radius).abs() < 0.03 * radius);
+        assert!((spot.rms_radius() - radius / 2f64.sqrt()).abs() < 0.02 * radius);
+
+        // Half the energy of a uniform disk lies within R/√2
+        let fraction = spot.encircled_energy(&[radius / 2f64.sqrt(), 2.0 * radius]);
+        assert!((fraction[0] - 0.5).abs() < 0.02);
+        assert_eq!(fraction[1], 1.0);
+
+        // MTF of a uniform disk: 2 J1(2πfR) / (2πfR)
+        for frequency in [0.2 / radius, 0.5 / radius] {
+            let x = std::f64::consts::TAU * frequency * radius;
+            let expected = (2.0 * bessel_j1(x) / x).abs();
+            assert!((spot.mtf(frequency, DVec2::X) - expected).abs() < 0.02, "{frequency}");
+            assert!((spot.mtf(frequency, DVec2::Y) - expected).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn test_reports() {
+        let spots: Vec<SpotDiagram> = [Vec2::ZERO, Vec2::new(0.01, 0.0)]
+            .into_iter()
+            .filter_map(|field| SpotDiagram::trace(&concave_sphere(1.0, 0.5, 1.0 + 1e-4), field, 8))
+            .collect();
+        assert_eq!(spots.len(), 2);
+        let rays: usize = spots.iter().map(|s| s.points.len()).sum();
+        assert_eq!(summary_csv(&spots).lines().count(), 3);
+        assert_eq!(points_csv(&spots).lines().count(), 1 + rays);
+        assert_eq!(encircled_energy_csv(&spots, 10).lines().count(), 1 + 2 * 11);
+        assert_eq!(mtf_csv(&spots, 1e5, 4).lines().count(), 1 + 2 * 5);
+        let svg = render_svg(&spots);
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches(r#"fill="navy""#).count(), rays);
+    }
+}
@@ -0,0 +1,239 @@
+//! Targeting diagnostics: classifies every laser pulse and every droplet
+//! outcome, and removes unhit droplets at the catch basin
+//!
+//! Quantifies targeting reliability from the event stream
+
+use bevy_ecs::prelude::*;
+use glam::Vec3;
+use crate::components::*;
+use crate::dose::RunningStatistics;
+use crate::source::{DropletGeneratorConfig, DropletState, DropletStream};
+use crate::units::{Distance, Position3D};
+
+/// What happened to a laser pulse or a droplet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetingOutcome {
+    /// Pulse hit a droplet in the expected state
+    Hit,
+    /// Pulse fired before the droplet reached the spot
+    MissEarly,
+    /// Pulse fired after the droplet had passed the spot
+    MissLate,
+    /// Pulse hit a droplet already processed by a pulse of the same type
+    DoubleHit,
+    /// Main pulse hit a droplet the pre-pulse had not shaped
+    Unshaped,
+    /// Pulse fired with no droplet in flight
+    NoTarget,
+    /// Droplet reached the catch basin without producing plasma
+    UnhitDropletEscaped,
+}
+
+impl TargetingOutcome {
+    pub const ALL: [TargetingOutcome; 7] = [
+        TargetingOutcome::Hit,
+        TargetingOutcome::MissEarly,
+        TargetingOutcome::MissLate,
+        TargetingOutcome::DoubleHit,
+        TargetingOutcome::Unshaped,
+        TargetingOutcome::NoTarget,
+        TargetingOutcome::UnhitDropletEscaped,
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            TargetingOutcome::Hit => 0,
+            TargetingOutcome::MissEarly => 1,
+            TargetingOutcome::MissLate => 2,
+            TargetingOutcome::DoubleHit => 3,
+            TargetingOutcome::Unshaped => 4,
+            TargetingOutcome::NoTarget => 5,
+            TargetingOutcome::UnhitDropletEscaped => 6,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TargetingOutcome::Hit => "hit",
+            TargetingOutcome::MissEarly => "miss (early)",
+            TargetingOutcome::MissLate => "miss (late)",
+            TargetingOutcome::DoubleHit => "double hit",
+            TargetingOutcome::Unshaped => "main pulse on unshaped droplet",
+            TargetingOutcome::NoTarget => "no droplet in flight",
+            TargetingOutcome::UnhitDropletEscaped => "unhit droplet escaped",
+        }
+    }
+}
+
+/// Fired once per resolved laser pulse and once per escaped droplet
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TargetingEvent {
+    pub outcome: TargetingOutcome,
+    /// Laser spot position, or the droplet position for escapes
+    pub position: Position3D,
+    /// Droplet offset from the laser spot along the droplet velocity (m); zero for escapes
+    pub offset: f32,
+    /// Pulse type; false for escapes
+    pub is_prepulse: bool,
+}
+
+/// Classifies a laser that found no droplet to hit from the nearest droplet's
+/// offset along its direction of travel
+pub fn classify_miss(spot: Vec3, droplet: Vec3, droplet_velocity: Vec3) -> (TargetingOutcome, f32) {
+    let offset = (droplet - spot).dot(droplet_velocity.normalize_or_zero());
+    if offset > 0.0 {
+        (TargetingOutcome::MissLate, offset)
+    } else {
+        (TargetingOutcome::MissEarly, offset)
+    }
+}
+
+/// Plane across each droplet stream where unhit droplets are caught and removed
+#[derive(Resource, Debug, Clone)]
+pub struct CatchBasin {
+    /// Distance downstream of the generator nozzle
+    pub distance: Distance,
+}
+
+impl Default for CatchBasin {
+    fn default() -> Self {
+        Self {
+            distance: Distance::from_millimeters(100), // 50 mm past the laser focus
+        }
+    }
+}
+
+/// Outcome counters and miss diagnostics
+#[derive(Resource, Debug, Default)]
+pub struct TargetingStatistics {
+    counts: [u64; 7],
+    last_position: [Option<Position3D>; 7],
+    /// Along-stream droplet offset from the spot for missed pulses (m)
+    pub miss_offset: RunningStatistics,
+    /// Misses by pre-pulses (the rest are main pulses)
+    pub prepulse_misses: u64,
+}
+
+impl TargetingStatistics {
+    pub fn record(&mut self, event: &TargetingEvent) {
+        let i = event.outcome.index();
+        self.counts[i] += 1;
+        self.last_position[i] = Some(event.position);
+        if matches!(event.outcome, TargetingOutcome::MissEarly | TargetingOutcome::MissLate) {
+            self.miss_offset.push(event.offset as f64);
+            if event.is_prepulse {
+                self.prepulse_misses += 1;
+            }
+        }
+    }
+
+    pub fn count(&self, outcome: TargetingOutcome) -> u64 {
+        self.counts[outcome.index()]
+    }
+
+    pub fn last_position(&self, outcome: TargetingOutcome) -> Option<Position3D> {
+        self.last_position[outcome.index()]
+    }
+
+    /// Pulses resolved (every outcome but escaped droplets)
+    pub fn pulses(&self) -> u64 {
+        self.counts[..6].iter().sum()
+    }
+
+    /// Fraction of pulses that hit their droplet
+    pub fn hit_rate(&self) -> f64 {
+        let pulses = self.pulses();
+        if pulses > 0 {
+            self.count(TargetingOutcome::Hit) as f64 / pulses as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// System that removes droplets that passed their stream's catch basin without producing plasma
+pub fn catch_basin_system(
+    mut commands: Commands,
+    basin: Res<CatchBasin>,
+    generators: Query<&DropletGeneratorConfig>,
+    droplets: Query<(Entity, &Position, &DropletState, &DropletStream)>,
+    mut events: EventWriter<TargetingEvent>,
+) {
+    let limit = basin.distance.as_meters_f64() as f32;
+
+    for (entity, pos, state, stream) in droplets.iter() {
+        if !matches!(state, DropletState::Spherical | DropletState::Pancaked) {
+            continue;
+        }
+        let Ok(generator) = generators.get(stream.0) else {
+            continue;
+        };
+        let travelled = (pos.0.to_vec3() - generator.spawn_position.to_vec3()).dot(generator.spawn_direction);
+        if travelled > limit {
+            commands.entity(entity).despawn();
+            events.send(TargetingEvent {
+                outcome: TargetingOutcome::UnhitDropletEscaped,
+                position: pos.0,
+                offset: 0.0,
+                is_prepulse: false,
+            });
+        }
+    }
+}
+
+/// System that accumulates targeting outcomes
+pub fn targeting_statistics_system(
+    mut events: EventReader<TargetingEvent>,
+    mut stats: ResMut<TargetingStatistics>,
+) {
+    for event in events.read() {
+        stats.record(event);
+    }
+}
+
+/// System that swaps the targeting event buffers once per tick
+pub fn targeting_event_update_system(mut events: ResMut<Events<TargetingEvent>>) {
+    events.update();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_miss() {
+        let spot = Vec3::ZERO;
+        let velocity = Vec3::X * 100.0;
+        let (outcome, offset) = classify_miss(spot, Vec3::new(50e-6, 0.0, 0.0), velocity);
+        assert_eq!(outcome, TargetingOutcome::MissLate);
+        assert!((offset - 50e-6).abs() < 1e-9);
+
+        let (outcome, _) = classify_miss(spot, Vec3::new(-50e-6, 0.0, 0.0), velocity);
+        assert_eq!(outcome, TargetingOutcome::MissEarly);
+    }
+
+    #[test]
+    fn test_statistics_counts() {
+        let mut stats = TargetingStatistics::default();
+        let event = |outcome, offset| TargetingEvent {
+            outcome,
+            position: Position3D::zero(),
+            offset,
+            is_prepulse: false,
+        };
+        for _ in 0..8 {
+            stats.record(&event(TargetingOutcome::Hit, 0.0));
+        }
+        stats.record(&event(TargetingOutcome::MissLate, 40e-6));
+        stats.record(&event(TargetingOutcome::DoubleHit, 0.0));
+        stats.record(&event(TargetingOutcome::NoTarget, 0.0));
+        stats.record(&event(TargetingOutcome::Unshaped, 0.0));
+        stats.record(&event(TargetingOutcome::UnhitDropletEscaped, 0.0));
+
+        assert_eq!(stats.pulses(), 12);
+        assert!((stats.hit_rate() - 8.0 / 12.0).abs() < 1e-12);
+        // Pulses without a droplet to miss carry no timing offset
+        assert_eq!(stats.miss_offset.count, 1);
+        assert!(stats.last_position(TargetingOutcome::MissEarly).is_none());
+    }
+}
@@ -164,6 +164,20 @@ impl Position3D {
             z: Distance::from_meters_f64(v.z as f64),
         }
     }
+
+    /// Double-precision meters, keeping sub-nanometer resolution far from the origin
+    pub fn to_dvec3(&self) -> glam::DVec3 {
+        glam::DVec3::new(self.x.as_meters_f64(), self.y.as_meters_f64(), self.z.as_meters_f64())
+    }
+
+    pub fn from_dvec3(v: glam::DVec3) -> Self {
+        Self {
+            x: Distance::from_meters_f64(v.x),
+            y: Distance::from_meters_f64(v.y),
+            z: Distance::from_meters_f64(v.z),
+        }
+    }
+
     pub fn distance_to(&self, other: &Position3D) -> Distance {
         let dx = (self.x.as_meters_f64() - other.x.as_meters_f64()).powi(2);
         let dy = (self.y.as_meters_f64() - other.y.as_meters_f64()).powi(2);
@@ -0,0 +1,200 @@
+//! Wavefront analysis at the exit pupil
+//!
+//! Rays from one reticle field point fill the entrance pupil on a square grid
+//! and are traced in double precision through the mirror train. Each ray's
+//! optical path is measured to a reference sphere centred on the chief ray's
+//! image point and passing through the chief ray's last mirror hit; the
+//! difference from the chief ray's path is the wavefront error (positive for a
+//! longer path), which is then decomposed into Zernike terms.
+
+use glam::{DVec2, DVec3, Vec2};
+use nalgebra::{DMatrix, DVector};
+use crate::projection::ProjectionOptics;
+use crate::raytracing::trace_specular;
+use crate::zernike;
+
+/// Sampling and decomposition settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WavefrontConfig {
+    /// Rays across the pupil diameter; only those inside the disk are traced
+    pub grid: usize,
+    /// Noll-ordered Zernike terms to fit, from piston
+    pub zernike_terms: u32,
+    /// Wavelength for reporting in waves (m)
+    pub wavelength: f64,
+}
+
+impl Default for WavefrontConfig {
+    fn default() -> Self {
+        Self {
+            grid: 32,
+            zernike_terms: 37,
+            wavelength: 13.5e-9,
+        }
+    }
+}
+
+/// Optical path difference over the pupil for one field point
+#[derive(Debug, Clone)]
+pub struct Wavefront {
+    pub field: Vec2,
+    /// Normalised entrance-pupil coordinates of each traced ray and its OPD (m)
+    pub samples: Vec<(DVec2, f64)>,
+    /// Coefficients (m) of Noll-ordered, RMS-normalised Zernike terms from j = 1
+    pub zernike: Vec<f64>,
+    pub wavelength: f64,
+}
+
+impl Wavefront {
+    /// RMS wavefront error over the pupil with piston removed (m)
+    pub fn rms(&self) -> f64 {
+        let n = self.samples.len() as f64;
+        let mean = self.samples.iter().map(|(_, opd)| opd).sum::<f64>() / n;
+        (self.samples.iter().map(|(_, opd)| (opd - mean).powi(2)).sum::<f64>() / n).sqrt()
+    }
+
+    /// RMS wavefront error in thousandths of a wave
+    pub fn rms_milliwaves(&self) -> f64 {
+        self.rms() / self.wavelength * 1e3
+    }
+
+    /// Peak-to-valley wavefront error (m)
+    pub fn peak_to_valley(&self) -> f64 {
+        let (low, high) = self.samples.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), (_, opd)| {
+            (low.min(*opd), high.max(*opd))
+        });
+        high - low
+    }
+
+    /// Zernike coefficient of Noll term `j` in thousandths of a wave; zero for
+    /// terms past the fit
+    pub fn zernike_milliwaves(&self, j: u32) -> f64 {
+        assert!(j >= 1, "Noll indices start at 1");
+        self.zernike.get(j as usize - 1).map_or(0.0, |c| c / self.wavelength * 1e3)
+    }
+}
+
+/// Traces the pupil of one reticle field point and fits its wavefront
+///
+/// Returns `None` if the chief ray does not reach the image plane or fewer
+/// rays than Zernike terms make it through the system.
+pub fn analyze_wavefront(optics: &ProjectionOptics, field: Vec2, config: &WavefrontConfig) -> Option<Wavefront> {
+    let origin = optics.reticle.point(field).as_dvec3();
+    let trace = |target: DVec3| trace_specular(&optics.mirrors, origin, target - origin);
+
+    // Reference sphere: centred on the chief ray's image, through its last hit
+    let chief = trace(optics.entrance_pupil.as_dvec3())?;
+    let normal = optics.wafer.normal().as_dvec3();
+    let denom = normal.dot(chief.direction);
+    if denom.abs() < 1e-12 {
+        return None;
+    }
+    let to_image = normal.dot(optics.wafer.origin.as_dvec3() - chief.point) / denom;
+    if to_image < 0.0 {
+        return None;
+    }
+    let image_point = chief.point + chief.direction * to_image;
+    let reference_radius = chief.point.distance(image_point);
+
+    let mut samples = Vec::new();
+    for pupil in ProjectionOptics::pupil_grid(config.grid) {
+        let Some(ray) = trace(optics.pupil_point(pupil)) else { continue };
+
+        // Distance along the ray to the reference sphere, taking the crossing nearest the last hit
+        let offset = ray.point - image_point;
+        let b = ray.direction.dot(offset);
+        let c = offset.length_squared() - reference_radius * reference_radius;
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            continue;
+        }
+        let root = discriminant.sqrt();
+        let (t1, t2) = (-b - root, -b + root);
+        let t = if t1.abs() < t2.abs() { t1 } else { t2 };
+        samples.push((pupil, ray.path_length + t - chief.path_length));
+    }
+
+    let terms = config.zernike_terms.max(1) as usize;
+    if samples.len() < terms {
+        return None;
+    }
+    let basis = DMatrix::from_fn(samples.len(), terms, |row, column| {
+        let (pupil, _) = samples[row];
+        zernike::zernike(column as u32 + 1, pupil.x, pupil.y).0
+    });
+    let opd = DVector::from_iterator(samples.len(), samples.iter().map(|(_, opd)| *opd));
+    let coefficients = basis.svd(true, true).solve(&opd, 1e-12).ok()?;
+
+    Some(Wavefront {
+        field,
+        samples,
+        zernike: coefficients.iter().copied().collect(),
+        wavelength: config.wavelength,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Quat, Vec3};
+    use crate::units::Position3D;
+    use crate::optics::{Aperture, MirrorSurface, SurfaceGeometry};
+    use crate::projection::FieldPlane;
+    use crate::projection::fixtures::concave_sphere;
+
+    #[test]
+    fn test_perfect_imaging_has_no_wavefront_error() {
+        let wavefront = analyze_wavefront(&concave_sphere(1.0, 0.5, 1.0), Vec2::ZERO, &WavefrontConfig::default()).unwrap();
+        assert!(wavefront.samples.len() > 700);
+        assert!(wavefront.rms_milliwaves() < 0.01, "{} mλ", wavefront.rms_milliwaves());
+
+        // Ellipsoidal mirror imaging one focus onto the other
+        let focus1 = Position3D::zero();
+        let focus2 = Position3D::from_vec3(Vec3::new(0.0, 0.0, 0.8));
+        let geometry = SurfaceGeometry::ellipsoid_from_foci(focus1, focus2, 0.6).unwrap();
+        let optics = ProjectionOptics {
+            reticle: FieldPlane::new(Vec3::ZERO, Quat::IDENTITY),
+            wafer: FieldPlane::new(Vec3::new(0.0, 0.0, 0.8), Quat::IDENTITY),
+            entrance_pupil: Vec3::new(0.0, 0.0, -0.1),
+            entrance_pupil_radius: 0.02,
+            mirrors: vec![(Position3D::zero(), MirrorSurface {
+                geometry,
+                orientation: Quat::IDENTITY,
+                aperture: Aperture::Circular { radius: 1.0 },
+            })],
+        };
+        let wavefront = analyze_wavefront(&optics, Vec2::ZERO, &WavefrontConfig::default()).unwrap();
+        assert!(wavefront.rms_milliwaves() < 0.01, "{} mλ", wavefront.rms_milliwaves());
+    }
+
+    #[test]
+    fn test_defocus_appears_as_zernike_defocus() {
+        // Image plane about 1 μm beyond the true focus, exactly representable in f32
+        let shift = 1.0 / 1048576.0;
+        let config = WavefrontConfig::default();
+        let wavefront = analyze_wavefront(&concave_sphere(1.0, 0.5, 1.0 + shift as f32), Vec2::ZERO, &config).unwrap();
+
+        // Path difference at the pupil edge: δ(1 - cos θ), with tan θ = 0.05 / 0.5
+        let edge = shift * (1.0 - (0.1f64).atan().cos());
+        let expected_defocus = edge / (2.0 * 3f64.sqrt());
+        let defocus = wavefront.zernike[3];
+        assert!((defocus.abs() - expected_defocus).abs() < 0.03 * expected_defocus, "{defocus} vs {expected_defocus}");
+        // Rotationally symmetric: no tilt, astigmatism or coma
+        for j in [2, 3, 5, 6, 7, 8] {
+            assert!(wavefront.zernike_milliwaves(j).abs() < 1e-3 * wavefront.zernike_milliwaves(4).abs() + 1e-3);
+        }
+        assert!((wavefront.rms() - defocus.abs()).abs() < 0.05 * defocus.abs());
+    }
+
+    #[test]
+    fn test_off_axis_field_adds_astigmatism() {
+        // Off-axis object on a sphere: the image is no longer stigmatic
+        let optics = concave_sphere(1.0, 0.5, 1.0);
+        let config = WavefrontConfig { grid: 24, zernike_terms: 15, ..Default::default() };
+        let on_axis = analyze_wavefront(&optics, Vec2::ZERO, &config).unwrap();
+        let off_axis = analyze_wavefront(&optics, Vec2::new(0.05, 0.0), &config).unwrap();
+        assert!(off_axis.rms() > 100.0 * on_axis.rms().max(1e-15));
+        let astigmatism = off_axis.zernike_milliwaves(5).hypot(off_axis.zernike_milliwaves(6));
+        assert!(astigmatism > 0.0);
+    }
+}
@@ -0,0 +1,118 @@
+//! Zernike circle polynomials over the unit disk
+//!
+//! Terms follow Noll's single-index ordering (j = 1 is piston) and are
+//! normalised to unit RMS over the disk, matching the "standard" Zernike
+//! convention used by optical design programs.
+
+/// Radial order n and signed azimuthal frequency m of the Noll index `j` (from 1)
+///
+/// m > 0 selects a cos(mθ) term, m < 0 a sin(|m|θ) term.
+pub fn noll_to_nm(j: u32) -> (u32, i32) {
+    assert!(j >= 1, "Noll indices start at 1");
+    let mut n = 0u32;
+    let mut remainder = j - 1;
+    while remainder > n {
+        n += 1;
+        remainder -= n;
+    }
+    let magnitude = (n % 2 + 2 * ((remainder + (n + 1) % 2) / 2)) as i32;
+    // Plain modulo keeps older toolchains (before `is_multiple_of`, Rust 1.87) building
+    #[allow(clippy::manual_is_multiple_of)]
+    let m = if j % 2 == 0 { magnitude } else { -magnitude };
+    (n, m)
+}
+
+fn factorial(k: u32) -> f64 {
+    (1..=k).map(f64::from).product()
+}
+
+/// Radial polynomial R_n^|m| at `rho`: value, derivative, and value divided by rho
+///
+/// The quotient is only meaningful for m ≠ 0, where every power of rho is at
+/// least one and it stays finite at the centre.
+fn radial(n: u32, m: u32, rho: f64) -> (f64, f64, f64) {
+    let mut value = 0.0;
+    let mut derivative = 0.0;
+    let mut over_rho = 0.0;
+    for k in 0..=(n - m) / 2 {
+        let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+        let coefficient = sign * factorial(n - k)
+            / (factorial(k) * factorial((n + m) / 2 - k) * factorial((n - m) / 2 - k));
+        let power = (n - 2 * k) as i32;
+        value += coefficient * rho.powi(power);
+        if power > 0 {
+            derivative += coefficient * power as f64 * rho.powi(power - 1);
+            over_rho += coefficient * rho.powi(power - 1);
+        }
+    }
+    (value, derivative, over_rho)
+}
+
+/// Value and Cartesian gradient of Noll term `j` at the normalised point (x, y)
+pub fn zernike(j: u32, x: f64, y: f64) -> (f64, [f64; 2]) {
+    let (n, m) = noll_to_nm(j);
+    let order = m.unsigned_abs();
+    let normalisation = if m == 0 {
+        f64::from(n + 1).sqrt()
+    } else {
+        (2.0 * f64::from(n + 1)).sqrt()
+    };
+
+    let rho = x.hypot(y);
+    let theta = y.atan2(x);
+    let (cos_t, sin_t) = (theta.cos(), theta.sin());
+    let (value, derivative, over_rho) = radial(n, order, rho);
+
+    // Angular factor and its derivative with respect to theta
+    let angle = f64::from(order) * theta;
+    let (angular, angular_derivative) = match m {
+        0 => (1.0, 0.0),
+        m if m > 0 => (angle.cos(), -f64::from(order) * angle.sin()),
+        _ => (angle.sin(), f64::from(order) * angle.cos()),
+    };
+
+    let gradient_x = derivative * angular * cos_t - over_rho * angular_derivative * sin_t;
+    let gradient_y = derivative * angular * sin_t + over_rho * angular_derivative * cos_t;
+    (
+        normalisation * value * angular,
+        [normalisation * gradient_x, normalisation * gradient_y],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noll_ordering() {
+        let expected = [(0, 0), (1, 1), (1, -1), (2, 0), (2, -2), (2, 2), (3, -1), (3, 1), (3, -3), (3, 3), (4, 0)];
+        for (j, nm) in (1..).zip(expected) {
+            assert_eq!(noll_to_nm(j), nm, "Noll index {j}");
+        }
+    }
+
+    #[test]
+    fn test_known_terms() {
+        let (x, y) = (0.3, -0.4);
+        let rho2 = x * x + y * y;
+        // Defocus √3 (2ρ² - 1) and vertical coma √8 (3ρ³ - 2ρ) sin θ
+        assert!((zernike(4, x, y).0 - 3f64.sqrt() * (2.0 * rho2 - 1.0)).abs() < 1e-12);
+        assert!((zernike(7, x, y).0 - 8f64.sqrt() * (3.0 * rho2 - 2.0) * y).abs() < 1e-12);
+        // Tilt has a constant gradient, including at the centre
+        let (_, gradient) = zernike(2, 0.0, 0.0);
+        assert!((gradient[0] - 2.0).abs() < 1e-12 && gradient[1].abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_gradient_matches_finite_difference() {
+        let h = 1e-6;
+        for j in 1..=28 {
+            for &(x, y) in &[(0.2, 0.1), (-0.5, 0.6), (0.0, -0.7)] {
+                let (_, gradient) = zernike(j, x, y);
+                let dx = (zernike(j, x + h, y).0 - zernike(j, x - h, y).0) / (2.0 * h);
+                let dy = (zernike(j, x, y + h).0 - zernike(j, x, y - h).0) / (2.0 * h);
+                assert!((gradient[0] - dx).abs() < 1e-6 && (gradient[1] - dy).abs() < 1e-6, "term {j}");
+            }
+        }
+    }
+}